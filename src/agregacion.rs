@@ -0,0 +1,260 @@
+use crate::errors::{ErrorPrograma, ErrorTipo};
+use crate::mensajes::Mensaje;
+use crate::plan::FilasTabla;
+use crate::query_identifier::Select;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FuncionAgregada {
+    Count,
+    Sum,
+    Min,
+    Max,
+    Avg,
+}
+
+impl FuncionAgregada {
+    fn desde_str(nombre: &str) -> Option<Self> {
+        match nombre {
+            "COUNT" => Some(FuncionAgregada::Count),
+            "SUM" => Some(FuncionAgregada::Sum),
+            "MIN" => Some(FuncionAgregada::Min),
+            "MAX" => Some(FuncionAgregada::Max),
+            "AVG" => Some(FuncionAgregada::Avg),
+            _ => None,
+        }
+    }
+
+    pub fn como_str(&self) -> &str {
+        match self {
+            FuncionAgregada::Count => "COUNT",
+            FuncionAgregada::Sum => "SUM",
+            FuncionAgregada::Min => "MIN",
+            FuncionAgregada::Max => "MAX",
+            FuncionAgregada::Avg => "AVG",
+        }
+    }
+}
+
+/*
+ * Función de agregación pedida en el SELECT o en el ORDER BY, como COUNT(*) o SUM(monto). El nombre es su
+ * columna en el resultado; COUNT(*) es la única que no tiene columna.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Agregado {
+    pub funcion: FuncionAgregada,
+    pub columna: Option<String>,
+    pub nombre: String,
+}
+
+impl Agregado {
+    pub fn new(funcion: FuncionAgregada, columna: Option<String>) -> Self {
+        let nombre = format!(
+            "{}({})",
+            funcion.como_str(),
+            columna.as_deref().unwrap_or("*")
+        );
+        Agregado {
+            funcion,
+            columna,
+            nombre,
+        }
+    }
+
+    /*
+     * Interpreta una columna del SELECT o del ORDER BY. Devuelve None si no es una función de agregación.
+     */
+    pub fn desde_texto(texto: &str) -> Result<Option<Self>, ErrorPrograma> {
+        let Some((funcion, argumento)) = texto.split_once('(') else {
+            return Ok(None);
+        };
+        let Some(funcion) = FuncionAgregada::desde_str(funcion.trim()) else {
+            return Ok(None);
+        };
+        let argumento = argumento
+            .strip_suffix(')')
+            .map(|argumento| argumento.trim())
+            .filter(|argumento| !argumento.is_empty() && !argumento.contains(['(', ')', ' ']));
+        let columna = match argumento {
+            Some("*") if funcion == FuncionAgregada::Count => None,
+            Some(columna) if columna != "*" => Some(columna.to_string()),
+            _ => {
+                return Err(ErrorPrograma::desde_mensaje(
+                    ErrorTipo::Syntax,
+                    Mensaje::AgregacionInvalida,
+                    &[texto],
+                )
+                .con_token(texto))
+            }
+        };
+        Ok(Some(Agregado::new(funcion, columna)))
+    }
+}
+
+/*
+ * Devuelve las funciones de agregación del SELECT y las del ORDER BY que no están en el SELECT, sin repetir.
+ */
+pub(crate) fn agregados_de_select(select: &Select) -> Result<Vec<Agregado>, ErrorPrograma> {
+    let columnas_orden = select.order_by.iter().flatten().map(|clave| &clave.columna);
+    let mut agregados: Vec<Agregado> = Vec::new();
+    for columna in select.columnas.iter().chain(columnas_orden) {
+        if let Some(agregado) = Agregado::desde_texto(columna)? {
+            if !agregados.contains(&agregado) {
+                agregados.push(agregado);
+            }
+        }
+    }
+    Ok(agregados)
+}
+
+/*
+ * Compara dos valores como números si los dos son enteros, y si no como texto.
+ */
+fn comparar_valores(a: &str, b: &str) -> Ordering {
+    match (a.parse::<i64>(), b.parse::<i64>()) {
+        (Ok(a), Ok(b)) => a.cmp(&b),
+        _ => a.cmp(b),
+    }
+}
+
+/*
+ * Resultado parcial de una función de agregación sobre las filas de un grupo. Los valores vacíos no se cuentan,
+ * salvo en COUNT(*).
+ */
+#[derive(Clone, Default)]
+struct Acumulado {
+    cantidad: u64,
+    suma: i128,
+    extremo: Option<String>,
+}
+
+impl Acumulado {
+    fn agregar(
+        &mut self,
+        agregado: &Agregado,
+        valor: Option<&String>,
+    ) -> Result<(), ErrorPrograma> {
+        let valor = match (agregado.columna.as_ref(), valor) {
+            (None, _) => {
+                self.cantidad += 1;
+                return Ok(());
+            }
+            (Some(_), Some(valor)) if !valor.is_empty() => valor,
+            _ => return Ok(()),
+        };
+        self.cantidad += 1;
+        match agregado.funcion {
+            FuncionAgregada::Count => {}
+            FuncionAgregada::Sum | FuncionAgregada::Avg => {
+                let numero = valor.parse::<i64>().map_err(|_| {
+                    let columna = agregado.columna.clone().unwrap_or_default();
+                    ErrorPrograma::desde_mensaje(
+                        ErrorTipo::TypeMismatch {
+                            columna: Some(columna.clone()),
+                            valor: valor.clone(),
+                        },
+                        Mensaje::AgregacionNoEntera,
+                        &[valor, &columna, agregado.funcion.como_str()],
+                    )
+                })?;
+                self.suma += numero as i128;
+            }
+            FuncionAgregada::Min | FuncionAgregada::Max => {
+                let buscado = match agregado.funcion {
+                    FuncionAgregada::Min => Ordering::Less,
+                    _ => Ordering::Greater,
+                };
+                if self
+                    .extremo
+                    .as_ref()
+                    .is_none_or(|extremo| comparar_valores(valor, extremo) == buscado)
+                {
+                    self.extremo = Some(valor.clone());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Valor final de la función. Sin valores SUM, MIN, MAX y AVG quedan vacíos.
+    fn resultado(&self, funcion: FuncionAgregada) -> String {
+        match funcion {
+            FuncionAgregada::Count => self.cantidad.to_string(),
+            _ if self.cantidad == 0 => String::new(),
+            FuncionAgregada::Sum => self.suma.to_string(),
+            FuncionAgregada::Avg if self.suma % self.cantidad as i128 == 0 => {
+                (self.suma / self.cantidad as i128).to_string()
+            }
+            FuncionAgregada::Avg => (self.suma as f64 / self.cantidad as f64).to_string(),
+            FuncionAgregada::Min | FuncionAgregada::Max => self.extremo.clone().unwrap_or_default(),
+        }
+    }
+}
+
+/*
+ * Agrupa las filas de la entrada por los valores de las columnas en las posiciones `grupos` y devuelve una fila
+ * por grupo, con esos valores y el resultado de cada función, en el orden en que aparece cada grupo. Sin GROUP BY
+ * todas las filas forman un solo grupo, que da una fila aunque la entrada no tenga filas. Las filas se agrupan
+ * la primera vez que se pide una.
+ */
+pub(crate) fn agregar_filas<'a>(
+    entrada: FilasTabla<'a>,
+    grupos: Option<Vec<usize>>,
+    agregados: Vec<(Agregado, Option<usize>)>,
+) -> FilasTabla<'a> {
+    let mut entrada = Some(entrada);
+    let mut resultado: Box<dyn Iterator<Item = Vec<String>> + 'a> = Box::new(std::iter::empty());
+    Box::new(std::iter::from_fn(move || {
+        if let Some(entrada) = entrada.take() {
+            match agrupar(entrada, grupos.as_deref(), &agregados) {
+                Ok(filas) => resultado = Box::new(filas.into_iter()),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        resultado.next().map(Ok)
+    }))
+}
+
+fn agrupar(
+    entrada: FilasTabla,
+    grupos: Option<&[usize]>,
+    agregados: &[(Agregado, Option<usize>)],
+) -> Result<Vec<Vec<String>>, ErrorPrograma> {
+    let mut posiciones: HashMap<Vec<String>, usize> = HashMap::new();
+    let mut acumulados: Vec<(Vec<String>, Vec<Acumulado>)> = Vec::new();
+    if grupos.is_none() {
+        acumulados.push((Vec::new(), vec![Acumulado::default(); agregados.len()]));
+    }
+    for fila in entrada {
+        let fila = fila?;
+        let clave: Vec<String> = grupos
+            .unwrap_or_default()
+            .iter()
+            .map(|posicion| fila.get(*posicion).cloned().unwrap_or_default())
+            .collect();
+        let posicion = match posiciones.get(&clave) {
+            Some(posicion) => *posicion,
+            None if grupos.is_none() => 0,
+            None => {
+                posiciones.insert(clave.clone(), acumulados.len());
+                acumulados.push((clave, vec![Acumulado::default(); agregados.len()]));
+                acumulados.len() - 1
+            }
+        };
+        for ((agregado, columna), acumulado) in
+            agregados.iter().zip(acumulados[posicion].1.iter_mut())
+        {
+            acumulado.agregar(agregado, columna.and_then(|columna| fila.get(columna)))?;
+        }
+    }
+    Ok(acumulados
+        .into_iter()
+        .map(|(mut fila, acumulados)| {
+            for ((agregado, _), acumulado) in agregados.iter().zip(acumulados.iter()) {
+                fila.push(acumulado.resultado(agregado.funcion));
+            }
+            fila
+        })
+        .collect())
+}
@@ -0,0 +1,120 @@
+use std::env;
+use std::error::Error;
+use std::fs::{remove_file, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+static CONTADOR_TEMPORALES: AtomicU64 = AtomicU64::new(0);
+
+/*
+ * Archivo temporal creado en el mismo directorio que la tabla que va a reemplazar, así el rename final
+ * nunca cruza de sistema de archivos, o en el de temporales del sistema si no la reemplaza. Si no se llega a preparar, el archivo se borra al salir de scope,
+ * por lo que cualquier error en el medio de la escritura no deja archivos a medio escribir.
+ */
+pub struct ArchivoTemporal {
+    ruta: PathBuf,
+    writer: BufWriter<File>,
+    preparado: bool,
+}
+
+impl ArchivoTemporal {
+    /*
+     * Crea un archivo temporal con nombre único al lado del archivo recibido,
+     * con la forma .<archivo>.<pid>.<contador>.<nanos>.tmp
+     */
+    pub fn crear_junto_a(ruta_destino: &str) -> Result<Self, Box<dyn Error>> {
+        let destino = Path::new(ruta_destino);
+        let directorio = match destino.parent() {
+            Some(directorio) if !directorio.as_os_str().is_empty() => directorio,
+            _ => Path::new("."),
+        };
+        Self::crear_en(directorio, destino)
+    }
+
+    /*
+     * Crea un archivo temporal con el mismo nombre que crear_junto_a, pero en el directorio de temporales
+     * del sistema. Sirve para los archivos que nunca reemplazan a la tabla, así un proceso que termina sin
+     * borrarlos no los deja en el directorio de datos.
+     */
+    pub(crate) fn crear_en_directorio_temporal(ruta_destino: &str) -> Result<Self, Box<dyn Error>> {
+        Self::crear_en(&env::temp_dir(), Path::new(ruta_destino))
+    }
+
+    fn crear_en(directorio: &Path, destino: &Path) -> Result<Self, Box<dyn Error>> {
+        let nombre = destino
+            .file_name()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        loop {
+            let nanos = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.subsec_nanos())
+                .unwrap_or(0);
+            let ruta = directorio.join(format!(
+                ".{}.{}.{}.{}.tmp",
+                nombre,
+                process::id(),
+                CONTADOR_TEMPORALES.fetch_add(1, Ordering::Relaxed),
+                nanos
+            ));
+            match OpenOptions::new().write(true).create_new(true).open(&ruta) {
+                Ok(archivo) => {
+                    return Ok(ArchivoTemporal {
+                        ruta,
+                        writer: BufWriter::new(archivo),
+                        preparado: false,
+                    })
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(Box::new(e)),
+            }
+        }
+    }
+
+    pub fn ruta(&self) -> &Path {
+        &self.ruta
+    }
+
+    /*
+     * Baja a disco el contenido del temporal sin renombrarlo y devuelve su ruta. Desde este momento
+     * el temporal ya no se borra al salir de scope, queda a cargo de la bitácora que lo registra.
+     */
+    pub fn preparar(mut self) -> Result<PathBuf, Box<dyn Error>> {
+        self.writer.flush()?;
+        self.writer.get_ref().sync_all()?;
+        self.preparado = true;
+        Ok(self.ruta.clone())
+    }
+
+    /*
+     * Cierra el temporal sin bajarlo a disco y devuelve su ruta, para volver a leerlo en el mismo proceso.
+     * Desde este momento el temporal ya no se borra al salir de scope, lo tiene que borrar quien lo recibe.
+     */
+    pub(crate) fn cerrar(mut self) -> io::Result<PathBuf> {
+        self.writer.flush()?;
+        self.preparado = true;
+        Ok(self.ruta.clone())
+    }
+}
+
+impl Write for ArchivoTemporal {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writer.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+impl Drop for ArchivoTemporal {
+    fn drop(&mut self) {
+        if !self.preparado {
+            let _ = remove_file(&self.ruta);
+        }
+    }
+}
@@ -0,0 +1,436 @@
+use crate::archivo_temporal::ArchivoTemporal;
+use crate::bloqueo::BloqueoTabla;
+use crate::errors::{ErrorPrograma, ErrorTipo};
+use crate::mensajes::Mensaje;
+use crate::salida::DestinoFilas;
+use std::error::Error;
+use std::fs::{read_dir, remove_file, rename, File, OpenOptions, TryLockError};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{absolute, Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static CONTADOR_BITACORAS: AtomicU64 = AtomicU64::new(0);
+
+const PREFIJO_BITACORA: &str = ".bitacora.";
+const CONFIRMADA: &str = "CONFIRMADA";
+
+// Nombre de la tabla, cantidad de filas y el primer problema encontrado en ella.
+pub type EstadoTabla = (String, usize, Option<String>);
+
+/*
+ * Cambio que una operación va a hacer sobre un archivo de la tabla:
+ * agregar líneas al final de un archivo que tenía cierta longitud, o renombrar un temporal sobre el archivo.
+ */
+#[derive(Debug)]
+enum Entrada {
+    Agregado(PathBuf, u64),
+    Reemplazo(PathBuf, PathBuf),
+}
+
+/*
+ * Bitácora de escritura anticipada de una operación. Antes de tocar una tabla se registra en un archivo
+ * .bitacora.<pid>.<contador> del directorio lo que se va a hacer, y la bitácora queda bloqueada mientras dure
+ * la operación. Si el proceso muere en el medio, la próxima consulta sobre el directorio encuentra la bitácora
+ * sin bloquear y la deshace, o la rehace si ya estaba confirmada.
+ */
+pub struct Bitacora {
+    ruta: PathBuf,
+    archivo: File,
+    entradas: Vec<Entrada>,
+    terminada: bool,
+    // Tablas que se mantienen bloqueadas hasta terminar la operación.
+    bloqueos: Vec<BloqueoTabla>,
+}
+
+impl Entrada {
+    fn serializar(&self) -> String {
+        match self {
+            Entrada::Agregado(ruta, longitud) => {
+                format!("AGREGADO\t{}\t{}\n", longitud, ruta.to_string_lossy())
+            }
+            Entrada::Reemplazo(temporal, destino) => format!(
+                "REEMPLAZO\t{}\t{}\n",
+                temporal.to_string_lossy(),
+                destino.to_string_lossy()
+            ),
+        }
+    }
+
+    fn parsear(linea: &str) -> Option<Self> {
+        let campos: Vec<&str> = linea.split('\t').collect();
+        match campos.as_slice() {
+            ["AGREGADO", longitud, ruta] => Some(Entrada::Agregado(
+                PathBuf::from(ruta),
+                longitud.parse::<u64>().ok()?,
+            )),
+            ["REEMPLAZO", temporal, destino] => Some(Entrada::Reemplazo(
+                PathBuf::from(temporal),
+                PathBuf::from(destino),
+            )),
+            _ => None,
+        }
+    }
+
+    // Vuelve el archivo al estado anterior a la operación.
+    fn deshacer(&self) -> Result<(), Box<dyn Error>> {
+        match self {
+            Entrada::Agregado(ruta, longitud) => {
+                if let Ok(archivo) = OpenOptions::new().write(true).open(ruta) {
+                    if archivo.metadata()?.len() > *longitud {
+                        archivo.set_len(*longitud)?;
+                        archivo.sync_all()?;
+                    }
+                }
+            }
+            Entrada::Reemplazo(temporal, _) => {
+                let _ = remove_file(temporal);
+            }
+        }
+        Ok(())
+    }
+
+    // Termina de aplicar el cambio de una operación confirmada, si no se llegó a aplicar.
+    fn rehacer(&self) -> Result<(), Box<dyn Error>> {
+        if let Entrada::Reemplazo(temporal, destino) = self {
+            if temporal.exists() {
+                rename(temporal, destino)?;
+                sincronizar_directorio(destino);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn sincronizar_directorio(ruta_archivo: &Path) {
+    if let Some(directorio) = ruta_archivo.parent() {
+        if let Ok(directorio) = File::open(directorio) {
+            let _ = directorio.sync_all();
+        }
+    }
+}
+
+fn directorio_o_actual(directorio: &str) -> &str {
+    if directorio.is_empty() {
+        "."
+    } else {
+        directorio
+    }
+}
+
+impl Bitacora {
+    /*
+     * Crea y bloquea una bitácora nueva en el directorio de tablas. Se crea con otro nombre y se renombra
+     * una vez bloqueada, así la recuperación de otro proceso nunca la encuentra sin su bloqueo.
+     */
+    pub fn iniciar(directorio: &str) -> Result<Self, Box<dyn Error>> {
+        let nombre = format!(
+            "{}.{}",
+            process::id(),
+            CONTADOR_BITACORAS.fetch_add(1, Ordering::Relaxed)
+        );
+        let directorio = absolute(directorio_o_actual(directorio))?;
+        let ruta_nueva = directorio.join(format!(".nueva_bitacora.{}", nombre));
+        let ruta = directorio.join(format!("{}{}", PREFIJO_BITACORA, nombre));
+
+        let archivo = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&ruta_nueva)?;
+        archivo.lock()?;
+        rename(&ruta_nueva, &ruta)?;
+        Ok(Bitacora {
+            ruta,
+            archivo,
+            entradas: Vec::new(),
+            terminada: false,
+            bloqueos: Vec::new(),
+        })
+    }
+
+    fn registrar(&mut self, entrada: Entrada) -> Result<(), Box<dyn Error>> {
+        self.archivo.write_all(entrada.serializar().as_bytes())?;
+        self.archivo.sync_all()?;
+        self.entradas.push(entrada);
+        Ok(())
+    }
+
+    /*
+     * Registra que se van a agregar líneas al final del archivo, que antes de la operación tiene la longitud recibida.
+     * Si el archivo ya estaba registrado se conserva la longitud original.
+     */
+    pub fn registrar_agregado(&mut self, ruta: &str, longitud: u64) -> Result<(), Box<dyn Error>> {
+        let ruta = absolute(ruta)?;
+        if self
+            .entradas
+            .iter()
+            .any(|e| matches!(e, Entrada::Agregado(registrada, _) if *registrada == ruta))
+        {
+            return Ok(());
+        }
+        self.registrar(Entrada::Agregado(ruta, longitud))
+    }
+
+    /*
+     * Registra que el archivo `origen`, ya completo en disco, va a reemplazar al destino al confirmar.
+     */
+    pub fn registrar_renombre(
+        &mut self,
+        origen: &Path,
+        destino: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        let destino = absolute(destino)?;
+        if self
+            .entradas
+            .iter()
+            .any(|e| matches!(e, Entrada::Reemplazo(_, registrado) if *registrado == destino))
+        {
+            let tabla = destino
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default();
+            return Err(Box::new(ErrorPrograma::desde_mensaje(
+                ErrorTipo::ConstraintViolation {
+                    tabla,
+                    columna: None,
+                    valor: None,
+                },
+                Mensaje::ArchivoModificadoDosVeces,
+                &[&destino.to_string_lossy()],
+            )));
+        }
+        self.registrar(Entrada::Reemplazo(absolute(origen)?, destino))
+    }
+
+    /*
+     * Baja a disco el archivo temporal y registra que va a reemplazar a la tabla al confirmar.
+     */
+    pub fn registrar_reemplazo(
+        &mut self,
+        temporal: ArchivoTemporal,
+        ruta_destino: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        let ruta_temporal = temporal.preparar()?;
+        if let Err(e) = self.registrar_renombre(&ruta_temporal, ruta_destino) {
+            let _ = remove_file(&ruta_temporal);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    pub fn retener_bloqueo(&mut self, bloqueo: BloqueoTabla) {
+        self.bloqueos.push(bloqueo);
+    }
+
+    /*
+     * Devuelve la ruta de cada tabla que escribe la operación, el archivo que tiene su contenido nuevo y, si solo
+     * se le agregaron líneas al final, la longitud que tenía antes: el contenido es el mismo csv si se le agregaron
+     * líneas, o el temporal que lo va a reemplazar.
+     */
+    pub fn tablas_escritas(&self) -> Vec<(String, PathBuf, Option<u64>)> {
+        let es_csv = |ruta: &Path| ruta.extension().is_some_and(|e| e == "csv");
+        let reemplazos: Vec<&PathBuf> = self
+            .entradas
+            .iter()
+            .filter_map(|entrada| match entrada {
+                Entrada::Reemplazo(_, destino) => Some(destino),
+                Entrada::Agregado(..) => None,
+            })
+            .collect();
+        self.entradas
+            .iter()
+            .filter_map(|entrada| match entrada {
+                Entrada::Agregado(ruta, _) if reemplazos.contains(&ruta) => None,
+                Entrada::Agregado(ruta, longitud) => Some((ruta, ruta, Some(*longitud))),
+                Entrada::Reemplazo(temporal, destino) => Some((destino, temporal, None)),
+            })
+            .filter(|(destino, _, _)| es_csv(destino))
+            .map(|(destino, contenido, longitud)| {
+                (
+                    destino.to_string_lossy().to_string(),
+                    contenido.clone(),
+                    longitud,
+                )
+            })
+            .collect()
+    }
+
+    /*
+     * Marca la operación como confirmada y aplica los reemplazos registrados. A partir de la marca,
+     * un corte en el medio se completa en la recuperación en lugar de deshacerse.
+     */
+    pub fn confirmar(mut self) -> Result<(), Box<dyn Error>> {
+        self.archivo
+            .write_all(format!("{}\n", CONFIRMADA).as_bytes())?;
+        self.archivo.sync_all()?;
+        // Si un rename falla la bitácora confirmada queda en el directorio para rehacerse después.
+        self.terminada = true;
+        for entrada in self.entradas.iter() {
+            entrada.rehacer()?;
+        }
+        self.descartar()
+    }
+
+    /*
+     * Vacía la bitácora antes de borrarla, así un proceso que la abrió para recuperarla no encuentra nada que hacer.
+     */
+    fn descartar(&self) -> Result<(), Box<dyn Error>> {
+        self.archivo.set_len(0)?;
+        remove_file(&self.ruta)?;
+        Ok(())
+    }
+}
+
+impl Drop for Bitacora {
+    // Una operación que terminó con error sin confirmar se deshace al salir de scope.
+    fn drop(&mut self) {
+        if self.terminada {
+            return;
+        }
+        for entrada in self.entradas.iter().rev() {
+            let _ = entrada.deshacer();
+        }
+        let _ = self.descartar();
+    }
+}
+
+/*
+ * Busca en el directorio bitácoras de operaciones que quedaron sin terminar. Las que siguen bloqueadas pertenecen
+ * a un proceso que está escribiendo y se dejan como están. El resto se rehacen si estaban confirmadas o se deshacen
+ * si no, y se eliminan. Devuelve la cantidad de bitácoras recuperadas.
+ */
+pub fn recuperar_directorio(directorio: &str) -> Result<usize, Box<dyn Error>> {
+    let mut recuperadas = 0;
+    for entrada in read_dir(directorio_o_actual(directorio))? {
+        let path = entrada?.path();
+        let es_bitacora = path
+            .file_name()
+            .map(|n| n.to_string_lossy().starts_with(PREFIJO_BITACORA))
+            .unwrap_or(false);
+        if !es_bitacora {
+            continue;
+        }
+        let mut archivo = match OpenOptions::new().read(true).write(true).open(&path) {
+            Ok(archivo) => archivo,
+            Err(_) => continue,
+        };
+        match archivo.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => continue,
+            Err(TryLockError::Error(e)) => return Err(Box::new(e)),
+        }
+
+        let mut contenido = String::new();
+        archivo.read_to_string(&mut contenido)?;
+        let confirmada = contenido.lines().any(|l| l == CONFIRMADA);
+        let entradas: Vec<Entrada> = contenido.lines().filter_map(Entrada::parsear).collect();
+        if confirmada {
+            for entrada in entradas.iter() {
+                entrada.rehacer()?;
+            }
+        } else {
+            for entrada in entradas.iter().rev() {
+                entrada.deshacer()?;
+            }
+        }
+        archivo.set_len(0)?;
+        match remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(Box::new(e)),
+            _ => {}
+        }
+        if !contenido.is_empty() {
+            recuperadas += 1;
+        }
+    }
+    Ok(recuperadas)
+}
+
+/*
+ * Recorre cada csv del directorio y verifica que todas sus filas tengan tantos valores como columnas
+ * tiene el encabezado. Devuelve por tabla la cantidad de filas y la descripción del primer problema encontrado.
+ */
+pub fn verificar_tablas(directorio: &str) -> Result<Vec<EstadoTabla>, Box<dyn Error>> {
+    let mut resultado = Vec::new();
+    let mut rutas: Vec<PathBuf> = read_dir(directorio_o_actual(directorio))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("csv"))
+        .collect();
+    rutas.sort();
+
+    for ruta in rutas {
+        let tabla = ruta
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mut lineas = BufReader::new(File::open(&ruta)?).lines();
+        let cantidad_columnas = match lineas.next() {
+            Some(encabezado) => encabezado?.split(',').count(),
+            None => {
+                resultado.push((tabla, 0, Some(Mensaje::TablaSinEncabezado.texto(&[]))));
+                continue;
+            }
+        };
+
+        let mut filas = 0;
+        let mut problema = None;
+        for (numero, linea) in lineas.enumerate() {
+            let linea = linea?;
+            filas += 1;
+            let cantidad = linea.split(',').count();
+            if problema.is_none() && cantidad != cantidad_columnas {
+                problema = Some(Mensaje::LineaConOtraCantidad.texto(&[
+                    &(numero + 2).to_string(),
+                    &cantidad.to_string(),
+                    &cantidad_columnas.to_string(),
+                ]));
+            }
+        }
+        resultado.push((tabla, filas, problema));
+    }
+    Ok(resultado)
+}
+
+/*
+ * Ejecuta la consulta RECOVER: recupera las bitácoras pendientes del directorio y escribe en el destino
+ * el estado de cada tabla. Si alguna tabla tiene filas con una cantidad de valores distinta al encabezado
+ * se devuelve un error después de escribir el reporte completo.
+ */
+pub fn aplicar_recover(
+    directorio: &str,
+    destino: &mut dyn DestinoFilas,
+) -> Result<(), Box<dyn Error>> {
+    recuperar_directorio(directorio)?;
+    let tablas = verificar_tablas(directorio)?;
+
+    destino.encabezado(&[
+        "tabla".to_string(),
+        "filas".to_string(),
+        "estado".to_string(),
+    ])?;
+    let mut con_problemas = Vec::new();
+    for (tabla, filas, problema) in tablas {
+        let estado = match problema {
+            Some(problema) => {
+                con_problemas.push(tabla.clone());
+                problema
+            }
+            None => "OK".to_string(),
+        };
+        destino.fila(vec![tabla, filas.to_string(), estado])?;
+    }
+
+    if !con_problemas.is_empty() {
+        // El error indica el archivo de la primera tabla con problemas, el reporte ya las incluye a todas.
+        let tipo = ErrorTipo::Csv {
+            archivo: format!("{}.csv", con_problemas[0]),
+            linea: None,
+        };
+        let error = ErrorPrograma::desde_mensaje(
+            tipo,
+            Mensaje::TablasConFilasInvalidas,
+            &[&con_problemas.join(", ")],
+        );
+        return Err(Box::new(error));
+    }
+    Ok(())
+}
@@ -0,0 +1,161 @@
+use crate::errors::{ErrorPrograma, ErrorTipo};
+use crate::esquema::separar_ruta_tabla;
+use crate::mensajes::Mensaje;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::{absolute, PathBuf};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Tiempo máximo que se espera por el bloqueo de una tabla antes de devolver un error.
+const ESPERA_POR_DEFECTO_MS: u64 = 5000;
+const INTERVALO_REINTENTO: Duration = Duration::from_millis(10);
+
+static ESPERA_MAXIMA_MS: AtomicU64 = AtomicU64::new(ESPERA_POR_DEFECTO_MS);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ModoBloqueo {
+    Compartido,
+    Exclusivo,
+}
+
+// Modo de un bloqueo tomado, cuántas veces se pidió y el archivo bloqueado.
+type BloqueoTomado = (ModoBloqueo, usize, Rc<File>);
+
+thread_local! {
+    // Bloqueos que ya tiene el hilo actual.
+    static BLOQUEOS_TOMADOS: RefCell<HashMap<PathBuf, BloqueoTomado>> = RefCell::new(HashMap::new());
+}
+
+/*
+ * Bloqueo sobre una tabla, se libera al salir de scope. Las consultas de lectura toman un bloqueo compartido
+ * y las de escritura uno exclusivo, sobre un archivo .<tabla>.lock al lado del csv. Se bloquea ese archivo y
+ * no el csv porque las escrituras reemplazan el csv por otro archivo.
+ * Si el mismo hilo ya tiene la tabla bloqueada se reutiliza su bloqueo, así una consulta puede volver
+ * a leer la tabla que está escribiendo. Todos los bloqueos de la tabla en el hilo comparten el archivo, que se
+ * cierra recién cuando se suelta el último, sin importar en qué orden se suelten.
+ */
+#[derive(Debug)]
+pub struct BloqueoTabla {
+    ruta: PathBuf,
+    _archivo: Rc<File>,
+}
+
+/*
+ * Cambia el tiempo máximo que se espera por el bloqueo de una tabla.
+ */
+pub fn configurar_espera_bloqueo(espera: Duration) {
+    ESPERA_MAXIMA_MS.store(espera.as_millis() as u64, Ordering::Relaxed);
+}
+
+fn ruta_bloqueo(ruta_tabla: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let (directorio, tabla) = separar_ruta_tabla(ruta_tabla);
+    let directorio = if directorio.is_empty() {
+        ".".to_string()
+    } else {
+        directorio
+    };
+    Ok(absolute(
+        PathBuf::from(directorio).join(format!(".{}.lock", tabla)),
+    )?)
+}
+
+// Los datos del mensaje empiezan por el nombre de la tabla, los demás son los que se agregan al mensaje.
+fn error_bloqueo(ruta_tabla: &str, mensaje: Mensaje, datos: &[&str]) -> Box<dyn Error> {
+    let tabla = separar_ruta_tabla(ruta_tabla).1;
+    let datos: Vec<&str> = std::iter::once(tabla.as_str())
+        .chain(datos.iter().copied())
+        .collect();
+    let error = ErrorPrograma::desde_mensaje(
+        ErrorTipo::Lock {
+            tabla: tabla.clone(),
+        },
+        mensaje,
+        &datos,
+    );
+    Box::new(error)
+}
+
+/*
+ * Bloquea la tabla en el modo pedido, esperando como máximo el tiempo configurado
+ * a que otro proceso la libere.
+ */
+pub fn bloquear_tabla(ruta_tabla: &str, modo: ModoBloqueo) -> Result<BloqueoTabla, Box<dyn Error>> {
+    let ruta = ruta_bloqueo(ruta_tabla)?;
+
+    let tomado = BLOQUEOS_TOMADOS.with(|tomados| {
+        let mut tomados = tomados.borrow_mut();
+        let (modo_tomado, cantidad, archivo) = tomados.get_mut(&ruta)?;
+        if *modo_tomado == ModoBloqueo::Compartido && modo == ModoBloqueo::Exclusivo {
+            return Some(None);
+        }
+        *cantidad += 1;
+        Some(Some(Rc::clone(archivo)))
+    });
+    match tomado {
+        Some(None) => return Err(error_bloqueo(ruta_tabla, Mensaje::TablaEnLectura, &[])),
+        Some(Some(archivo)) => {
+            return Ok(BloqueoTabla {
+                ruta,
+                _archivo: archivo,
+            })
+        }
+        None => {}
+    }
+
+    let archivo = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&ruta)?;
+    let espera = Duration::from_millis(ESPERA_MAXIMA_MS.load(Ordering::Relaxed));
+    let inicio = Instant::now();
+    loop {
+        let resultado = match modo {
+            ModoBloqueo::Compartido => archivo.try_lock_shared(),
+            ModoBloqueo::Exclusivo => archivo.try_lock(),
+        };
+        match resultado {
+            Ok(()) => break,
+            Err(TryLockError::WouldBlock) if inicio.elapsed() < espera => {
+                thread::sleep(INTERVALO_REINTENTO)
+            }
+            Err(TryLockError::WouldBlock) => {
+                let espera = espera.as_millis().to_string();
+                return Err(error_bloqueo(ruta_tabla, Mensaje::TablaOcupada, &[&espera]));
+            }
+            Err(TryLockError::Error(e)) => return Err(Box::new(e)),
+        }
+    }
+
+    let archivo = Rc::new(archivo);
+    BLOQUEOS_TOMADOS.with(|tomados| {
+        tomados
+            .borrow_mut()
+            .insert(ruta.clone(), (modo, 1, Rc::clone(&archivo)))
+    });
+    Ok(BloqueoTabla {
+        ruta,
+        _archivo: archivo,
+    })
+}
+
+impl Drop for BloqueoTabla {
+    // El archivo se cierra al soltar el último bloqueo que lo comparte y con eso se libera el bloqueo del
+    // sistema operativo.
+    fn drop(&mut self) {
+        BLOQUEOS_TOMADOS.with(|tomados| {
+            let mut tomados = tomados.borrow_mut();
+            if let Some((_, cantidad, _)) = tomados.get_mut(&self.ruta) {
+                *cantidad -= 1;
+                if *cantidad == 0 {
+                    tomados.remove(&self.ruta);
+                }
+            }
+        });
+    }
+}
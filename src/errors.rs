@@ -1,17 +1,77 @@
+use crate::mensajes::Mensaje;
+use crate::salida::ancho_visible;
+use crate::sugerencias::sugerencia_mas_cercana;
 use std::error::Error;
 use std::fmt;
+use std::io;
+use std::ops::Range;
 
-#[derive(Debug)]
+/*
+ * Tipo de error de una consulta, con los datos que lo identifican para que quien use la biblioteca
+ * pueda distinguirlos con un match sin depender del texto de la descripción.
+ */
+#[derive(Debug, Clone, PartialEq)]
 pub enum ErrorTipo {
+    // La query, o el esquema de una tabla, no respeta la sintaxis.
     Syntax,
-    Table,
-    Column,
+    UnknownTable {
+        tabla: String,
+    },
+    UnknownColumn {
+        columna: String,
+    },
+    UnknownIndex {
+        indice: String,
+    },
+    // Un valor no es del tipo que se esperaba, la columna es None si el valor es un literal de la query.
+    TypeMismatch {
+        columna: Option<String>,
+        valor: String,
+    },
+    // Una restricción de la tabla (foreign key, valores únicos) impide la operación.
+    ConstraintViolation {
+        tabla: String,
+        columna: Option<String>,
+        valor: Option<String>,
+    },
+    // Error al leer o escribir un archivo, el error original queda como source() del ErrorPrograma.
+    Io {
+        tipo: io::ErrorKind,
+    },
+    Lock {
+        tabla: String,
+    },
+    // Un archivo csv, una tabla o un archivo importado, no tiene un formato válido.
+    Csv {
+        archivo: String,
+        linea: Option<usize>,
+    },
 }
 
+/*
+ * Error de una consulta. Además del tipo y la descripción puede indicar el token que lo causó, lo que se
+ * esperaba en su lugar y la posición en bytes del problema dentro de la query, para mostrarlo como
+ * un diagnóstico con la línea de la query y un '^' debajo del problema.
+ */
 #[derive(Debug)]
 pub struct ErrorPrograma {
     tipo: ErrorTipo,
     descripcion: String,
+    // Se guarda aparte para que el error sea chico al devolverlo en un Result.
+    ubicacion: Box<Ubicacion>,
+    origen: Option<io::Error>,
+}
+
+#[derive(Debug, Default)]
+struct Ubicacion {
+    posicion: Option<Range<usize>>,
+    token: Option<String>,
+    esperados: Vec<String>,
+    query: Option<String>,
+    // El problema es que la query terminó antes de tiempo, la posición es el final de la query.
+    al_final: bool,
+    sugerencia: Option<String>,
+    codigo: Option<u16>,
 }
 
 impl ErrorPrograma {
@@ -19,14 +79,305 @@ impl ErrorPrograma {
         ErrorPrograma {
             tipo,
             descripcion: descripcion.to_string(),
+            ubicacion: Box::default(),
+            origen: None,
+        }
+    }
+
+    /*
+     * Crea el error con el texto del mensaje en el idioma configurado, guardando su código del catálogo.
+     */
+    pub fn desde_mensaje(tipo: ErrorTipo, mensaje: Mensaje, datos: &[&str]) -> Self {
+        let mut error = ErrorPrograma::new(tipo, &mensaje.texto(datos));
+        error.ubicacion.codigo = Some(mensaje.codigo());
+        error
+    }
+
+    pub fn con_posicion(mut self, posicion: Range<usize>) -> Self {
+        self.ubicacion.posicion = Some(posicion);
+        self
+    }
+
+    pub fn con_token(mut self, token: &str) -> Self {
+        self.ubicacion.token = Some(token.to_string());
+        self
+    }
+
+    pub fn con_esperados(mut self, esperados: &[&str]) -> Self {
+        self.ubicacion.esperados = esperados.iter().map(|e| e.to_string()).collect();
+        self
+    }
+
+    /*
+     * Agrega el nombre válido más parecido al que causó el error, si hay alguno, para sugerirlo en el mensaje.
+     */
+    pub fn con_sugerencia(mut self, sugerencia: Option<&str>) -> Self {
+        self.ubicacion.sugerencia = sugerencia.map(|s| s.to_string());
+        self
+    }
+
+    /*
+     * Indica que falta algo al final de la query, el diagnóstico se muestra después de su último caracter.
+     */
+    pub fn al_final_de_la_query(mut self) -> Self {
+        self.ubicacion.al_final = true;
+        self
+    }
+
+    /*
+     * Agrega la query en la que ocurrió el error. Si el error no tiene posición se la busca a partir del token,
+     * en su primera aparición como palabra completa, o al final de la query si lo que faltaba era el final.
+     */
+    pub fn con_query(mut self, query: &str) -> Self {
+        if self.ubicacion.posicion.is_none() {
+            self.ubicacion.posicion = if self.ubicacion.al_final {
+                let fin = query.trim_end().trim_end_matches(';').trim_end().len();
+                Some(fin..fin)
+            } else {
+                self.ubicacion
+                    .token
+                    .as_ref()
+                    .and_then(|token| buscar_token(query, token))
+            };
+        }
+        self.ubicacion.query = Some(query.to_string());
+        self
+    }
+
+    pub fn tipo(&self) -> &ErrorTipo {
+        &self.tipo
+    }
+
+    pub fn descripcion(&self) -> &str {
+        &self.descripcion
+    }
+
+    // Código del mensaje en el catálogo, igual en todos los idiomas. None si el error viene de otra biblioteca.
+    pub fn codigo(&self) -> Option<u16> {
+        self.ubicacion.codigo
+    }
+
+    // Posición en bytes dentro de la query, si se pudo ubicar.
+    pub fn posicion(&self) -> Option<Range<usize>> {
+        self.ubicacion.posicion.clone()
+    }
+
+    pub fn token(&self) -> Option<&str> {
+        self.ubicacion.token.as_deref()
+    }
+
+    pub fn esperados(&self) -> &[String] {
+        &self.ubicacion.esperados
+    }
+
+    pub fn query(&self) -> Option<&str> {
+        self.ubicacion.query.as_deref()
+    }
+
+    /*
+     * Nombre que probablemente se quiso escribir. Si no se indicó uno, se busca entre las alternativas esperadas
+     * la más parecida al token; las que tienen varias palabras, como INSERT INTO, se comparan por la primera.
+     */
+    pub fn sugerencia(&self) -> Option<&str> {
+        if let Some(ref sugerencia) = self.ubicacion.sugerencia {
+            return Some(sugerencia);
+        }
+        let token = self.ubicacion.token.as_deref()?;
+        let esperados = &self.ubicacion.esperados;
+        let elegida = sugerencia_mas_cercana(token, esperados.iter().map(|e| primera_palabra(e)))?;
+        esperados
+            .iter()
+            .find(|esperado| primera_palabra(esperado) == elegida)
+            .map(|esperado| esperado.as_str())
+    }
+
+    /*
+     * Muestra el error como un diagnóstico: la descripción, la línea de la query con el problema
+     * marcado con '^' y lo que se esperaba en su lugar.
+     */
+    pub fn diagnostico(&self) -> String {
+        let mut diagnostico = format!("Invalid {}: {}", self.tipo.nombre(), self.descripcion);
+        if let (Some(query), Some(posicion)) = (&self.ubicacion.query, &self.ubicacion.posicion) {
+            if posicion.start <= query.len() && query.is_char_boundary(posicion.start) {
+                let inicio_linea = query[..posicion.start].rfind('\n').map_or(0, |i| i + 1);
+                let fin_linea = query[posicion.start..]
+                    .find('\n')
+                    .map_or(query.len(), |i| posicion.start + i);
+                let numero_linea = query[..inicio_linea].matches('\n').count() + 1;
+                let fin = posicion.end.clamp(posicion.start, fin_linea);
+                let columna = ancho_visible(&query[inicio_linea..posicion.start]);
+                let marcas = ancho_visible(&query[posicion.start..fin]).max(1);
+                let margen = " ".repeat(numero_linea.to_string().len());
+
+                let linea_columna = Mensaje::DiagnosticoUbicacion
+                    .texto(&[&numero_linea.to_string(), &(columna + 1).to_string()]);
+                diagnostico.push_str(&format!(
+                    "\n{} --> {}\n{} |\n{} | {}\n{} | {}{}",
+                    margen,
+                    linea_columna,
+                    margen,
+                    numero_linea,
+                    &query[inicio_linea..fin_linea],
+                    margen,
+                    " ".repeat(columna),
+                    "^".repeat(marcas)
+                ));
+            }
+        }
+        if !self.ubicacion.esperados.is_empty() {
+            let esperados = unir_alternativas(&self.ubicacion.esperados);
+            diagnostico.push_str(&format!(
+                "\n  = {}",
+                Mensaje::DiagnosticoEsperados.texto(&[&esperados])
+            ));
+        }
+        if let Some(sugerencia) = self.sugerencia() {
+            diagnostico.push_str(&format!(
+                "\n  = {}",
+                Mensaje::DiagnosticoSugerencia.texto(&[sugerencia])
+            ));
+        }
+        diagnostico
+    }
+}
+
+fn primera_palabra(texto: &str) -> &str {
+    texto.split_whitespace().next().unwrap_or("")
+}
+
+fn es_parte_de_palabra(caracter: Option<char>) -> bool {
+    caracter.is_some_and(|c| c.is_alphanumeric() || c == '_')
+}
+
+/*
+ * Posición de la primera aparición del token en la query que no sea parte de una palabra más larga,
+ * o la primera aparición cualquiera si no hay ninguna así.
+ */
+fn buscar_token(query: &str, token: &str) -> Option<Range<usize>> {
+    if token.is_empty() {
+        return None;
+    }
+    let mut apariciones = query.match_indices(token).map(|(inicio, _)| inicio);
+    let primera = apariciones.next()?;
+    let completa = std::iter::once(primera).chain(apariciones).find(|&inicio| {
+        let fin = inicio + token.len();
+        let antes = query[..inicio].chars().next_back();
+        let despues = query[fin..].chars().next();
+        let token_es_palabra = token.starts_with(|c: char| c.is_alphanumeric() || c == '_');
+        !token_es_palabra || (!es_parte_de_palabra(antes) && !es_parte_de_palabra(despues))
+    });
+    let inicio = completa.unwrap_or(primera);
+    Some(inicio..inicio + token.len())
+}
+
+fn unir_alternativas(alternativas: &[String]) -> String {
+    match alternativas {
+        [] => String::new(),
+        [unica] => unica.to_string(),
+        [anteriores @ .., ultima] => Mensaje::Alternativas.texto(&[&anteriores.join(", "), ultima]),
+    }
+}
+
+// Código de salida de los errores de lectura o escritura de archivos.
+pub const CODIGO_SALIDA_IO: u8 = 7;
+// Código de salida de cualquier otro error.
+pub const CODIGO_SALIDA_OTRO: u8 = 1;
+
+impl ErrorTipo {
+    // Nombre con el que se muestra el tipo de error al principio del mensaje.
+    pub fn nombre(&self) -> &'static str {
+        match self {
+            ErrorTipo::Syntax => "Syntax",
+            ErrorTipo::UnknownTable { .. } => "Table",
+            ErrorTipo::UnknownColumn { .. } => "Column",
+            ErrorTipo::UnknownIndex { .. } => "Index",
+            ErrorTipo::TypeMismatch { .. } => "Type",
+            ErrorTipo::ConstraintViolation { .. } => "Constraint",
+            ErrorTipo::Io { .. } => "Io",
+            ErrorTipo::Lock { .. } => "Lock",
+            ErrorTipo::Csv { .. } => "Csv",
+        }
+    }
+
+    /*
+     * Código con el que termina el programa ante un error de este tipo, así quien lo ejecuta desde un script
+     * puede distinguir los errores sin leer el mensaje.
+     */
+    pub fn codigo_salida(&self) -> u8 {
+        match self {
+            ErrorTipo::Syntax => 2,
+            ErrorTipo::UnknownTable { .. } | ErrorTipo::UnknownIndex { .. } => 3,
+            ErrorTipo::UnknownColumn { .. } | ErrorTipo::TypeMismatch { .. } => 4,
+            ErrorTipo::ConstraintViolation { .. } => 5,
+            ErrorTipo::Lock { .. } => 6,
+            ErrorTipo::Io { .. } => CODIGO_SALIDA_IO,
+            ErrorTipo::Csv { .. } => 8,
+        }
+    }
+}
+
+/*
+ * Código de salida para cualquier error que devuelva una consulta.
+ */
+pub fn codigo_salida(error: &(dyn Error + 'static)) -> u8 {
+    if let Some(error) = error.downcast_ref::<ErrorPrograma>() {
+        error.tipo.codigo_salida()
+    } else if error.downcast_ref::<io::Error>().is_some() {
+        CODIGO_SALIDA_IO
+    } else {
+        CODIGO_SALIDA_OTRO
+    }
+}
+
+impl From<io::Error> for ErrorPrograma {
+    fn from(error: io::Error) -> Self {
+        let mut error_programa =
+            ErrorPrograma::new(ErrorTipo::Io { tipo: error.kind() }, &error.to_string());
+        error_programa.origen = Some(error);
+        error_programa
+    }
+}
+
+/*
+ * Los módulos internos y los destinos de las filas devuelven Box<dyn Error>, que en la práctica son un
+ * ErrorPrograma o un error de entrada y salida. Cualquier otro error se toma como un error al escribir.
+ */
+impl From<Box<dyn Error>> for ErrorPrograma {
+    fn from(error: Box<dyn Error>) -> Self {
+        match error.downcast::<ErrorPrograma>() {
+            Ok(error) => *error,
+            Err(error) => match error.downcast::<io::Error>() {
+                Ok(error) => ErrorPrograma::from(*error),
+                Err(error) => ErrorPrograma::new(
+                    ErrorTipo::Io {
+                        tipo: io::ErrorKind::Other,
+                    },
+                    &error.to_string(),
+                ),
+            },
         }
     }
 }
 
-impl Error for ErrorPrograma {}
+impl Error for ErrorPrograma {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.origen
+            .as_ref()
+            .map(|error| error as &(dyn Error + 'static))
+    }
+}
 
+// Con el formato alternativo ({:#}) se muestra el diagnóstico completo en varias líneas.
 impl fmt::Display for ErrorPrograma {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Invalid {:?}: {}", self.tipo, self.descripcion)
+        if f.alternate() {
+            write!(f, "{}", self.diagnostico())
+        } else {
+            write!(f, "Invalid {}: {}", self.tipo.nombre(), self.descripcion)?;
+            match self.sugerencia() {
+                Some(sugerencia) => write!(f, " {}", Mensaje::Sugerencia.texto(&[sugerencia])),
+                None => Ok(()),
+            }
+        }
     }
 }
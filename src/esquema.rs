@@ -0,0 +1,378 @@
+use crate::archivo_temporal::ArchivoTemporal;
+use crate::errors::{ErrorPrograma, ErrorTipo};
+use crate::mensajes::Mensaje;
+use std::error::Error;
+use std::fs::{read_dir, read_to_string, rename, File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AccionReferencial {
+    Restrict,
+    Cascade,
+    SetNull,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForeignKey {
+    pub columna: String,
+    pub tabla_referenciada: String,
+    pub columna_referenciada: String,
+    pub on_delete: AccionReferencial,
+    pub on_update: AccionReferencial,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AutoIncrement {
+    pub columna: String,
+    pub siguiente: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TipoIndice {
+    Hash,
+    // Índice con las filas ordenadas por el valor, sirve para rangos y ORDER BY.
+    Btree,
+}
+
+// Índice declarado con CREATE INDEX, sus datos se guardan en el archivo <tabla>.<nombre>.indice.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Indice {
+    pub nombre: String,
+    pub columna: String,
+    pub tipo: TipoIndice,
+}
+
+/*
+ * Metadatos declarados sobre una tabla. Se guardan en un archivo <tabla>.esquema al lado del csv,
+ * con una restricción por línea y sus campos separados por comas.
+ */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Esquema {
+    pub foreign_keys: Vec<ForeignKey>,
+    pub auto_increment: Option<AutoIncrement>,
+    // Columna y valor por defecto que se usa al insertar una fila sin esa columna.
+    pub defaults: Vec<(String, String)>,
+    pub indices: Vec<Indice>,
+}
+
+impl AccionReferencial {
+    pub fn desde_str(accion: &str) -> Result<Self, Box<dyn Error>> {
+        match accion {
+            "RESTRICT" | "NO ACTION" => Ok(AccionReferencial::Restrict),
+            "CASCADE" => Ok(AccionReferencial::Cascade),
+            "SET NULL" => Ok(AccionReferencial::SetNull),
+            _ => Err(Box::new(ErrorPrograma::desde_mensaje(
+                ErrorTipo::Syntax,
+                Mensaje::AccionReferencialInvalida,
+                &[accion],
+            ))),
+        }
+    }
+
+    pub fn como_str(&self) -> &str {
+        match self {
+            AccionReferencial::Restrict => "RESTRICT",
+            AccionReferencial::Cascade => "CASCADE",
+            AccionReferencial::SetNull => "SET NULL",
+        }
+    }
+}
+
+impl TipoIndice {
+    pub fn desde_str(tipo: &str) -> Result<Self, Box<dyn Error>> {
+        match tipo {
+            "HASH" => Ok(TipoIndice::Hash),
+            "BTREE" => Ok(TipoIndice::Btree),
+            _ => Err(Box::new(ErrorPrograma::desde_mensaje(
+                ErrorTipo::Syntax,
+                Mensaje::TipoIndiceInvalido,
+                &[tipo],
+            ))),
+        }
+    }
+
+    pub fn como_str(&self) -> &str {
+        match self {
+            TipoIndice::Hash => "HASH",
+            TipoIndice::Btree => "BTREE",
+        }
+    }
+}
+
+/*
+ * Devuelve la ruta del archivo de esquema correspondiente a la ruta del csv de una tabla.
+ */
+pub fn ruta_esquema(ruta_tabla: &str) -> String {
+    Path::new(ruta_tabla)
+        .with_extension("esquema")
+        .to_string_lossy()
+        .to_string()
+}
+
+/*
+ * Bloquea de forma exclusiva el archivo .<tabla>.esquema.lock al lado del esquema, hasta que se suelta el
+ * archivo devuelto. Se bloquea ese archivo y no el esquema porque cada escritura reemplaza el esquema por otro.
+ */
+pub(crate) fn bloquear_esquema(ruta_tabla: &str) -> Result<File, Box<dyn Error>> {
+    let ruta = Path::new(ruta_tabla);
+    let nombre = ruta
+        .with_extension("esquema")
+        .file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let archivo = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(ruta.with_file_name(format!(".{}.lock", nombre)))?;
+    archivo.lock()?;
+    Ok(archivo)
+}
+
+/*
+ * Lee el esquema guardado de la tabla, None si no tiene archivo de esquema.
+ */
+fn leer_esquema(ruta_tabla: &str) -> Result<Option<Esquema>, Box<dyn Error>> {
+    match read_to_string(ruta_esquema(ruta_tabla)) {
+        Ok(contenido) => Ok(Some(Esquema::parsear(&contenido, ruta_tabla)?)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(Box::new(e)),
+    }
+}
+
+/*
+ * Escribe el esquema en un archivo temporal, lo baja a disco y lo renombra sobre el esquema de la tabla. Así un
+ * corte en el medio deja el esquema anterior o el nuevo completo, nunca uno a medio escribir.
+ */
+fn escribir_esquema(esquema: &Esquema, ruta_tabla: &str) -> Result<(), Box<dyn Error>> {
+    let destino = ruta_esquema(ruta_tabla);
+    let ruta_temporal = esquema.escribir_temporal(ruta_tabla)?.preparar()?;
+    if let Err(e) = rename(&ruta_temporal, &destino) {
+        let _ = std::fs::remove_file(&ruta_temporal);
+        return Err(Box::new(e));
+    }
+    Ok(())
+}
+
+/*
+ * Devuelve el directorio y el nombre de la tabla a partir de la ruta de su csv.
+ */
+pub fn separar_ruta_tabla(ruta_tabla: &str) -> (String, String) {
+    let path = Path::new(ruta_tabla);
+    let directorio = path
+        .parent()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let tabla = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    (directorio, tabla)
+}
+
+/*
+ * Devuelve los nombres de las tablas del directorio, ordenados. Los archivos ocultos no son tablas.
+ */
+pub fn tablas_del_directorio(directorio: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let directorio = if directorio.is_empty() {
+        "."
+    } else {
+        directorio
+    };
+    let mut tablas: Vec<String> = read_dir(directorio)?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("csv"))
+        .filter_map(|p| p.file_stem().map(|s| s.to_string_lossy().to_string()))
+        .filter(|tabla| !tabla.starts_with('.'))
+        .collect();
+    tablas.sort();
+    Ok(tablas)
+}
+
+impl Esquema {
+    fn parsear(contenido: &str, ruta_tabla: &str) -> Result<Self, Box<dyn Error>> {
+        let mut esquema = Esquema::default();
+        for linea in contenido.lines() {
+            // El valor por defecto puede tener comas, por eso se separa en tres partes como máximo.
+            if let Some(resto) = linea.strip_prefix("DEFAULT,") {
+                if let Some((columna, valor)) = resto.split_once(',') {
+                    esquema
+                        .defaults
+                        .push((columna.trim().to_string(), valor.to_string()));
+                    continue;
+                }
+            }
+            let campos: Vec<&str> = linea.split(',').map(|s| s.trim()).collect();
+            match campos.as_slice() {
+                ["FOREIGN KEY", columna, tabla, columna_ref, on_delete, on_update] => {
+                    esquema.foreign_keys.push(ForeignKey {
+                        columna: columna.to_string(),
+                        tabla_referenciada: tabla.to_string(),
+                        columna_referenciada: columna_ref.to_string(),
+                        on_delete: AccionReferencial::desde_str(on_delete)?,
+                        on_update: AccionReferencial::desde_str(on_update)?,
+                    })
+                }
+                ["INDEX", nombre, tipo, columna] => esquema.indices.push(Indice {
+                    nombre: nombre.to_string(),
+                    columna: columna.to_string(),
+                    tipo: TipoIndice::desde_str(tipo)?,
+                }),
+                ["AUTO_INCREMENT", columna, siguiente] => {
+                    esquema.auto_increment = Some(AutoIncrement {
+                        columna: columna.to_string(),
+                        siguiente: siguiente.parse::<u64>()?,
+                    })
+                }
+                [""] => continue,
+                _ => {
+                    return Err(Box::new(ErrorPrograma::desde_mensaje(
+                        ErrorTipo::Syntax,
+                        Mensaje::LineaEsquemaInvalida,
+                        &[linea, ruta_tabla],
+                    )));
+                }
+            }
+        }
+        Ok(esquema)
+    }
+
+    fn serializar(&self) -> String {
+        let mut contenido = String::new();
+        for fk in self.foreign_keys.iter() {
+            contenido.push_str(&format!(
+                "FOREIGN KEY,{},{},{},{},{}\n",
+                fk.columna,
+                fk.tabla_referenciada,
+                fk.columna_referenciada,
+                fk.on_delete.como_str(),
+                fk.on_update.como_str()
+            ));
+        }
+        if let Some(ref auto_increment) = self.auto_increment {
+            contenido.push_str(&format!(
+                "AUTO_INCREMENT,{},{}\n",
+                auto_increment.columna, auto_increment.siguiente
+            ));
+        }
+        for (columna, valor) in self.defaults.iter() {
+            contenido.push_str(&format!("DEFAULT,{},{}\n", columna, valor));
+        }
+        for indice in self.indices.iter() {
+            contenido.push_str(&format!(
+                "INDEX,{},{},{}\n",
+                indice.nombre,
+                indice.tipo.como_str(),
+                indice.columna
+            ));
+        }
+        contenido
+    }
+
+    /*
+     * Devuelve el valor por defecto declarado para la columna, si lo tiene.
+     */
+    pub fn default_de(&self, columna: &str) -> Option<&str> {
+        self.defaults
+            .iter()
+            .find(|(col, _)| col == columna)
+            .map(|(_, valor)| valor.as_str())
+    }
+
+    /*
+     * Lee el esquema de la tabla, si la tabla no tiene un archivo de esquema se devuelve uno vacío.
+     */
+    /*
+     * Escribe el esquema en un archivo temporal al lado del esquema de la tabla, para reemplazarlo después.
+     */
+    pub(crate) fn escribir_temporal(
+        &self,
+        ruta_tabla: &str,
+    ) -> Result<ArchivoTemporal, Box<dyn Error>> {
+        let mut temporal = ArchivoTemporal::crear_junto_a(&ruta_esquema(ruta_tabla))?;
+        temporal.write_all(self.serializar().as_bytes())?;
+        Ok(temporal)
+    }
+
+    pub fn cargar(ruta_tabla: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(leer_esquema(ruta_tabla)?.unwrap_or_default())
+    }
+
+    /*
+     * Escribe el esquema completo en el archivo de esquema de la tabla, reemplazándolo de una vez. Si mientras
+     * tanto otro proceso reservó valores del auto increment de la misma columna, el contador guardado no
+     * vuelve atrás.
+     */
+    pub fn guardar(&self, ruta_tabla: &str) -> Result<(), Box<dyn Error>> {
+        let _bloqueo = bloquear_esquema(ruta_tabla)?;
+        let mut esquema = self.clone();
+        let guardado = leer_esquema(ruta_tabla)?.and_then(|guardado| guardado.auto_increment);
+        if let (Some(auto_increment), Some(guardado)) = (esquema.auto_increment.as_mut(), guardado)
+        {
+            if auto_increment.columna == guardado.columna {
+                auto_increment.siguiente = auto_increment.siguiente.max(guardado.siguiente);
+            }
+        }
+        escribir_esquema(&esquema, ruta_tabla)
+    }
+}
+
+/*
+ * Reserva `cantidad` valores consecutivos de la columna auto increment de la tabla y devuelve el primero.
+ * El siguiente valor nunca queda por debajo de `minimo_siguiente`, así los valores ingresados a mano no se repiten.
+ * La lectura y escritura del contador se hacen con el esquema bloqueado, por lo que dos procesos que insertan
+ * a la vez nunca reciben el mismo valor. Devuelve None si la tabla no tiene auto increment.
+ */
+pub fn reservar_auto_increment(
+    ruta_tabla: &str,
+    cantidad: u64,
+    minimo_siguiente: u64,
+) -> Result<Option<u64>, Box<dyn Error>> {
+    if !Path::new(&ruta_esquema(ruta_tabla)).exists() {
+        return Ok(None);
+    }
+    let _bloqueo = bloquear_esquema(ruta_tabla)?;
+    let Some(mut esquema) = leer_esquema(ruta_tabla)? else {
+        return Ok(None);
+    };
+    let auto_increment = match esquema.auto_increment {
+        Some(ref mut auto_increment) => auto_increment,
+        None => return Ok(None),
+    };
+    let primero = auto_increment.siguiente.max(minimo_siguiente);
+    auto_increment.siguiente = primero + cantidad;
+
+    escribir_esquema(&esquema, ruta_tabla)?;
+    Ok(Some(primero))
+}
+
+/*
+ * Busca en el directorio de tablas todas las foreign keys que referencian a la tabla recibida.
+ * Devuelve la ruta del csv de cada tabla hija junto a la foreign key declarada en ella.
+ */
+pub fn foreign_keys_que_referencian(
+    directorio: &str,
+    tabla: &str,
+) -> Result<Vec<(String, ForeignKey)>, Box<dyn Error>> {
+    let mut referencias = Vec::new();
+    let directorio = if directorio.is_empty() {
+        "."
+    } else {
+        directorio
+    };
+    for entrada in read_dir(directorio)? {
+        let path = entrada?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("esquema") {
+            continue;
+        }
+        let ruta_hija = path.with_extension("csv").to_string_lossy().to_string();
+        for fk in Esquema::cargar(&ruta_hija)?.foreign_keys {
+            if fk.tabla_referenciada == tabla {
+                referencias.push((ruta_hija.clone(), fk));
+            }
+        }
+    }
+    referencias.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(referencias)
+}
@@ -0,0 +1,894 @@
+use crate::archivo_temporal::ArchivoTemporal;
+use crate::bitacora::Bitacora;
+use crate::bloqueo::{bloquear_tabla, ModoBloqueo};
+use crate::errors::{ErrorPrograma, ErrorTipo};
+use crate::esquema::{
+    bloquear_esquema, ruta_esquema, separar_ruta_tabla, tablas_del_directorio, Esquema, Indice,
+    TipoIndice,
+};
+use crate::mensajes::Mensaje;
+use crate::predicado::valor_sin_error;
+use crate::query_identifier::{
+    Comparacion, CreateIndex, DropIndex, Order, OrderByClause, WhereClause,
+};
+use crate::read_file::{abrir_archivo, verificar_existen_columnas_query};
+use crate::sugerencias::sugerencia_mas_cercana;
+use std::fs::{metadata, remove_file, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+// Ancho de cada entrada del directorio de cubetas: 20 dígitos y el salto de línea.
+const ANCHO_ENTRADA: u64 = 21;
+// Ancho de la marca que cierra las filas agregadas al final de un índice: '#', 20 dígitos y el salto de línea.
+const ANCHO_MARCA: u64 = 22;
+// El índice se reconstruye cuando las filas agregadas al final ocupan más que esta fracción de lo ordenado.
+const FRACCION_AGREGADAS: u64 = 4;
+// Cantidad promedio de filas por cubeta de un índice HASH.
+const FILAS_POR_CUBETA: usize = 4;
+// Cantidad de filas por página de un índice BTREE.
+const FILAS_POR_PAGINA: usize = 64;
+
+// Valor de la columna en cada fila de la tabla y la posición en bytes en la que empieza la fila.
+type ValoresConPosicion = Vec<(String, u64)>;
+
+/*
+ * Devuelve la ruta del archivo con los datos del índice, <tabla>.<nombre>.indice al lado del csv.
+ */
+pub fn ruta_indice(ruta_tabla: &str, nombre: &str) -> String {
+    Path::new(ruta_tabla)
+        .with_extension(format!("{}.indice", nombre))
+        .to_string_lossy()
+        .to_string()
+}
+
+/*
+ * Hash FNV-1a del valor. No se usa el de la biblioteca estándar porque el índice queda guardado en disco
+ * y el hash de un valor tiene que ser el mismo en todas las ejecuciones.
+ */
+fn hash_valor(valor: &str) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in valor.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+/*
+ * Recorre el contenido de una tabla desde la posición `desde`, o desde la primera fila si es anterior, y devuelve
+ * el valor de la columna en cada fila con la posición en la que empieza la fila, junto con la longitud total
+ * del archivo.
+ */
+fn valores_con_posicion(
+    contenido: &Path,
+    columna: &str,
+    desde: u64,
+) -> Result<(ValoresConPosicion, u64), ErrorPrograma> {
+    let mut lector = BufReader::new(File::open(contenido)?);
+    let mut linea = String::new();
+    let mut posicion = lector.read_line(&mut linea)? as u64;
+    let indice_columna = linea
+        .trim_end()
+        .split(',')
+        .position(|c| c.trim() == columna)
+        .ok_or_else(|| {
+            ErrorPrograma::desde_mensaje(
+                ErrorTipo::UnknownColumn {
+                    columna: columna.to_string(),
+                },
+                Mensaje::ColumnaInexistente,
+                &[columna],
+            )
+        })?;
+    if desde > posicion {
+        lector.seek(SeekFrom::Start(desde))?;
+        posicion = desde;
+    }
+
+    let mut valores = Vec::new();
+    loop {
+        linea.clear();
+        let leidos = lector.read_line(&mut linea)?;
+        if leidos == 0 {
+            break;
+        }
+        let fila = linea.trim_end_matches(['\n', '\r']);
+        let valor = fila.split(',').nth(indice_columna).unwrap_or("").trim();
+        valores.push((valor.to_string(), posicion));
+        posicion += leidos as u64;
+    }
+    Ok((valores, posicion))
+}
+
+/*
+ * El índice HASH reparte las filas en cubetas según el hash de su valor. El archivo empieza con una línea con
+ * el tipo, la columna, la longitud de la tabla indexada, la cantidad de cubetas y dónde terminan las cubetas.
+ * Le sigue un directorio con la posición de cada cubeta, de ancho fijo para poder saltar directo a la del valor
+ * buscado, y las cubetas con una línea <posición de la fila>,<valor> por fila. Después de las cubetas quedan
+ * las filas agregadas a la tabla desde que se construyó el índice (ver `agregar_al_indice`).
+ */
+fn escribir_hash(
+    destino: &mut impl Write,
+    columna: &str,
+    valores: ValoresConPosicion,
+    longitud: u64,
+) -> io::Result<()> {
+    let cantidad = (valores.len() / FILAS_POR_CUBETA).max(1);
+    let mut cubetas = vec![String::new(); cantidad];
+    for (valor, posicion) in valores.iter() {
+        let cubeta = (hash_valor(valor) % cantidad as u64) as usize;
+        cubetas[cubeta].push_str(&format!("{},{}\n", posicion, valor));
+    }
+
+    let encabezado = format!("HASH,{},{},{},", columna, longitud, cantidad);
+    let datos: usize = cubetas.iter().map(|cubeta| cubeta.len()).sum();
+    let fin = encabezado.len() as u64 + ANCHO_ENTRADA * (cantidad as u64 + 2) + datos as u64;
+    writeln!(destino, "{}{:020}", encabezado, fin)?;
+    let mut inicio = 0;
+    for cubeta in cubetas.iter() {
+        writeln!(destino, "{:020}", inicio)?;
+        inicio += cubeta.len();
+    }
+    writeln!(destino, "{:020}", inicio)?;
+    for cubeta in cubetas {
+        destino.write_all(cubeta.as_bytes())?;
+    }
+    Ok(())
+}
+
+/*
+ * El índice BTREE guarda las filas ordenadas por su valor, y por su posición entre las que tienen el mismo valor,
+ * en páginas de un tamaño fijo de filas. El archivo empieza con una línea con el tipo, la columna, la longitud de
+ * la tabla indexada, la cantidad de filas, la cantidad de páginas y dónde terminan las páginas. Le sigue el nodo
+ * raíz, con una línea <posición de la página>,<primer valor de la página> por página, y las páginas con una línea
+ * <posición de la fila>,<valor> por fila. Después de las páginas quedan, sin ordenar, las filas agregadas a la
+ * tabla desde que se construyó el índice (ver `agregar_al_indice`).
+ */
+fn escribir_arbol(
+    destino: &mut impl Write,
+    columna: &str,
+    mut valores: ValoresConPosicion,
+    longitud: u64,
+) -> io::Result<()> {
+    valores.sort();
+    let paginas: Vec<String> = valores
+        .chunks(FILAS_POR_PAGINA)
+        .map(|pagina| {
+            pagina
+                .iter()
+                .map(|(valor, posicion)| format!("{},{}\n", posicion, valor))
+                .collect()
+        })
+        .collect();
+
+    let mut raiz = String::new();
+    let mut inicio = 0;
+    for (pagina, filas) in paginas.iter().zip(valores.chunks(FILAS_POR_PAGINA)) {
+        raiz.push_str(&format!("{:020},{}\n", inicio, filas[0].0));
+        inicio += pagina.len();
+    }
+    let encabezado = format!(
+        "BTREE,{},{},{},{},",
+        columna,
+        longitud,
+        valores.len(),
+        paginas.len()
+    );
+    let fin = encabezado.len() as u64 + ANCHO_ENTRADA + raiz.len() as u64 + inicio as u64;
+    writeln!(destino, "{}{:020}", encabezado, fin)?;
+    destino.write_all(raiz.as_bytes())?;
+    for pagina in paginas {
+        destino.write_all(pagina.as_bytes())?;
+    }
+    Ok(())
+}
+
+/*
+ * Escribe en un temporal al lado del archivo del índice su contenido para la tabla guardada en `contenido`.
+ */
+fn construir_indice(
+    ruta_tabla: &str,
+    contenido: &Path,
+    indice: &Indice,
+) -> Result<ArchivoTemporal, ErrorPrograma> {
+    let (valores, longitud) = valores_con_posicion(contenido, &indice.columna, 0)?;
+    let mut temporal = ArchivoTemporal::crear_junto_a(&ruta_indice(ruta_tabla, &indice.nombre))?;
+    match indice.tipo {
+        TipoIndice::Hash => escribir_hash(&mut temporal, &indice.columna, valores, longitud)?,
+        TipoIndice::Btree => escribir_arbol(&mut temporal, &indice.columna, valores, longitud)?,
+    }
+    Ok(temporal)
+}
+
+/*
+ * Devuelve la longitud de la tabla con la que está al día el índice y dónde empiezan sus filas agregadas.
+ * La longitud es la de la última marca de filas agregadas o, si no hay, la del encabezado.
+ */
+fn longitud_vigente(archivo: &File, tipo: TipoIndice, longitud_indice: u64) -> Option<(u64, u64)> {
+    let mut lector = BufReader::new(archivo);
+    let numeros = leer_encabezado(&mut lector, tipo)?;
+    let (longitud, fin) = (*numeros.first()?, *numeros.last()?);
+    if longitud_indice <= fin {
+        return Some((longitud, fin));
+    }
+    lector
+        .seek(SeekFrom::Start(longitud_indice.checked_sub(ANCHO_MARCA)?))
+        .ok()?;
+    let mut marca = String::new();
+    lector.read_line(&mut marca).ok()?;
+    let longitud = marca.strip_prefix('#')?.trim_end().parse::<u64>().ok()?;
+    Some((longitud, fin))
+}
+
+/*
+ * Agrega al final del índice las filas que se agregaron al final de la tabla desde la posición `desde`, y después
+ * una marca con la longitud nueva de la tabla. Las filas agregadas se registran en la bitácora, así si la
+ * operación no termina se quitan junto con su marca y el índice vuelve a estar al día con la tabla.
+ * Devuelve false si el índice no estaba al día o si las filas agregadas ya ocupan demasiado, y entonces hay
+ * que reconstruirlo.
+ */
+fn agregar_al_indice(
+    ruta_tabla: &str,
+    indice: &Indice,
+    desde: u64,
+    bitacora: &mut Bitacora,
+) -> Result<bool, ErrorPrograma> {
+    let ruta = ruta_indice(ruta_tabla, &indice.nombre);
+    let mut archivo = match OpenOptions::new().read(true).append(true).open(&ruta) {
+        Ok(archivo) => archivo,
+        Err(_) => return Ok(false),
+    };
+    let longitud_indice = archivo.metadata()?.len();
+    match longitud_vigente(&archivo, indice.tipo, longitud_indice) {
+        Some((longitud, fin)) if longitud == desde => {
+            let (valores, longitud_nueva) =
+                valores_con_posicion(Path::new(ruta_tabla), &indice.columna, desde)?;
+            let mut agregado = String::new();
+            for (valor, posicion) in valores {
+                agregado.push_str(&format!("{},{}\n", posicion, valor));
+            }
+            agregado.push_str(&format!("#{:020}\n", longitud_nueva));
+            let agregadas = longitud_indice - fin + agregado.len() as u64;
+            if agregadas * FRACCION_AGREGADAS > fin {
+                return Ok(false);
+            }
+            bitacora.registrar_agregado(&ruta, longitud_indice)?;
+            archivo.write_all(agregado.as_bytes())?;
+            archivo.sync_all()?;
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+/*
+ * Antes de confirmar una operación se actualizan los índices de cada tabla que escribió, y se registran en la
+ * misma bitácora para que se publiquen junto con las tablas. Si solo se agregaron filas al final de la tabla se
+ * agregan también al final del índice; si no, el índice se reconstruye a partir del contenido nuevo de la tabla.
+ */
+pub(crate) fn confirmar_con_indices(mut bitacora: Bitacora) -> Result<(), ErrorPrograma> {
+    for (ruta_tabla, contenido, agregado_desde) in bitacora.tablas_escritas() {
+        for indice in Esquema::cargar(&ruta_tabla)?.indices.iter() {
+            if let Some(desde) = agregado_desde {
+                if agregar_al_indice(&ruta_tabla, indice, desde, &mut bitacora)? {
+                    continue;
+                }
+            }
+            let temporal = construir_indice(&ruta_tabla, &contenido, indice)?;
+            bitacora.registrar_reemplazo(temporal, &ruta_indice(&ruta_tabla, &indice.nombre))?;
+        }
+    }
+    Ok(bitacora.confirmar()?)
+}
+
+fn leer_numero(lector: &mut BufReader<File>, bytes: usize) -> Option<u64> {
+    let mut numero = vec![0; bytes];
+    lector.read_exact(&mut numero).ok()?;
+    String::from_utf8(numero).ok()?.trim().parse::<u64>().ok()
+}
+
+/*
+ * Lee el encabezado del índice y devuelve sus campos numéricos: la longitud de la tabla al construirlo, los
+ * propios del tipo y dónde empiezan las filas agregadas. Devuelve None si el índice no es del tipo esperado.
+ */
+fn leer_encabezado(lector: &mut impl BufRead, tipo: TipoIndice) -> Option<Vec<u64>> {
+    let mut encabezado = String::new();
+    lector.read_line(&mut encabezado).ok()?;
+    let mut campos = encabezado.trim_end().split(',');
+    if campos.next()? != tipo.como_str() {
+        return None;
+    }
+    let numeros = campos
+        .skip(1)
+        .map(|campo| campo.parse::<u64>().ok())
+        .collect::<Option<Vec<u64>>>()?;
+    let cantidad = match tipo {
+        TipoIndice::Hash => 3,
+        TipoIndice::Btree => 4,
+    };
+    if numeros.len() != cantidad {
+        return None;
+    }
+    Some(numeros)
+}
+
+// Separa una línea <posición de la fila>,<valor> de un índice.
+fn leer_entrada(linea: &str) -> Option<(u64, String)> {
+    let (posicion, valor) = linea.split_once(',')?;
+    Some((posicion.parse::<u64>().ok()?, valor.to_string()))
+}
+
+/*
+ * Lee las filas agregadas al final del índice, desde `fin`. Cada tanda de filas termina con una marca con la
+ * longitud de la tabla después de agregarlas; una tanda sin su marca quedó a medio escribir y no se tiene en
+ * cuenta. Devuelve None si el índice no está al día con la tabla.
+ */
+fn leer_agregadas(
+    lector: &mut BufReader<File>,
+    fin: u64,
+    longitud_inicial: u64,
+    longitud_tabla: u64,
+) -> Option<Vec<(u64, String)>> {
+    lector.seek(SeekFrom::Start(fin)).ok()?;
+    let mut agregadas = Vec::new();
+    let mut pendientes = Vec::new();
+    let mut longitud = longitud_inicial;
+    for linea in lector.lines() {
+        let linea = linea.ok()?;
+        match linea.strip_prefix('#') {
+            Some(marca) => {
+                longitud = marca.parse::<u64>().ok()?;
+                agregadas.append(&mut pendientes);
+            }
+            None => pendientes.push(leer_entrada(&linea)?),
+        }
+    }
+    (longitud == longitud_tabla).then_some(agregadas)
+}
+
+/*
+ * Devuelve las filas que tienen el valor según un índice HASH, con su posición. Devuelve None si el archivo
+ * del índice no existe, no se puede leer o no corresponde a la tabla.
+ */
+fn buscar_en_hash(
+    ruta_indice: &str,
+    valor: &str,
+    longitud_tabla: u64,
+) -> Option<Vec<(u64, String)>> {
+    let mut lector = BufReader::new(File::open(ruta_indice).ok()?);
+    let (longitud, cantidad, fin) = match leer_encabezado(&mut lector, TipoIndice::Hash)?[..] {
+        [longitud, cantidad, fin] if cantidad > 0 => (longitud, cantidad, fin),
+        _ => return None,
+    };
+
+    let inicio_directorio = lector.stream_position().ok()?;
+    let inicio_cubetas = inicio_directorio + (cantidad + 1) * ANCHO_ENTRADA;
+    let cubeta = hash_valor(valor) % cantidad;
+    lector
+        .seek(SeekFrom::Start(inicio_directorio + cubeta * ANCHO_ENTRADA))
+        .ok()?;
+    let desde = leer_numero(&mut lector, ANCHO_ENTRADA as usize)?;
+    let hasta = leer_numero(&mut lector, ANCHO_ENTRADA as usize)?;
+    lector.seek(SeekFrom::Start(inicio_cubetas + desde)).ok()?;
+    let mut contenido = vec![0; hasta.checked_sub(desde)? as usize];
+    lector.read_exact(&mut contenido).ok()?;
+
+    let mut entradas = Vec::new();
+    for linea in String::from_utf8(contenido).ok()?.lines() {
+        let entrada = leer_entrada(linea)?;
+        if entrada.1 == valor {
+            entradas.push(entrada);
+        }
+    }
+    let agregadas = leer_agregadas(&mut lector, fin, longitud, longitud_tabla)?;
+    entradas.extend(
+        agregadas
+            .into_iter()
+            .filter(|(_, agregado)| agregado == valor),
+    );
+    Some(entradas)
+}
+
+/*
+ * Devuelve las filas cuyo valor está en el rango según un índice BTREE, ordenadas por valor, junto con la
+ * cantidad de filas de la tabla. Con el nodo raíz se busca la primera página que puede tener valores del rango
+ * y desde ahí se leen las páginas en orden hasta pasar el final del rango.
+ */
+fn buscar_en_arbol(
+    ruta_indice: &str,
+    rango: &Rango,
+    longitud_tabla: u64,
+) -> Option<(Vec<(u64, String)>, u64)> {
+    let mut lector = BufReader::new(File::open(ruta_indice).ok()?);
+    let (longitud, filas, paginas, fin) = match leer_encabezado(&mut lector, TipoIndice::Btree)?[..]
+    {
+        [longitud, filas, paginas, fin] => (longitud, filas, paginas, fin),
+        _ => return None,
+    };
+
+    let mut raiz = Vec::new();
+    let mut linea = String::new();
+    for _ in 0..paginas {
+        linea.clear();
+        lector.read_line(&mut linea).ok()?;
+        raiz.push(leer_entrada(linea.trim_end_matches('\n'))?);
+    }
+    let inicio_paginas = lector.stream_position().ok()?;
+    let primera = match rango.desde {
+        Some((desde, _)) => raiz
+            .partition_point(|(_, primer_valor)| primer_valor.as_str() < desde)
+            .saturating_sub(1),
+        None => 0,
+    };
+    if let Some((posicion, _)) = raiz.get(primera) {
+        lector
+            .seek(SeekFrom::Start(inicio_paginas + posicion))
+            .ok()?;
+    }
+
+    let mut entradas = Vec::new();
+    let mut posicion = lector.stream_position().ok()?;
+    while posicion < fin {
+        linea.clear();
+        let leidos = lector.read_line(&mut linea).ok()?;
+        if leidos == 0 {
+            break;
+        }
+        posicion += leidos as u64;
+        let entrada = leer_entrada(linea.trim_end_matches('\n'))?;
+        if rango.pasa_el_final(&entrada.1) {
+            break;
+        }
+        if rango.contiene(&entrada.1) {
+            entradas.push(entrada);
+        }
+    }
+
+    // Las filas agregadas no están ordenadas, se ubican entre las de las páginas.
+    let agregadas = leer_agregadas(&mut lector, fin, longitud, longitud_tabla)?;
+    let filas = filas + agregadas.len() as u64;
+    let antes = entradas.len();
+    entradas.extend(
+        agregadas
+            .into_iter()
+            .filter(|(_, valor)| rango.contiene(valor)),
+    );
+    if entradas.len() > antes {
+        entradas.sort_by(|a, b| a.1.cmp(&b.1).then(a.0.cmp(&b.0)));
+    }
+    Some((entradas, filas))
+}
+
+/*
+ * Lee las filas de la tabla que empiezan en las posiciones de las entradas, en ese orden, y verifica que tengan
+ * el valor de la entrada en la columna. Si alguna no lo tiene el índice no corresponde a la tabla y se devuelve None.
+ */
+fn leer_filas(
+    ruta_tabla: &str,
+    entradas: &[(u64, String)],
+    indice_columna: usize,
+) -> Result<Option<Vec<Vec<String>>>, ErrorPrograma> {
+    let mut lector = BufReader::new(File::open(ruta_tabla)?);
+    let mut linea = String::new();
+    let mut filas = Vec::new();
+    for (posicion, valor) in entradas {
+        lector.seek(SeekFrom::Start(*posicion))?;
+        linea.clear();
+        lector.read_line(&mut linea)?;
+        let fila: Vec<String> = linea
+            .trim_end_matches(['\n', '\r'])
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .collect();
+        if fila.get(indice_columna) != Some(valor) {
+            return Ok(None);
+        }
+        filas.push(fila);
+    }
+    Ok(Some(filas))
+}
+
+/*
+ * Valores entre los que tiene que estar la columna, cada extremo con si se incluye o no. Los valores se comparan
+ * como strings, igual que al comparar una columna con un valor al recorrer la tabla.
+ */
+#[derive(Default)]
+struct Rango<'a> {
+    desde: Option<(&'a str, bool)>,
+    hasta: Option<(&'a str, bool)>,
+}
+
+impl<'a> Rango<'a> {
+    fn restringir_desde(&mut self, valor: &'a str, incluido: bool) {
+        match self.desde {
+            Some((desde, _)) if desde > valor => {}
+            Some((desde, false)) if desde == valor => {}
+            _ => self.desde = Some((valor, incluido)),
+        }
+    }
+
+    fn restringir_hasta(&mut self, valor: &'a str, incluido: bool) {
+        match self.hasta {
+            Some((hasta, _)) if hasta < valor => {}
+            Some((hasta, false)) if hasta == valor => {}
+            _ => self.hasta = Some((valor, incluido)),
+        }
+    }
+
+    fn restringir(&mut self, comparacion: &Comparacion, valor: &'a str) {
+        match comparacion {
+            Comparacion::Igual => {
+                self.restringir_desde(valor, true);
+                self.restringir_hasta(valor, true);
+            }
+            Comparacion::Mayor => self.restringir_desde(valor, false),
+            Comparacion::MayorIgual => self.restringir_desde(valor, true),
+            Comparacion::Menor => self.restringir_hasta(valor, false),
+            Comparacion::MenorIgual => self.restringir_hasta(valor, true),
+            Comparacion::Diferente => {}
+        }
+    }
+
+    fn describir(&self, columna: &str) -> String {
+        let mut partes = Vec::new();
+        if let Some((desde, incluido)) = self.desde {
+            let comparacion = if incluido { ">=" } else { ">" };
+            partes.push(format!("{} {} '{}'", columna, comparacion, desde));
+        }
+        if let Some((hasta, incluido)) = self.hasta {
+            let comparacion = if incluido { "<=" } else { "<" };
+            partes.push(format!("{} {} '{}'", columna, comparacion, hasta));
+        }
+        partes.join(" AND ")
+    }
+
+    fn esta_acotado(&self) -> bool {
+        self.desde.is_some() || self.hasta.is_some()
+    }
+
+    fn pasa_el_final(&self, valor: &str) -> bool {
+        match self.hasta {
+            Some((hasta, true)) => valor > hasta,
+            Some((hasta, false)) => valor >= hasta,
+            None => false,
+        }
+    }
+
+    fn contiene(&self, valor: &str) -> bool {
+        let desde_cumple = match self.desde {
+            Some((desde, true)) => valor >= desde,
+            Some((desde, false)) => valor > desde,
+            None => true,
+        };
+        desde_cumple && !self.pasa_el_final(valor)
+    }
+}
+
+/*
+ * Si la condición compara una columna con un valor que no es una columna, devuelve la columna, la comparación
+ * vista desde la columna y el valor sin comillas, igual que como se comparan al recorrer la tabla.
+ */
+fn condicion_con_valor<'a>(
+    clause: &'a WhereClause,
+    columnas_csv: &[String],
+) -> Option<(&'a str, Comparacion, &'a str)> {
+    if clause.es_not {
+        return None;
+    }
+    let valor1 = clause.valor1.trim_matches('\'');
+    let valor2 = clause.valor2.trim_matches('\'');
+    let es_columna = |valor: &str| columnas_csv.iter().any(|c| c == valor);
+    match (es_columna(valor1), es_columna(valor2)) {
+        (true, false) => Some((valor1, clause.comparacion, valor2)),
+        (false, true) => {
+            let comparacion = match clause.comparacion {
+                Comparacion::Mayor => Comparacion::Menor,
+                Comparacion::Menor => Comparacion::Mayor,
+                Comparacion::MayorIgual => Comparacion::MenorIgual,
+                Comparacion::MenorIgual => Comparacion::MayorIgual,
+                comparacion => comparacion,
+            };
+            Some((valor2, comparacion, valor1))
+        }
+        _ => None,
+    }
+}
+
+/*
+ * Devuelve las condiciones que comparan una columna con un valor, de las que toda fila del resultado tiene
+ * que cumplir. Si alguna puede dar error se devuelve una lista vacía.
+ */
+fn condiciones_para_indices<'a>(
+    condiciones: &[&'a WhereClause],
+    columnas_csv: &[String],
+) -> Vec<(&'a str, Comparacion, &'a str)> {
+    let sin_errores = condiciones.iter().all(|clause| {
+        valor_sin_error(&clause.valor1, columnas_csv)
+            && valor_sin_error(&clause.valor2, columnas_csv)
+    });
+    if !sin_errores {
+        return Vec::new();
+    }
+    condiciones
+        .iter()
+        .filter_map(|clause| condicion_con_valor(clause, columnas_csv))
+        .collect()
+}
+
+/*
+ * Forma elegida para leer una tabla con uno de sus índices: las posiciones de las filas que encontró el índice
+ * y si ya están en el orden que pide el ORDER BY.
+ */
+#[derive(Debug)]
+pub struct AccesoIndice {
+    pub indice: Indice,
+    // Condición o columna por la que se busca en el índice, para mostrarla en el plan.
+    pub busqueda: String,
+    pub ordenadas: bool,
+    entradas: Vec<(u64, String)>,
+}
+
+impl AccesoIndice {
+    fn new(
+        indice: &Indice,
+        busqueda: String,
+        mut entradas: Vec<(u64, String)>,
+        orden: Option<&OrderByClause>,
+    ) -> Self {
+        let ordenadas = orden.is_some();
+        match orden {
+            Some(orden) if orden.orden == Order::Desc => {
+                entradas.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)))
+            }
+            Some(_) => {}
+            None => entradas.sort(),
+        }
+        AccesoIndice {
+            indice: indice.clone(),
+            busqueda,
+            ordenadas,
+            entradas,
+        }
+    }
+
+    /*
+     * Lee de la tabla las filas que encontró el índice. Devuelve None si el índice no corresponde a la tabla,
+     * y entonces hay que recorrerla completa.
+     */
+    pub(crate) fn leer_filas(
+        &self,
+        ruta_tabla: &str,
+        columnas_csv: &[String],
+    ) -> Result<Option<Vec<Vec<String>>>, ErrorPrograma> {
+        let tabla = separar_ruta_tabla(ruta_tabla).1;
+        let indice_columna = columnas_csv
+            .iter()
+            .position(|c| nombra_columna(c, &self.indice, &tabla))
+            .unwrap_or(0);
+        leer_filas(ruta_tabla, &self.entradas, indice_columna)
+    }
+}
+
+/*
+ * Indica si la columna de la consulta es la del índice, sola o como <tabla>.<columna> en un JOIN.
+ */
+fn nombra_columna(columna: &str, indice: &Indice, tabla: &str) -> bool {
+    let sin_tabla = columna
+        .strip_prefix(tabla)
+        .and_then(|columna| columna.strip_prefix('.'));
+    columna == indice.columna || sin_tabla == Some(indice.columna.as_str())
+}
+
+/*
+ * Elige un índice de la tabla para buscar las filas que pueden cumplir el WHERE, a las que todavía hay que
+ * verificarles el WHERE completo. Se usa, en este orden:
+ * - un índice HASH sobre una columna que el WHERE compara por igualdad con un valor,
+ * - un índice BTREE sobre una columna que el WHERE acota, si el rango no abarca más de la mitad de las filas o si
+ *   el índice también da el orden del ORDER BY,
+ * - un índice BTREE sobre la columna del ORDER BY, que devuelve todas las filas ya ordenadas.
+ * Sin ORDER BY las filas quedan en el orden del archivo. Devuelve None si no hay un índice que sirva o si
+ * quedó desactualizado, y entonces la tabla se recorre completa.
+ */
+pub(crate) fn elegir_indice(
+    ruta_tabla: &str,
+    condiciones: &[&WhereClause],
+    order_by: Option<&[OrderByClause]>,
+    columnas_csv: &[String],
+) -> Result<Option<AccesoIndice>, ErrorPrograma> {
+    let esquema = Esquema::cargar(ruta_tabla)?;
+    if esquema.indices.is_empty() {
+        return Ok(None);
+    }
+    let longitud_tabla = metadata(ruta_tabla)?.len();
+    let tabla = separar_ruta_tabla(ruta_tabla).1;
+    let condiciones = condiciones_para_indices(condiciones, columnas_csv);
+    // Solo un ORDER BY de una columna se puede resolver con un índice.
+    let orden = match order_by {
+        Some([orden]) => Some(orden),
+        _ => None,
+    };
+
+    for (columna, _, valor) in condiciones
+        .iter()
+        .filter(|(_, comparacion, _)| *comparacion == Comparacion::Igual)
+    {
+        let indice = esquema
+            .indices
+            .iter()
+            .find(|i| nombra_columna(columna, i, &tabla) && i.tipo == TipoIndice::Hash);
+        if let Some(indice) = indice {
+            let ruta = ruta_indice(ruta_tabla, &indice.nombre);
+            if let Some(entradas) = buscar_en_hash(&ruta, valor, longitud_tabla) {
+                let busqueda = format!("{} = '{}'", columna, valor);
+                return Ok(Some(AccesoIndice::new(indice, busqueda, entradas, None)));
+            }
+        }
+    }
+
+    let arboles: Vec<&Indice> = esquema
+        .indices
+        .iter()
+        .filter(|i| i.tipo == TipoIndice::Btree)
+        .collect();
+    for indice in arboles.iter() {
+        let mut rango = Rango::default();
+        for (columna, comparacion, valor) in condiciones.iter() {
+            if nombra_columna(columna, indice, &tabla) {
+                rango.restringir(comparacion, valor);
+            }
+        }
+        if !rango.esta_acotado() {
+            continue;
+        }
+        let orden_del_indice = orden.filter(|orden| nombra_columna(&orden.columna, indice, &tabla));
+        let ruta = ruta_indice(ruta_tabla, &indice.nombre);
+        if let Some((entradas, filas)) = buscar_en_arbol(&ruta, &rango, longitud_tabla) {
+            // Si el rango abarca casi toda la tabla conviene recorrerla en orden.
+            if orden_del_indice.is_none() && entradas.len() as u64 * 2 > filas {
+                continue;
+            }
+            let busqueda = rango.describir(&indice.columna);
+            return Ok(Some(AccesoIndice::new(
+                indice,
+                busqueda,
+                entradas,
+                orden_del_indice,
+            )));
+        }
+    }
+
+    if let Some(orden) = orden {
+        if let Some(indice) = arboles
+            .iter()
+            .find(|i| nombra_columna(&orden.columna, i, &tabla))
+        {
+            let ruta = ruta_indice(ruta_tabla, &indice.nombre);
+            if let Some((entradas, _)) = buscar_en_arbol(&ruta, &Rango::default(), longitud_tabla) {
+                let busqueda = format!("ORDER BY {} {}", orden.columna, orden.orden.como_str());
+                return Ok(Some(AccesoIndice::new(
+                    indice,
+                    busqueda,
+                    entradas,
+                    Some(orden),
+                )));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/*
+ * Devuelve todos los índices declarados en las tablas del directorio, con la ruta del csv de su tabla.
+ */
+fn indices_del_directorio(directorio: &str) -> Result<Vec<(String, Indice)>, ErrorPrograma> {
+    let mut indices = Vec::new();
+    for tabla in tablas_del_directorio(directorio)? {
+        let ruta_tabla = format!("{}/{}.csv", directorio, tabla);
+        for indice in Esquema::cargar(&ruta_tabla)?.indices {
+            indices.push((ruta_tabla.clone(), indice));
+        }
+    }
+    Ok(indices)
+}
+
+/*
+ * Busca en el directorio el índice con ese nombre y devuelve la ruta del csv de su tabla.
+ */
+pub(crate) fn buscar_indice(
+    directorio: &str,
+    nombre: &str,
+) -> Result<Option<(String, Indice)>, ErrorPrograma> {
+    Ok(indices_del_directorio(directorio)?
+        .into_iter()
+        .find(|(_, indice)| indice.nombre == nombre))
+}
+
+/*
+ * Crea el índice con las filas actuales de la tabla y lo declara en su esquema. Los nombres de los índices
+ * son únicos en todo el directorio, así DROP INDEX los encuentra solo por su nombre.
+ */
+pub fn aplicar_create_index(ruta: &String, create: &CreateIndex) -> Result<(), ErrorPrograma> {
+    let _bloqueo = bloquear_tabla(ruta, ModoBloqueo::Exclusivo)?;
+    let (_, columnas_csv) = abrir_archivo(ruta)?;
+    verificar_existen_columnas_query(&[create.columna.to_string()], &columnas_csv)?;
+    let directorio = separar_ruta_tabla(ruta).0;
+    if let Some((ruta_existente, existente)) = buscar_indice(&directorio, &create.nombre)? {
+        let tabla = separar_ruta_tabla(&ruta_existente).1;
+        return Err(ErrorPrograma::desde_mensaje(
+            ErrorTipo::ConstraintViolation {
+                tabla: tabla.clone(),
+                columna: Some(existente.columna),
+                valor: None,
+            },
+            Mensaje::IndiceExistente,
+            &[&create.nombre, &tabla],
+        )
+        .con_token(&create.nombre));
+    }
+
+    let indice = Indice {
+        nombre: create.nombre.to_string(),
+        columna: create.columna.to_string(),
+        tipo: create.tipo,
+    };
+    let mut bitacora = Bitacora::iniciar(&directorio)?;
+    let temporal = construir_indice(ruta, Path::new(ruta), &indice)?;
+    bitacora.registrar_reemplazo(temporal, &ruta_indice(ruta, &indice.nombre))?;
+    // El esquema se publica en la misma bitácora que el índice. Queda bloqueado hasta entonces, así no se
+    // pierden los valores de auto increment que otro proceso reserve mientras tanto.
+    let _bloqueo_esquema = bloquear_esquema(ruta)?;
+    let mut esquema = Esquema::cargar(ruta)?;
+    esquema.indices.push(indice);
+    bitacora.registrar_reemplazo(esquema.escribir_temporal(ruta)?, &ruta_esquema(ruta))?;
+    bitacora.confirmar()?;
+    Ok(())
+}
+
+/*
+ * Quita el índice del esquema de su tabla y borra su archivo. Si la query indica la tabla el índice
+ * se busca solo en ella.
+ */
+pub fn aplicar_drop_index(directorio: &str, drop: &DropIndex) -> Result<(), ErrorPrograma> {
+    let indices = match drop.tabla {
+        Some(ref tabla) => {
+            let ruta_tabla = format!("{}/{}.csv", directorio, tabla);
+            abrir_archivo(&ruta_tabla)?;
+            Esquema::cargar(&ruta_tabla)?
+                .indices
+                .into_iter()
+                .map(|indice| (ruta_tabla.clone(), indice))
+                .collect()
+        }
+        None => indices_del_directorio(directorio)?,
+    };
+    let ruta_tabla = match indices
+        .iter()
+        .find(|(_, indice)| indice.nombre == drop.nombre)
+    {
+        Some((ruta_tabla, _)) => ruta_tabla,
+        None => {
+            let nombres = indices.iter().map(|(_, indice)| indice.nombre.as_str());
+            let sugerencia = sugerencia_mas_cercana(&drop.nombre, nombres);
+            return Err(ErrorPrograma::desde_mensaje(
+                ErrorTipo::UnknownIndex {
+                    indice: drop.nombre.to_string(),
+                },
+                Mensaje::IndiceInexistente,
+                &[&drop.nombre],
+            )
+            .con_token(&drop.nombre)
+            .con_sugerencia(sugerencia));
+        }
+    };
+
+    let _bloqueo = bloquear_tabla(ruta_tabla, ModoBloqueo::Exclusivo)?;
+    let mut esquema = Esquema::cargar(ruta_tabla)?;
+    esquema
+        .indices
+        .retain(|indice| indice.nombre != drop.nombre);
+    esquema.guardar(ruta_tabla)?;
+    match remove_file(ruta_indice(ruta_tabla, &drop.nombre)) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
@@ -1,3 +1,20 @@
+pub mod agregacion;
+pub mod archivo_temporal;
+pub mod bitacora;
+pub mod bloqueo;
 pub mod errors;
+pub mod esquema;
+pub mod indices;
+pub mod mensajes;
+pub mod orden_externo;
+pub mod plan;
+pub mod predicado;
 pub mod query_identifier;
 pub mod read_file;
+pub mod recorrido_paralelo;
+pub mod repl;
+pub mod restricciones;
+pub mod salida;
+pub mod script;
+pub mod sesion;
+pub mod sugerencias;
@@ -1,46 +1,262 @@
 use std::env;
 use std::error::Error;
-mod errors;
-mod query_identifier;
-mod read_file;
-use query_identifier::analisar_query;
+use std::fs::read_to_string;
+use std::io::{read_to_string as leer_entrada, sink, stderr, stdin, stdout, IsTerminal, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+use taller_tp_individual::bloqueo::configurar_espera_bloqueo;
+use taller_tp_individual::errors::{codigo_salida, ErrorPrograma, ErrorTipo};
+use taller_tp_individual::mensajes::{configurar_idioma, Idioma, Mensaje, VARIABLE_IDIOMA};
+use taller_tp_individual::orden_externo::configurar_memoria_orden;
+use taller_tp_individual::recorrido_paralelo::{
+    configurar_recorrido_paralelo, BYTES_POR_HILO_POR_DEFECTO,
+};
+use taller_tp_individual::repl::Repl;
+use taller_tp_individual::salida::{Formato, SalidaFormateada};
+use taller_tp_individual::script::ejecutar_script_con_registro;
+use taller_tp_individual::sesion::Sesion;
+
+// Variables de entorno con las que se ajusta la ejecución.
+const VARIABLE_ESPERA_BLOQUEO: &str = "SQL_RUSTICO_ESPERA_BLOQUEO_MS";
+const VARIABLE_MEMORIA_ORDEN: &str = "SQL_RUSTICO_MEMORIA_ORDEN_KB";
+const VARIABLE_HILOS: &str = "SQL_RUSTICO_HILOS";
+
+// Cuánto se informa por salida de error además de los errores de las consultas.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Verbosidad {
+    Silencioso,
+    Normal,
+    Detallado,
+}
 
 struct Comando {
     ruta: String,
-    query: String,
+    query: Option<String>,
+    // Archivo con un script de consultas, "-" para leerlo de la entrada estándar.
+    archivo: Option<String>,
+    continuar_con_error: bool,
+    formato: Formato,
+    verbosidad: Verbosidad,
+    // Idioma de los mensajes pedido con --lang, tiene prioridad sobre la variable de entorno.
+    idioma: Option<Idioma>,
 }
 
 impl Comando {
-    pub fn new(f: &str, q: &str) -> Self {
-        Comando {
-            ruta: f.to_string(),
-            query: q.to_string(),
+    /*
+     * Interpreta los argumentos: la ruta a las tablas, la consulta opcional
+     * y las opciones --file <script>, --continue-on-error, --format <formato>, --lang <idioma>, --quiet y --verbose.
+     */
+    pub fn desde_argumentos(argumentos: &[String]) -> Result<Self, Box<dyn Error>> {
+        let mut comando = Comando {
+            ruta: argumentos[0].to_string(),
+            query: None,
+            archivo: None,
+            continuar_con_error: false,
+            formato: Formato::Csv,
+            verbosidad: Verbosidad::Normal,
+            idioma: None,
+        };
+        let mut restantes = argumentos[1..].iter();
+        while let Some(argumento) = restantes.next() {
+            match argumento.as_str() {
+                "--file" | "-f" => match restantes.next() {
+                    Some(archivo) => comando.archivo = Some(archivo.to_string()),
+                    None => {
+                        return Err(Box::new(ErrorPrograma::desde_mensaje(
+                            ErrorTipo::Syntax,
+                            Mensaje::FaltaRutaScript,
+                            &[],
+                        )))
+                    }
+                },
+                "--format" => match restantes.next() {
+                    Some(formato) => comando.formato = Formato::desde_str(formato)?,
+                    None => {
+                        return Err(Box::new(ErrorPrograma::desde_mensaje(
+                            ErrorTipo::Syntax,
+                            Mensaje::FaltaFormato,
+                            &[],
+                        )))
+                    }
+                },
+                "--lang" => match restantes.next() {
+                    Some(idioma) => comando.idioma = Some(Idioma::desde_str(idioma)?),
+                    None => {
+                        return Err(Box::new(ErrorPrograma::desde_mensaje(
+                            ErrorTipo::Syntax,
+                            Mensaje::FaltaIdioma,
+                            &[],
+                        )))
+                    }
+                },
+                "--continue-on-error" => comando.continuar_con_error = true,
+                "--quiet" | "-q" => comando.verbosidad = Verbosidad::Silencioso,
+                "--verbose" | "-v" => comando.verbosidad = Verbosidad::Detallado,
+                _ if comando.query.is_none() => comando.query = Some(argumento.to_string()),
+                _ => {
+                    return Err(Box::new(ErrorPrograma::desde_mensaje(
+                        ErrorTipo::Syntax,
+                        Mensaje::ArgumentoInvalido,
+                        &[argumento],
+                    )));
+                }
+            }
         }
+        Ok(comando)
     }
 }
 
-pub fn ejecutar(ruta: &String, query: &str) -> Result<(), Box<dyn Error>> {
-    let resultado = analisar_query(ruta, query);
+/*
+ * Muestra el error por salida de error y devuelve el código de salida que le corresponde.
+ */
+fn fallar(error: Box<dyn Error>) -> ExitCode {
+    eprintln!("{:#}", error);
+    ExitCode::from(codigo_salida(&*error))
+}
+
+/*
+ * Ejecuta las consultas separadas por ';' en una misma sesión, así una transacción puede abarcar varias.
+ * Si una consulta falla no se ejecutan las siguientes, salvo con --continue-on-error, y la transacción abierta
+ * se descarta. Los errores se muestran por salida de error para no mezclarlos con las filas de los resultados;
+ * en un script se indica la línea de cada consulta con error y al final se muestra un resumen.
+ * El programa termina con el código del primer error.
+ */
+fn ejecutar(comando: &Comando, consultas: &str, es_script: bool) -> ExitCode {
+    let mut sesion = Sesion::new(&comando.ruta);
+    let mut destino = SalidaFormateada::new(comando.formato, stdout());
+    let mut registro: Box<dyn Write> = match comando.verbosidad {
+        Verbosidad::Detallado => Box::new(stderr()),
+        _ => Box::new(sink()),
+    };
+    let resumen = ejecutar_script_con_registro(
+        &mut sesion,
+        consultas,
+        comando.continuar_con_error,
+        &mut destino,
+        &mut registro,
+    );
+
+    for error in resumen.errores.iter() {
+        if es_script {
+            eprintln!("{:#}", error);
+        } else {
+            eprintln!("{:#}", error.error);
+        }
+    }
+    if es_script && comando.verbosidad != Verbosidad::Silencioso {
+        eprintln!("{}", resumen);
+    }
+    match resumen.errores.first() {
+        Some(error) => ExitCode::from(codigo_salida(&*error.error)),
+        None => ExitCode::SUCCESS,
+    }
+}
 
-    if let Err(descripcion_error) = resultado {
-        println!("{}", descripcion_error);
-        return Err(descripcion_error);
+/*
+ * Abre el intérprete interactivo sobre el directorio de tablas. El historial de consultas
+ * se guarda en ~/.sql_rustico_historial entre sesiones.
+ */
+fn ejecutar_interactivo(comando: &Comando) -> Result<(), Box<dyn Error>> {
+    if comando.verbosidad != Verbosidad::Silencioso {
+        eprintln!("{}", Mensaje::BienvenidaInterprete.texto(&[]));
     }
+    let mut repl = Repl::new(&comando.ruta, stdout()).con_formato(comando.formato);
+    if let Some(home) = env::var_os("HOME") {
+        repl = repl.con_historial(PathBuf::from(home).join(".sql_rustico_historial"));
+    }
+    repl.ejecutar(stdin().lock())
+}
 
-    Ok(())
+fn leer_script(archivo: &str) -> Result<String, Box<dyn Error>> {
+    if archivo == "-" {
+        Ok(leer_entrada(stdin())?)
+    } else {
+        Ok(read_to_string(archivo)?)
+    }
 }
 
-pub fn main() -> Result<(), Box<dyn Error>> {
+pub fn main() -> ExitCode {
     let argumentos: Vec<String> = env::args().collect();
 
-    if argumentos.len() < 3 {
-        println!(
-            "El comando ingresado no es correcto, recuerde que debe tener el siguiente formato:"
-        );
-        println!("cargo run -- ruta/a/tablas \"<consulta>\"");
-        return Ok(());
+    // El idioma de la variable de entorno se aplica antes de leer los argumentos, así sus errores ya lo usan.
+    let idioma_entorno = env::var(VARIABLE_IDIOMA)
+        .ok()
+        .map(|idioma| Idioma::desde_str(&idioma));
+    if let Some(Ok(idioma)) = idioma_entorno {
+        configurar_idioma(idioma);
     }
 
-    let comando = Comando::new(&argumentos[1], &argumentos[2]);
-    ejecutar(&comando.ruta, &comando.query)
+    if argumentos.len() < 2 {
+        eprintln!("{}", Mensaje::Uso.texto(&[]));
+        return ExitCode::from(ErrorTipo::Syntax.codigo_salida());
+    }
+    let comando = match Comando::desde_argumentos(&argumentos[1..]) {
+        Ok(comando) => comando,
+        Err(error) => return fallar(error),
+    };
+    if let Some(idioma) = comando.idioma {
+        configurar_idioma(idioma);
+    }
+    if let Some(Err(error)) = idioma_entorno {
+        if comando.verbosidad != Verbosidad::Silencioso {
+            eprintln!("{}: {}", VARIABLE_IDIOMA, error);
+        }
+    }
+
+    // El tiempo de espera por el bloqueo de una tabla se puede cambiar con una variable de entorno.
+    if let Ok(espera) = env::var(VARIABLE_ESPERA_BLOQUEO) {
+        match espera.parse::<u64>() {
+            Ok(espera) => configurar_espera_bloqueo(Duration::from_millis(espera)),
+            Err(_) if comando.verbosidad != Verbosidad::Silencioso => {
+                let mensaje = Mensaje::EsperaBloqueoInvalida;
+                eprintln!("{}", mensaje.texto(&[VARIABLE_ESPERA_BLOQUEO]))
+            }
+            Err(_) => {}
+        }
+    }
+    // La memoria que puede ocupar un ORDER BY antes de ordenar por partes en disco, en kilobytes.
+    if let Ok(memoria) = env::var(VARIABLE_MEMORIA_ORDEN) {
+        match memoria.parse::<u64>() {
+            Ok(memoria) => configurar_memoria_orden(memoria.saturating_mul(1024)),
+            Err(_) if comando.verbosidad != Verbosidad::Silencioso => {
+                let mensaje = Mensaje::MemoriaOrdenInvalida;
+                eprintln!("{}", mensaje.texto(&[VARIABLE_MEMORIA_ORDEN]))
+            }
+            Err(_) => {}
+        }
+    }
+
+    // La cantidad máxima de hilos con los que se recorre una tabla grande.
+    if let Ok(hilos) = env::var(VARIABLE_HILOS) {
+        match hilos.parse::<usize>() {
+            Ok(hilos) => configurar_recorrido_paralelo(hilos, BYTES_POR_HILO_POR_DEFECTO),
+            Err(_) if comando.verbosidad != Verbosidad::Silencioso => {
+                eprintln!("{}", Mensaje::HilosInvalidos.texto(&[VARIABLE_HILOS]))
+            }
+            Err(_) => {}
+        }
+    }
+
+    match (&comando.query, &comando.archivo) {
+        (Some(query), None) => ejecutar(&comando, query, false),
+        (None, Some(archivo)) => match leer_script(archivo) {
+            Ok(script) => ejecutar(&comando, &script, true),
+            Err(error) => fallar(error),
+        },
+        // Si la entrada no es una terminal se ejecuta como script en vez de abrir el intérprete.
+        (None, None) if !stdin().is_terminal() => match leer_script("-") {
+            Ok(script) => ejecutar(&comando, &script, true),
+            Err(error) => fallar(error),
+        },
+        (None, None) => match ejecutar_interactivo(&comando) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(error) => fallar(error),
+        },
+        (Some(_), Some(_)) => fallar(Box::new(ErrorPrograma::desde_mensaje(
+            ErrorTipo::Syntax,
+            Mensaje::ConsultaYScript,
+            &[],
+        ))),
+    }
 }
@@ -0,0 +1,846 @@
+use crate::errors::{ErrorPrograma, ErrorTipo};
+use std::error::Error;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+// Variable de entorno con la que se elige el idioma de los mensajes desde la línea de comandos.
+pub const VARIABLE_IDIOMA: &str = "SQL_RUSTICO_IDIOMA";
+
+static IDIOMA_ACTUAL: AtomicU8 = AtomicU8::new(Idioma::Espanol as u8);
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Idioma {
+    #[default]
+    Espanol,
+    Ingles,
+}
+
+impl Idioma {
+    /*
+     * Interpreta el código del idioma: es o en, también con región como es-AR o en_US.
+     */
+    pub fn desde_str(idioma: &str) -> Result<Self, Box<dyn Error>> {
+        let codigo = idioma.split(['-', '_']).next().unwrap_or("");
+        match codigo.to_lowercase().as_str() {
+            "es" => Ok(Idioma::Espanol),
+            "en" => Ok(Idioma::Ingles),
+            _ => Err(Box::new(ErrorPrograma::desde_mensaje(
+                ErrorTipo::Syntax,
+                Mensaje::IdiomaInvalido,
+                &[idioma],
+            ))),
+        }
+    }
+}
+
+/*
+ * Cambia el idioma de los mensajes de error de todas las consultas siguientes. Por defecto es español.
+ */
+pub fn configurar_idioma(idioma: Idioma) {
+    IDIOMA_ACTUAL.store(idioma as u8, Ordering::Relaxed);
+}
+
+pub fn idioma_actual() -> Idioma {
+    match IDIOMA_ACTUAL.load(Ordering::Relaxed) {
+        valor if valor == Idioma::Ingles as u8 => Idioma::Ingles,
+        _ => Idioma::Espanol,
+    }
+}
+
+/*
+ * Mensajes del programa. El valor de cada uno es su código, que no cambia con el idioma: las centenas indican
+ * el tipo de error (1 sintaxis, 2 tabla o índice, 3 columna, 4 tipo, 5 restricción, 6 bloqueo, 7 csv) y el 9 los textos
+ * que acompañan a los errores y los demás textos del programa.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mensaje {
+    InsertTablaInvalida = 101,
+    InsertColumnasSinCerrar = 102,
+    InsertSinValues = 103,
+    InsertSinValores = 104,
+    ReturningInvalido = 105,
+    ArchivoSalidaInvalido = 106,
+    CopyToInvalido = 107,
+    CopyFromInvalido = 108,
+    DelimitadorInvalido = 109,
+    WhereSinValor = 110,
+    WhereSinComparacion = 111,
+    ComparacionInvalida = 112,
+    OperadorLogicoInvalido = 113,
+    SinWhere = 114,
+    SinOrderBy = 115,
+    OrderBySinColumna = 116,
+    SelectSinTabla = 117,
+    SelectSinFrom = 118,
+    UpdateSinSet = 119,
+    DeleteSinWhere = 120,
+    DeleteSinFrom = 121,
+    AlterSeEsperaba = 122,
+    AlterSinNombre = 123,
+    AlterOnInvalido = 124,
+    AlterSinDefault = 125,
+    AlterColumnInvalido = 126,
+    AlterAccionInvalida = 127,
+    AlterValoresDeMas = 128,
+    QueryDesconocida = 129,
+    AsteriscoConColumnas = 130,
+    SetInvalido = 131,
+    DeleteSinWhereClause = 132,
+    CantidadValoresFila = 133,
+    CantidadValoresSelect = 134,
+    ColumnaRepetidaInsert = 135,
+    ColumnaRepetidaCopy = 136,
+    CopyHeaderConColumnas = 137,
+    AccionReferencialInvalida = 138,
+    LineaEsquemaInvalida = 139,
+    TransaccionAnidada = 140,
+    CommitSinTransaccion = 141,
+    RollbackSinTransaccion = 142,
+    FormatoInvalido = 143,
+    HistorialSinConsulta = 144,
+    ComandoDesconocido = 145,
+    FaltaRutaScript = 146,
+    FaltaFormato = 147,
+    ArgumentoInvalido = 148,
+    ConsultaYScript = 149,
+    FaltaIdioma = 150,
+    IdiomaInvalido = 151,
+    CreateIndexInvalido = 152,
+    DropIndexInvalido = 153,
+    NombreIndiceInvalido = 154,
+    TipoIndiceInvalido = 155,
+    ExplainInvalido = 156,
+    JoinInvalido = 157,
+    JoinTablaRepetida = 158,
+    LimitInvalido = 159,
+    GroupByInvalido = 160,
+    AgregacionInvalida = 161,
+    ColumnaFueraDeGroupBy = 162,
+    TablaInexistente = 201,
+    IndiceInexistente = 202,
+    ColumnaInexistente = 301,
+    ColumnasWhereInexistentes = 302,
+    ColumnaOrderByInexistente = 303,
+    ColumnaNoEncontrada = 304,
+    ColumnaSetInexistente = 305,
+    ColumnaForeignKeyInexistente = 306,
+    ColumnaAmbigua = 307,
+    EnteroPositivo = 401,
+    AutoIncrementNoEntero = 402,
+    AgregacionNoEntera = 403,
+    ValorNoGuardable = 404,
+    ValorReferenciadoInexistente = 501,
+    ValorReferenciado = 502,
+    ForeignKeysEnCiclo = 503,
+    ValorReferenciadoRepetido = 504,
+    ForeignKeyNuevaInvalida = 505,
+    ForeignKeyPropia = 506,
+    ArchivoModificadoDosVeces = 507,
+    IndiceExistente = 508,
+    TablaEnLectura = 601,
+    TablaOcupada = 602,
+    TransaccionEnConflicto = 603,
+    CsvVacio = 701,
+    ImportacionFallida = 702,
+    ImportacionLinea = 703,
+    ImportacionMasFilas = 704,
+    ImportacionCantidadValores = 705,
+    ImportacionValorNoGuardable = 706,
+    ImportacionNoEntero = 707,
+    ImportacionAutoIncrement = 708,
+    ImportacionComillasSinCerrar = 709,
+    ImportacionSinEncabezado = 710,
+    TablasConFilasInvalidas = 711,
+    TablaSinEncabezado = 712,
+    LineaConOtraCantidad = 713,
+    DiagnosticoUbicacion = 901,
+    DiagnosticoEsperados = 902,
+    DiagnosticoSugerencia = 903,
+    Sugerencia = 904,
+    Alternativas = 905,
+    ErrorEnScript = 906,
+    ErrorEnScriptDetallado = 907,
+    ResumenScript = 908,
+    RegistroConsulta = 909,
+    RegistroCorrecta = 910,
+    RegistroFallida = 911,
+    Uso = 912,
+    BienvenidaInterprete = 913,
+    AyudaInterprete = 914,
+    TiempoConsulta = 915,
+    EsperaBloqueoInvalida = 916,
+    MemoriaOrdenInvalida = 917,
+    HilosInvalidos = 918,
+    PlanRecorridoIndice = 919,
+    PlanRecorridoParalelo = 920,
+    PlanRecorridoSecuencial = 921,
+    PlanColumnas = 922,
+    PlanFiltroRecorrido = 923,
+    PlanSinFilas = 924,
+    PlanFiltro = 925,
+    PlanJoin = 926,
+    PlanAgregacion = 927,
+    PlanGrupos = 928,
+    PlanOrden = 929,
+    PlanProyeccion = 930,
+    PlanLimite = 931,
+    PlanMetricas = 932,
+    PlanTiempoTotal = 933,
+}
+
+impl Mensaje {
+    // Todos los mensajes del catálogo, en orden de código.
+    pub const TODOS: [Mensaje; 132] = [
+        Mensaje::InsertTablaInvalida,
+        Mensaje::InsertColumnasSinCerrar,
+        Mensaje::InsertSinValues,
+        Mensaje::InsertSinValores,
+        Mensaje::ReturningInvalido,
+        Mensaje::ArchivoSalidaInvalido,
+        Mensaje::CopyToInvalido,
+        Mensaje::CopyFromInvalido,
+        Mensaje::DelimitadorInvalido,
+        Mensaje::WhereSinValor,
+        Mensaje::WhereSinComparacion,
+        Mensaje::ComparacionInvalida,
+        Mensaje::OperadorLogicoInvalido,
+        Mensaje::SinWhere,
+        Mensaje::SinOrderBy,
+        Mensaje::OrderBySinColumna,
+        Mensaje::SelectSinTabla,
+        Mensaje::SelectSinFrom,
+        Mensaje::UpdateSinSet,
+        Mensaje::DeleteSinWhere,
+        Mensaje::DeleteSinFrom,
+        Mensaje::AlterSeEsperaba,
+        Mensaje::AlterSinNombre,
+        Mensaje::AlterOnInvalido,
+        Mensaje::AlterSinDefault,
+        Mensaje::AlterColumnInvalido,
+        Mensaje::AlterAccionInvalida,
+        Mensaje::AlterValoresDeMas,
+        Mensaje::QueryDesconocida,
+        Mensaje::AsteriscoConColumnas,
+        Mensaje::SetInvalido,
+        Mensaje::DeleteSinWhereClause,
+        Mensaje::CantidadValoresFila,
+        Mensaje::CantidadValoresSelect,
+        Mensaje::ColumnaRepetidaInsert,
+        Mensaje::ColumnaRepetidaCopy,
+        Mensaje::CopyHeaderConColumnas,
+        Mensaje::AccionReferencialInvalida,
+        Mensaje::LineaEsquemaInvalida,
+        Mensaje::TransaccionAnidada,
+        Mensaje::CommitSinTransaccion,
+        Mensaje::RollbackSinTransaccion,
+        Mensaje::FormatoInvalido,
+        Mensaje::HistorialSinConsulta,
+        Mensaje::ComandoDesconocido,
+        Mensaje::FaltaRutaScript,
+        Mensaje::FaltaFormato,
+        Mensaje::ArgumentoInvalido,
+        Mensaje::ConsultaYScript,
+        Mensaje::FaltaIdioma,
+        Mensaje::IdiomaInvalido,
+        Mensaje::CreateIndexInvalido,
+        Mensaje::DropIndexInvalido,
+        Mensaje::NombreIndiceInvalido,
+        Mensaje::TipoIndiceInvalido,
+        Mensaje::ExplainInvalido,
+        Mensaje::JoinInvalido,
+        Mensaje::JoinTablaRepetida,
+        Mensaje::LimitInvalido,
+        Mensaje::GroupByInvalido,
+        Mensaje::AgregacionInvalida,
+        Mensaje::ColumnaFueraDeGroupBy,
+        Mensaje::TablaInexistente,
+        Mensaje::IndiceInexistente,
+        Mensaje::ColumnaInexistente,
+        Mensaje::ColumnasWhereInexistentes,
+        Mensaje::ColumnaOrderByInexistente,
+        Mensaje::ColumnaNoEncontrada,
+        Mensaje::ColumnaSetInexistente,
+        Mensaje::ColumnaForeignKeyInexistente,
+        Mensaje::ColumnaAmbigua,
+        Mensaje::EnteroPositivo,
+        Mensaje::AutoIncrementNoEntero,
+        Mensaje::AgregacionNoEntera,
+        Mensaje::ValorNoGuardable,
+        Mensaje::ValorReferenciadoInexistente,
+        Mensaje::ValorReferenciado,
+        Mensaje::ForeignKeysEnCiclo,
+        Mensaje::ValorReferenciadoRepetido,
+        Mensaje::ForeignKeyNuevaInvalida,
+        Mensaje::ForeignKeyPropia,
+        Mensaje::ArchivoModificadoDosVeces,
+        Mensaje::IndiceExistente,
+        Mensaje::TablaEnLectura,
+        Mensaje::TablaOcupada,
+        Mensaje::TransaccionEnConflicto,
+        Mensaje::CsvVacio,
+        Mensaje::ImportacionFallida,
+        Mensaje::ImportacionLinea,
+        Mensaje::ImportacionMasFilas,
+        Mensaje::ImportacionCantidadValores,
+        Mensaje::ImportacionValorNoGuardable,
+        Mensaje::ImportacionNoEntero,
+        Mensaje::ImportacionAutoIncrement,
+        Mensaje::ImportacionComillasSinCerrar,
+        Mensaje::ImportacionSinEncabezado,
+        Mensaje::TablasConFilasInvalidas,
+        Mensaje::TablaSinEncabezado,
+        Mensaje::LineaConOtraCantidad,
+        Mensaje::DiagnosticoUbicacion,
+        Mensaje::DiagnosticoEsperados,
+        Mensaje::DiagnosticoSugerencia,
+        Mensaje::Sugerencia,
+        Mensaje::Alternativas,
+        Mensaje::ErrorEnScript,
+        Mensaje::ErrorEnScriptDetallado,
+        Mensaje::ResumenScript,
+        Mensaje::RegistroConsulta,
+        Mensaje::RegistroCorrecta,
+        Mensaje::RegistroFallida,
+        Mensaje::Uso,
+        Mensaje::BienvenidaInterprete,
+        Mensaje::AyudaInterprete,
+        Mensaje::TiempoConsulta,
+        Mensaje::EsperaBloqueoInvalida,
+        Mensaje::MemoriaOrdenInvalida,
+        Mensaje::HilosInvalidos,
+        Mensaje::PlanRecorridoIndice,
+        Mensaje::PlanRecorridoParalelo,
+        Mensaje::PlanRecorridoSecuencial,
+        Mensaje::PlanColumnas,
+        Mensaje::PlanFiltroRecorrido,
+        Mensaje::PlanSinFilas,
+        Mensaje::PlanFiltro,
+        Mensaje::PlanJoin,
+        Mensaje::PlanAgregacion,
+        Mensaje::PlanGrupos,
+        Mensaje::PlanOrden,
+        Mensaje::PlanProyeccion,
+        Mensaje::PlanLimite,
+        Mensaje::PlanMetricas,
+        Mensaje::PlanTiempoTotal,
+    ];
+
+    pub fn codigo(&self) -> u16 {
+        *self as u16
+    }
+
+    /*
+     * Texto del mensaje en español y en inglés. Cada {} se reemplaza, en orden, por uno de los datos del mensaje.
+     */
+    fn plantillas(&self) -> (&'static str, &'static str) {
+        match self {
+            Mensaje::InsertTablaInvalida => (
+                "El nombre de tabla '{}' no es válido, revise si las columnas de la tabla donde insertará se encuentran entre parentesís.",
+                "The table name '{}' is not valid, check that the columns of the table to insert into are enclosed in parentheses.",
+            ),
+            Mensaje::InsertColumnasSinCerrar => (
+                "Revise si las columnas de la tabla donde insertará se encuentran entre parentesís.",
+                "Check that the columns of the table to insert into are enclosed in parentheses.",
+            ),
+            Mensaje::InsertSinValues => (
+                "No se pudo encontrar VALUES en la query.",
+                "VALUES was not found in the query.",
+            ),
+            Mensaje::InsertSinValores => (
+                "No se encontraron valores entre paréntesis luego de VALUES.",
+                "No values in parentheses were found after VALUES.",
+            ),
+            Mensaje::ReturningInvalido => (
+                "La cláusula RETURNING no es válida, la sintaxis válida es: RETURNING * o RETURNING <columnas>.",
+                "The RETURNING clause is not valid, the valid syntax is: RETURNING * or RETURNING <columns>.",
+            ),
+            Mensaje::ArchivoSalidaInvalido => (
+                "El archivo de salida no es válido, la sintaxis válida es: SELECT ... INTO OUTFILE '<archivo>' [FORMAT <formato>] o COPY (SELECT ...) TO '<archivo>' [FORMAT <formato>].",
+                "The output file is not valid, the valid syntax is: SELECT ... INTO OUTFILE '<file>' [FORMAT <format>] or COPY (SELECT ...) TO '<file>' [FORMAT <format>].",
+            ),
+            Mensaje::CopyToInvalido => (
+                "La query COPY no es válida, la sintaxis válida es: COPY (SELECT ...) TO '<archivo>' [FORMAT <formato>].",
+                "The COPY query is not valid, the valid syntax is: COPY (SELECT ...) TO '<file>' [FORMAT <format>].",
+            ),
+            Mensaje::CopyFromInvalido => (
+                "La query COPY no es válida, la sintaxis válida es: COPY <tabla> [(<columnas>)] FROM '<archivo>' [WITH (HEADER, DELIMITER '<caracter>')].",
+                "The COPY query is not valid, the valid syntax is: COPY <table> [(<columns>)] FROM '<file>' [WITH (HEADER, DELIMITER '<character>')].",
+            ),
+            Mensaje::DelimitadorInvalido => (
+                "El DELIMITER debe ser un único caracter entre comillas simples, por ejemplo DELIMITER ';'.",
+                "The DELIMITER must be a single character in single quotes, for example DELIMITER ';'.",
+            ),
+            Mensaje::WhereSinValor => (
+                "No se encontró un valor en la cláusula WHERE, recuerde que la clause debe ser \"valor1 comparacion valor2\".",
+                "A value was not found in the WHERE clause, remember that the clause must be \"value1 comparison value2\".",
+            ),
+            Mensaje::WhereSinComparacion => (
+                "No se encontró una comparación en la cláusula WHERE, recuerde que la clause debe ser \"valor1 comparacion valor2\".",
+                "A comparison was not found in the WHERE clause, remember that the clause must be \"value1 comparison value2\".",
+            ),
+            Mensaje::ComparacionInvalida => (
+                "El tipo de comparación ingresado no es válido, recuerde que estos pueden ser: =, !=, <, >, <= o >=.",
+                "The comparison is not valid, remember that it can be: =, !=, <, >, <= or >=.",
+            ),
+            Mensaje::OperadorLogicoInvalido => (
+                "El operador lógico ingresado no es válido o la sintaxis fue inválida, los operadores que se pueden manejar son AND, OR y NOT.",
+                "The logical operator is not valid or the syntax was invalid, the supported operators are AND, OR and NOT.",
+            ),
+            Mensaje::SinWhere => (
+                "No se encontro WHERE en la query, revise si esta en minúscula.",
+                "WHERE was not found in the query, check whether it is in lowercase.",
+            ),
+            Mensaje::SinOrderBy => (
+                "No se encontró ORDER BY dentro de la query.",
+                "ORDER BY was not found in the query.",
+            ),
+            Mensaje::OrderBySinColumna => (
+                "La columna ingresada en el ORDER BY no fue válida o no se encontró.",
+                "The column in the ORDER BY is not valid or was not found.",
+            ),
+            Mensaje::SelectSinTabla => (
+                "No se encontro el nombre de la tabla, la sintaxis válidad es: SELECT <columnas> FROM <tabla>.",
+                "The table name was not found, the valid syntax is: SELECT <columns> FROM <table>.",
+            ),
+            Mensaje::SelectSinFrom => (
+                "No se encontró el FROM dentro de la query, la sintaxis válida es: SELECT <columnas> FROM <tabla>.",
+                "FROM was not found in the query, the valid syntax is: SELECT <columns> FROM <table>.",
+            ),
+            Mensaje::UpdateSinSet => (
+                "No se encontró el SET dentro de la query, la sintaxis válida es: UPDATE FROM <tabla> WHERE <condiciones>.",
+                "SET was not found in the query, the valid syntax is: UPDATE <table> SET <values> WHERE <conditions>.",
+            ),
+            Mensaje::DeleteSinWhere => (
+                "No se encontró WHERE dentro de la query, la sintaxis válida es: DELETE FROM <tabla> WHERE <condición>.",
+                "WHERE was not found in the query, the valid syntax is: DELETE FROM <table> WHERE <condition>.",
+            ),
+            Mensaje::DeleteSinFrom => (
+                "No se encontró DELETE FROM dentro de la query, la sintaxis válida es: SELECT <columnas> FROM <tabla>.",
+                "DELETE FROM was not found in the query, the valid syntax is: DELETE FROM <table> WHERE <condition>.",
+            ),
+            Mensaje::AlterSeEsperaba => (
+                "Se esperaba {} en la query, la sintaxis válida es: ALTER TABLE <tabla> ADD FOREIGN KEY (<columna>) REFERENCES <tabla> (<columna>) [ON DELETE <acción>] [ON UPDATE <acción>].",
+                "Expected {} in the query, the valid syntax is: ALTER TABLE <table> ADD FOREIGN KEY (<column>) REFERENCES <table> (<column>) [ON DELETE <action>] [ON UPDATE <action>].",
+            ),
+            Mensaje::AlterSinNombre => (
+                "No se encontró el nombre de una tabla o columna en la query ALTER TABLE.",
+                "A table or column name was not found in the ALTER TABLE query.",
+            ),
+            Mensaje::AlterOnInvalido => (
+                "Luego de ON se esperaba DELETE o UPDATE en la foreign key.",
+                "DELETE or UPDATE was expected after ON in the foreign key.",
+            ),
+            Mensaje::AlterSinDefault => (
+                "No se encontró el valor luego de SET DEFAULT.",
+                "The value after SET DEFAULT was not found.",
+            ),
+            Mensaje::AlterColumnInvalido => (
+                "Luego de ALTER COLUMN <columna> se esperaba SET AUTO_INCREMENT, SET SERIAL, SET DEFAULT <valor> o DROP DEFAULT.",
+                "SET AUTO_INCREMENT, SET SERIAL, SET DEFAULT <value> or DROP DEFAULT was expected after ALTER COLUMN <column>.",
+            ),
+            Mensaje::AlterAccionInvalida => (
+                "La acción de ALTER TABLE no es válida, la sintaxis válida es: ALTER TABLE <tabla> ADD FOREIGN KEY ... o ALTER TABLE <tabla> ALTER COLUMN <columna> SET ...",
+                "The ALTER TABLE action is not valid, the valid syntax is: ALTER TABLE <table> ADD FOREIGN KEY ... or ALTER TABLE <table> ALTER COLUMN <column> SET ...",
+            ),
+            Mensaje::AlterValoresDeMas => (
+                "La query ALTER TABLE tiene valores de más al final.",
+                "The ALTER TABLE query has extra values at the end.",
+            ),
+            Mensaje::QueryDesconocida => (
+                "La query ingresada es desconocida, no corresponde con SELECT, INSERT, UPDATE, DELETE, ALTER TABLE, CREATE INDEX, DROP INDEX, COPY, EXPLAIN o RECOVER.",
+                "The query is unknown, it is not a SELECT, INSERT, UPDATE, DELETE, ALTER TABLE, CREATE INDEX, DROP INDEX, COPY, EXPLAIN or RECOVER.",
+            ),
+            Mensaje::AsteriscoConColumnas => (
+                "No se puede seleccionar todas las columnas '*' y otras columnas al mismo tiempo.",
+                "All columns '*' and other columns cannot be selected at the same time.",
+            ),
+            Mensaje::SetInvalido => (
+                "La sintaxis es inválida en la SET clause.",
+                "The syntax of the SET clause is invalid.",
+            ),
+            Mensaje::DeleteSinWhereClause => (
+                "No hay una WHERE clause en la consulta DELETE.",
+                "The DELETE query has no WHERE clause.",
+            ),
+            Mensaje::CantidadValoresFila => (
+                "La fila {} de VALUES tiene {} valores pero se indicaron {} columnas.",
+                "Row {} of VALUES has {} values but {} columns were given.",
+            ),
+            Mensaje::CantidadValoresSelect => (
+                "El SELECT tiene {} valores pero se indicaron {} columnas.",
+                "The SELECT has {} values but {} columns were given.",
+            ),
+            Mensaje::ColumnaRepetidaInsert => (
+                "La columna {} está repetida en el INSERT.",
+                "The column {} is repeated in the INSERT.",
+            ),
+            Mensaje::ColumnaRepetidaCopy => (
+                "La columna {} está repetida en las columnas del COPY.",
+                "The column {} is repeated in the COPY columns.",
+            ),
+            Mensaje::CopyHeaderConColumnas => (
+                "Con HEADER las columnas se toman del encabezado del archivo, no se pueden indicar en la query.",
+                "With HEADER the columns are taken from the file header, they cannot be given in the query.",
+            ),
+            Mensaje::AccionReferencialInvalida => (
+                "La acción referencial '{}' no es válida, las acciones posibles son RESTRICT, CASCADE y SET NULL.",
+                "The referential action '{}' is not valid, the possible actions are RESTRICT, CASCADE and SET NULL.",
+            ),
+            Mensaje::LineaEsquemaInvalida => (
+                "La línea '{}' del esquema de {} no es válida.",
+                "The line '{}' of the schema of {} is not valid.",
+            ),
+            Mensaje::TransaccionAnidada => (
+                "Ya hay una transacción abierta, no se pueden anidar transacciones.",
+                "There is already an open transaction, transactions cannot be nested.",
+            ),
+            Mensaje::CommitSinTransaccion => (
+                "No hay una transacción abierta para hacer COMMIT.",
+                "There is no open transaction to COMMIT.",
+            ),
+            Mensaje::RollbackSinTransaccion => (
+                "No hay una transacción abierta para hacer ROLLBACK.",
+                "There is no open transaction to ROLLBACK.",
+            ),
+            Mensaje::FormatoInvalido => (
+                "El formato '{}' no es válido, los formatos posibles son csv, tsv, table, json, ndjson, markdown y html.",
+                "The format '{}' is not valid, the possible formats are csv, tsv, table, json, ndjson, markdown and html.",
+            ),
+            Mensaje::HistorialSinConsulta => (
+                "No hay una consulta con ese número en el historial.",
+                "There is no query with that number in the history.",
+            ),
+            Mensaje::ComandoDesconocido => (
+                "El comando '{}' no existe, use .help para ver los comandos disponibles.",
+                "The command '{}' does not exist, use .help to see the available commands.",
+            ),
+            Mensaje::FaltaRutaScript => (
+                "Falta la ruta del script después de --file.",
+                "The script path is missing after --file.",
+            ),
+            Mensaje::FaltaFormato => (
+                "Falta el formato después de --format.",
+                "The format is missing after --format.",
+            ),
+            Mensaje::ArgumentoInvalido => (
+                "El argumento '{}' no es válido.",
+                "The argument '{}' is not valid.",
+            ),
+            Mensaje::ConsultaYScript => (
+                "No se puede recibir una consulta y un script a la vez.",
+                "A query and a script cannot be given at the same time.",
+            ),
+            Mensaje::FaltaIdioma => (
+                "Falta el idioma después de --lang.",
+                "The language is missing after --lang.",
+            ),
+            Mensaje::IdiomaInvalido => (
+                "El idioma '{}' no es válido, los idiomas posibles son es y en.",
+                "The language '{}' is not valid, the possible languages are es and en.",
+            ),
+            Mensaje::CreateIndexInvalido => (
+                "La query CREATE INDEX no es válida, la sintaxis válida es: CREATE INDEX <nombre> ON <tabla> [USING HASH|BTREE] (<columna>).",
+                "The CREATE INDEX query is not valid, the valid syntax is: CREATE INDEX <name> ON <table> [USING HASH|BTREE] (<column>).",
+            ),
+            Mensaje::DropIndexInvalido => (
+                "La query DROP INDEX no es válida, la sintaxis válida es: DROP INDEX <nombre> [ON <tabla>].",
+                "The DROP INDEX query is not valid, the valid syntax is: DROP INDEX <name> [ON <table>].",
+            ),
+            Mensaje::NombreIndiceInvalido => (
+                "El nombre de índice '{}' no es válido, solo puede tener letras, números y '_'.",
+                "The index name '{}' is not valid, it can only have letters, numbers and '_'.",
+            ),
+            Mensaje::TipoIndiceInvalido => (
+                "El tipo de índice '{}' no es válido, los tipos posibles son HASH y BTREE.",
+                "The index type '{}' is not valid, the possible types are HASH and BTREE.",
+            ),
+            Mensaje::ExplainInvalido => (
+                "La query EXPLAIN no es válida, la sintaxis válida es: EXPLAIN [ANALYZE] SELECT <columnas> FROM <tabla> ...",
+                "The EXPLAIN query is not valid, the valid syntax is: EXPLAIN [ANALYZE] SELECT <columns> FROM <table> ...",
+            ),
+            Mensaje::JoinInvalido => (
+                "La cláusula JOIN no es válida, la sintaxis válida es: [INNER] JOIN <tabla> ON <columna> = <columna>, con una columna de cada lado.",
+                "The JOIN clause is not valid, the valid syntax is: [INNER] JOIN <table> ON <column> = <column>, with a column from each side.",
+            ),
+            Mensaje::JoinTablaRepetida => (
+                "La tabla {} aparece más de una vez en la consulta.",
+                "The table {} appears more than once in the query.",
+            ),
+            Mensaje::LimitInvalido => (
+                "El valor '{}' de LIMIT no es válido, debe ser una cantidad de filas.",
+                "The LIMIT value '{}' is not valid, it must be a number of rows.",
+            ),
+            Mensaje::GroupByInvalido => (
+                "La cláusula GROUP BY no es válida, la sintaxis válida es: GROUP BY <columna>, ...",
+                "The GROUP BY clause is not valid, the valid syntax is: GROUP BY <column>, ...",
+            ),
+            Mensaje::AgregacionInvalida => (
+                "La función de agregación '{}' no es válida, las funciones posibles son COUNT(*), COUNT, SUM, MIN, MAX y AVG de una columna.",
+                "The aggregate function '{}' is not valid, the possible functions are COUNT(*), COUNT, SUM, MIN, MAX and AVG of a column.",
+            ),
+            Mensaje::ColumnaFueraDeGroupBy => (
+                "La columna {} tiene que estar en GROUP BY o dentro de una función de agregación.",
+                "The column {} must appear in GROUP BY or inside an aggregate function.",
+            ),
+            Mensaje::TablaInexistente => ("La tabla {} no existe.", "The table {} does not exist."),
+            Mensaje::IndiceInexistente => ("El índice {} no existe.", "The index {} does not exist."),
+            Mensaje::ColumnaInexistente => (
+                "La columna {} no fue encontrada en el archivo csv.",
+                "The column {} was not found in the csv file.",
+            ),
+            Mensaje::ColumnasWhereInexistentes => (
+                "Ninguno de estos dos valores '{}' y '{}' en la WHERE clause fue encontrado como una columna en el csv.",
+                "Neither of the values '{}' and '{}' in the WHERE clause was found as a column in the csv.",
+            ),
+            Mensaje::ColumnaOrderByInexistente => (
+                "La columna '{}' ingresada en ORDER BY clause no se encuentra en el csv.",
+                "The column '{}' in the ORDER BY clause is not in the csv.",
+            ),
+            Mensaje::ColumnaNoEncontrada => (
+                "La coumna no se encuentra.",
+                "The column was not found.",
+            ),
+            Mensaje::ColumnaSetInexistente => (
+                "La columna {} no fue existe en el archivo csv.",
+                "The column {} does not exist in the csv file.",
+            ),
+            Mensaje::ColumnaForeignKeyInexistente => (
+                "La columna {} de la foreign key no fue encontrada en el archivo csv.",
+                "The foreign key column {} was not found in the csv file.",
+            ),
+            Mensaje::ColumnaAmbigua => (
+                "La columna {} está en más de una tabla de la consulta, indique la tabla como <tabla>.{}.",
+                "The column {} is in more than one table of the query, specify the table as <table>.{}.",
+            ),
+            Mensaje::EnteroPositivo => (
+                "El valor {} no es un entero positivo.",
+                "The value {} is not a positive integer.",
+            ),
+            Mensaje::AutoIncrementNoEntero => (
+                "El valor '{}' de la columna auto increment {} no es un entero positivo.",
+                "The value '{}' of the auto increment column {} is not a positive integer.",
+            ),
+            Mensaje::AgregacionNoEntera => (
+                "El valor '{}' de la columna {} no es un entero y no se puede usar en {}.",
+                "The value '{}' of the column {} is not an integer and cannot be used in {}.",
+            ),
+            Mensaje::ValorNoGuardable => (
+                "El valor '{}' de la columna {} tiene comas o saltos de línea, que no se pueden guardar en la tabla.",
+                "The value '{}' of the column {} has commas or line breaks, which cannot be stored in the table.",
+            ),
+            Mensaje::ValorReferenciadoInexistente => (
+                "El valor '{}' de {}.{} no existe en {}.{}.",
+                "The value '{}' of {}.{} does not exist in {}.{}.",
+            ),
+            Mensaje::ValorReferenciado => (
+                "No se puede modificar {}.{} = '{}' porque {}.{} lo referencia.",
+                "{}.{} = '{}' cannot be modified because {}.{} references it.",
+            ),
+            Mensaje::ForeignKeysEnCiclo => (
+                "Las foreign keys forman un ciclo, no se pueden propagar los cambios.",
+                "The foreign keys form a cycle, the changes cannot be propagated.",
+            ),
+            Mensaje::ValorReferenciadoRepetido => (
+                "La columna referenciada {} tiene el valor '{}' repetido, debe tener valores únicos.",
+                "The referenced column {} has the value '{}' repeated, it must have unique values.",
+            ),
+            Mensaje::ForeignKeyNuevaInvalida => (
+                "No se puede agregar la foreign key, el valor '{}' de {} no existe en la columna referenciada {}.",
+                "The foreign key cannot be added, the value '{}' of {} does not exist in the referenced column {}.",
+            ),
+            Mensaje::ForeignKeyPropia => (
+                "Una tabla no puede tener una foreign key que se referencie a sí misma.",
+                "A table cannot have a foreign key that references itself.",
+            ),
+            Mensaje::ArchivoModificadoDosVeces => (
+                "El archivo {} se modificaría dos veces en la misma operación.",
+                "The file {} would be modified twice in the same operation.",
+            ),
+            Mensaje::IndiceExistente => (
+                "Ya existe un índice llamado {} en la tabla {}.",
+                "An index named {} already exists on the table {}.",
+            ),
+            Mensaje::TablaEnLectura => (
+                "No se pudo bloquear la tabla {}: la tabla ya se está leyendo en esta consulta y no se puede escribir a la vez.",
+                "The table {} could not be locked: it is already being read in this query and cannot be written at the same time.",
+            ),
+            Mensaje::TablaOcupada => (
+                "No se pudo bloquear la tabla {}: otro proceso la está usando y no se liberó en {} ms.",
+                "The table {} could not be locked: another process is using it and it was not released in {} ms.",
+            ),
+            Mensaje::TransaccionEnConflicto => (
+                "No se pudo confirmar la transacción: otro proceso modificó {} después del BEGIN. Los cambios de la transacción se descartaron.",
+                "The transaction could not be committed: another process modified {} after the BEGIN. The changes of the transaction were discarded.",
+            ),
+            Mensaje::CsvVacio => (
+                "El archivo csv se encuentra vacío",
+                "The csv file is empty",
+            ),
+            Mensaje::ImportacionFallida => (
+                "No se importó ninguna fila de {}, {} filas tienen errores:{}",
+                "No rows were imported from {}, {} rows have errors:{}",
+            ),
+            Mensaje::ImportacionLinea => ("línea {}: {}", "line {}: {}"),
+            Mensaje::ImportacionMasFilas => ("y {} filas más.", "and {} more rows."),
+            Mensaje::ImportacionCantidadValores => (
+                "tiene {} valores pero se esperaban {}.",
+                "it has {} values but {} were expected.",
+            ),
+            Mensaje::ImportacionValorNoGuardable => (
+                "el valor '{}' tiene comas o saltos de línea, que no se pueden guardar en la tabla.",
+                "the value '{}' has commas or line breaks, which cannot be stored in the table.",
+            ),
+            Mensaje::ImportacionNoEntero => (
+                "el valor '{}' de la columna {} no es un entero como el resto de la columna.",
+                "the value '{}' of the column {} is not an integer like the rest of the column.",
+            ),
+            Mensaje::ImportacionAutoIncrement => (
+                "el valor '{}' de la columna auto increment {} no es un entero positivo.",
+                "the value '{}' of the auto increment column {} is not a positive integer.",
+            ),
+            Mensaje::ImportacionComillasSinCerrar => (
+                "las comillas del valor no se cierran antes del final del archivo.",
+                "the quotes of the value are not closed before the end of the file.",
+            ),
+            Mensaje::ImportacionSinEncabezado => (
+                "El archivo {} está vacío, no tiene encabezado.",
+                "The file {} is empty, it has no header.",
+            ),
+            Mensaje::TablasConFilasInvalidas => (
+                "Las tablas {} tienen filas con una cantidad de valores distinta a la de columnas.",
+                "The tables {} have rows with a number of values different from the number of columns.",
+            ),
+            Mensaje::TablaSinEncabezado => (
+                "El archivo no tiene encabezado.",
+                "The file has no header.",
+            ),
+            Mensaje::LineaConOtraCantidad => (
+                "La línea {} tiene {} valores y el encabezado {} columnas.",
+                "Line {} has {} values and the header has {} columns.",
+            ),
+            Mensaje::DiagnosticoUbicacion => ("línea {}, columna {}", "line {}, column {}"),
+            Mensaje::DiagnosticoEsperados => ("se esperaba {}", "expected {}"),
+            Mensaje::DiagnosticoSugerencia => ("¿quiso decir {}?", "did you mean {}?"),
+            Mensaje::Sugerencia => ("¿Quiso decir {}?", "Did you mean {}?"),
+            Mensaje::Alternativas => ("{} o {}", "{} or {}"),
+            Mensaje::ErrorEnScript => (
+                "Error en la consulta de la línea {} ({}): {}",
+                "Error in the query on line {} ({}): {}",
+            ),
+            Mensaje::ErrorEnScriptDetallado => (
+                "Error en la consulta de la línea {}: {}",
+                "Error in the query on line {}: {}",
+            ),
+            Mensaje::ResumenScript => (
+                "{} consultas: {} correctas, {} con error, {} sin ejecutar.",
+                "{} queries: {} succeeded, {} failed, {} not run.",
+            ),
+            Mensaje::RegistroConsulta => ("[línea {}] {}", "[line {}] {}"),
+            Mensaje::RegistroCorrecta => (
+                "[línea {}] correcta en {} ms",
+                "[line {}] succeeded in {} ms",
+            ),
+            Mensaje::RegistroFallida => ("[línea {}] falló en {} ms", "[line {}] failed in {} ms"),
+            Mensaje::Uso => (
+                "El comando ingresado no es correcto, recuerde que debe tener el siguiente formato:
+cargo run -- ruta/a/tablas \"<consulta>\"
+o sin consulta para abrir el intérprete interactivo: cargo run -- ruta/a/tablas
+o con un script: cargo run -- ruta/a/tablas --file script.sql [--continue-on-error]
+y elegir el formato de los resultados con --format csv|tsv|table|json|ndjson|markdown|html
+Con --quiet solo se muestran los errores y con --verbose cada consulta ejecutada y su duración.
+Los mensajes se muestran en español o en inglés con --lang es|en o la variable SQL_RUSTICO_IDIOMA.",
+                "The command is not valid, remember that it must have the following format:
+cargo run -- path/to/tables \"<query>\"
+or without a query to open the interactive interpreter: cargo run -- path/to/tables
+or with a script: cargo run -- path/to/tables --file script.sql [--continue-on-error]
+and choose the format of the results with --format csv|tsv|table|json|ndjson|markdown|html
+With --quiet only the errors are shown and with --verbose each executed query and its duration.
+The messages are shown in Spanish or in English with --lang es|en or the variable SQL_RUSTICO_IDIOMA.",
+            ),
+            Mensaje::BienvenidaInterprete => (
+                "SQL Rústico, use .help para ver los comandos disponibles.",
+                "SQL Rústico, use .help to see the available commands.",
+            ),
+            Mensaje::AyudaInterprete => (
+                ".tables              Lista las tablas del directorio
+.schema [tabla]      Muestra las columnas y restricciones de la tabla, o de todas
+.mode <formato>      Cambia el formato de los resultados: csv, tsv, table, json, ndjson, markdown o html
+.timer on|off        Muestra el tiempo que tarda cada consulta
+.history             Lista las consultas ejecutadas, !<n> vuelve a ejecutar la número n
+.help                Muestra esta ayuda
+.quit, .exit         Sale del programa
+Las consultas pueden ocupar varias líneas y terminan con ';'.",
+                ".tables              Lists the tables of the directory
+.schema [table]      Shows the columns and constraints of the table, or of all of them
+.mode <format>       Changes the format of the results: csv, tsv, table, json, ndjson, markdown or html
+.timer on|off        Shows the time each query takes
+.history             Lists the executed queries, !<n> runs number n again
+.help                Shows this help
+.quit, .exit         Exits the program
+Queries can span several lines and end with ';'.",
+            ),
+            Mensaje::TiempoConsulta => ("Tiempo: {} ms", "Time: {} ms"),
+            Mensaje::EsperaBloqueoInvalida => (
+                "{} debe ser una cantidad de milisegundos.",
+                "{} must be a number of milliseconds.",
+            ),
+            Mensaje::MemoriaOrdenInvalida => (
+                "{} debe ser una cantidad de kilobytes.",
+                "{} must be a number of kilobytes.",
+            ),
+            Mensaje::HilosInvalidos => (
+                "{} debe ser una cantidad de hilos.",
+                "{} must be a number of threads.",
+            ),
+            Mensaje::PlanRecorridoIndice => (
+                "Recorrido con índice {} ({}) en {}: {}",
+                "Index scan with {} ({}) on {}: {}",
+            ),
+            Mensaje::PlanRecorridoParalelo => (
+                "Recorrido paralelo ({} hilos) en {}",
+                "Parallel scan ({} threads) on {}",
+            ),
+            Mensaje::PlanRecorridoSecuencial => (
+                "Recorrido secuencial en {}",
+                "Sequential scan on {}",
+            ),
+            Mensaje::PlanColumnas => (" [columnas: {}]", " [columns: {}]"),
+            Mensaje::PlanFiltroRecorrido => (" [filtro: {}]", " [filter: {}]"),
+            Mensaje::PlanSinFilas => (
+                "Sin filas en {}: el WHERE nunca se cumple",
+                "No rows on {}: the WHERE is never true",
+            ),
+            Mensaje::PlanFiltro => ("Filtro: {}", "Filter: {}"),
+            Mensaje::PlanJoin => ("Join (hash): {} = {}", "Hash join: {} = {}"),
+            Mensaje::PlanAgregacion => ("Agregación", "Aggregate"),
+            Mensaje::PlanGrupos => (" [grupos: {}]", " [groups: {}]"),
+            Mensaje::PlanOrden => ("Orden: {}", "Sort: {}"),
+            Mensaje::PlanProyeccion => ("Proyección: {}", "Projection: {}"),
+            Mensaje::PlanLimite => ("Límite: {}", "Limit: {}"),
+            Mensaje::PlanMetricas => (" (filas={} tiempo={})", " (rows={} time={})"),
+            Mensaje::PlanTiempoTotal => ("Tiempo total: {}", "Total time: {}"),
+        }
+    }
+
+    pub fn plantilla(&self, idioma: Idioma) -> &'static str {
+        match idioma {
+            Idioma::Espanol => self.plantillas().0,
+            Idioma::Ingles => self.plantillas().1,
+        }
+    }
+
+    // Texto del mensaje en el idioma pedido, con los datos en el lugar de cada {}.
+    pub fn texto_en(&self, idioma: Idioma, datos: &[&str]) -> String {
+        let mut partes = self.plantilla(idioma).split("{}");
+        let mut texto = partes.next().unwrap_or_default().to_string();
+        for (indice, parte) in partes.enumerate() {
+            texto.push_str(datos.get(indice).copied().unwrap_or_default());
+            texto.push_str(parte);
+        }
+        texto
+    }
+
+    // Texto del mensaje en el idioma configurado.
+    pub fn texto(&self, datos: &[&str]) -> String {
+        self.texto_en(idioma_actual(), datos)
+    }
+}
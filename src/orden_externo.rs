@@ -0,0 +1,276 @@
+use crate::archivo_temporal::ArchivoTemporal;
+use crate::errors::ErrorPrograma;
+use crate::plan::FilasTabla;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::fs::{remove_file, File};
+use std::io::{BufRead, BufReader, Lines, Write};
+use std::mem::size_of;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering as OrdenAtomico};
+
+// Memoria que puede ocupar un ORDER BY con las filas antes de pasarlas a disco.
+const MEMORIA_POR_DEFECTO: u64 = 64 * 1024 * 1024;
+// Cantidad máxima de partes que se mezclan a la vez, cada una con su archivo abierto.
+const PARTES_POR_MEZCLA: usize = 64;
+
+type Comparacion<'a> = dyn Fn(&[String], &[String]) -> Ordering + 'a;
+
+static MEMORIA_ORDEN: AtomicU64 = AtomicU64::new(MEMORIA_POR_DEFECTO);
+
+/*
+ * Cambia la memoria, en bytes, que puede ocupar un ORDER BY antes de ordenar por partes en disco.
+ */
+pub fn configurar_memoria_orden(bytes: u64) {
+    MEMORIA_ORDEN.store(bytes, OrdenAtomico::Relaxed);
+}
+
+// Bytes que ocupa una fila en memoria, contando los valores y los vectores que los guardan.
+fn tamanio_fila(fila: &[String]) -> u64 {
+    let valores: usize = fila
+        .iter()
+        .map(|valor| size_of::<String>() + valor.capacity())
+        .sum();
+    (size_of::<Vec<String>>() + valores) as u64
+}
+
+/*
+ * Escribe una fila en una línea, con los valores separados por comas. Las barras, comas y saltos de línea
+ * de los valores se escapan con una barra para poder leerlos igual.
+ */
+fn escribir_fila(archivo: &mut ArchivoTemporal, fila: &[String]) -> Result<(), ErrorPrograma> {
+    let mut linea = String::new();
+    for (indice, valor) in fila.iter().enumerate() {
+        if indice > 0 {
+            linea.push(',');
+        }
+        for caracter in valor.chars() {
+            match caracter {
+                '\\' => linea.push_str("\\\\"),
+                ',' => linea.push_str("\\,"),
+                '\n' => linea.push_str("\\n"),
+                caracter => linea.push(caracter),
+            }
+        }
+    }
+    linea.push('\n');
+    archivo.write_all(linea.as_bytes())?;
+    Ok(())
+}
+
+fn leer_fila(linea: &str) -> Vec<String> {
+    let mut fila = vec![String::new()];
+    let mut caracteres = linea.chars();
+    while let Some(caracter) = caracteres.next() {
+        match caracter {
+            '\\' => {
+                let escapado = match caracteres.next() {
+                    Some('n') => '\n',
+                    Some(escapado) => escapado,
+                    None => break,
+                };
+                if let Some(valor) = fila.last_mut() {
+                    valor.push(escapado);
+                }
+            }
+            ',' => fila.push(String::new()),
+            caracter => {
+                if let Some(valor) = fila.last_mut() {
+                    valor.push(caracter);
+                }
+            }
+        }
+    }
+    fila
+}
+
+/*
+ * Parte de las filas ya ordenada y guardada en el directorio de temporales del sistema, no al lado de la
+ * tabla, así un proceso que se corta en medio del orden no deja archivos en los datos. Se borra al terminar
+ * de leerla. El archivo se abre recién al leer la primera fila, así las partes que esperan su mezcla no
+ * ocupan archivos abiertos.
+ */
+struct Tramo {
+    ruta: PathBuf,
+    lineas: Option<Lines<BufReader<File>>>,
+}
+
+impl Tramo {
+    fn escribir(
+        filas: impl Iterator<Item = Result<Vec<String>, ErrorPrograma>>,
+        ruta_tabla: &str,
+    ) -> Result<Self, ErrorPrograma> {
+        let mut archivo = ArchivoTemporal::crear_en_directorio_temporal(ruta_tabla)?;
+        for fila in filas {
+            escribir_fila(&mut archivo, &fila?)?;
+        }
+        Ok(Tramo {
+            ruta: archivo.cerrar()?,
+            lineas: None,
+        })
+    }
+}
+
+impl Iterator for Tramo {
+    type Item = Result<Vec<String>, ErrorPrograma>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.lineas.is_none() {
+            match File::open(&self.ruta) {
+                Ok(archivo) => self.lineas = Some(BufReader::new(archivo).lines()),
+                Err(e) => return Some(Err(e.into())),
+            }
+        }
+        self.lineas
+            .as_mut()?
+            .next()
+            .map(|linea| Ok(leer_fila(&linea?)))
+    }
+}
+
+impl Drop for Tramo {
+    fn drop(&mut self) {
+        let _ = remove_file(&self.ruta);
+    }
+}
+
+/*
+ * Próxima fila de una de las partes que se mezclan. El montículo devuelve primero la menor fila y, entre
+ * filas iguales, la de la parte que se leyó antes.
+ */
+struct Cabeza<'a> {
+    fila: Vec<String>,
+    parte: usize,
+    comparar: Rc<Comparacion<'a>>,
+}
+
+impl Ord for Cabeza<'_> {
+    fn cmp(&self, otra: &Self) -> Ordering {
+        (self.comparar)(&otra.fila, &self.fila).then(otra.parte.cmp(&self.parte))
+    }
+}
+
+impl PartialOrd for Cabeza<'_> {
+    fn partial_cmp(&self, otra: &Self) -> Option<Ordering> {
+        Some(self.cmp(otra))
+    }
+}
+
+impl PartialEq for Cabeza<'_> {
+    fn eq(&self, otra: &Self) -> bool {
+        self.cmp(otra) == Ordering::Equal
+    }
+}
+
+impl Eq for Cabeza<'_> {}
+
+/*
+ * Mezcla partes ya ordenadas en una sola, dejando en un montículo la próxima fila de cada parte.
+ */
+fn mezclar<'a>(
+    mut partes: Vec<FilasTabla<'a>>,
+    comparar: Rc<Comparacion<'a>>,
+) -> Result<FilasTabla<'a>, ErrorPrograma> {
+    let mut monticulo = BinaryHeap::with_capacity(partes.len());
+    for (parte, filas) in partes.iter_mut().enumerate() {
+        if let Some(fila) = filas.next().transpose()? {
+            monticulo.push(Cabeza {
+                fila,
+                parte,
+                comparar: Rc::clone(&comparar),
+            });
+        }
+    }
+    Ok(Box::new(std::iter::from_fn(move || {
+        let Cabeza {
+            fila,
+            parte,
+            comparar,
+        } = monticulo.pop()?;
+        match partes[parte].next().transpose() {
+            Ok(Some(siguiente)) => monticulo.push(Cabeza {
+                fila: siguiente,
+                parte,
+                comparar,
+            }),
+            Ok(None) => {}
+            Err(e) => return Some(Err(e)),
+        }
+        Some(Ok(fila))
+    })))
+}
+
+/*
+ * Mezcla los tramos de a grupos consecutivos en tramos nuevos, tantas pasadas como haga falta para que
+ * queden menos que las partes que se mezclan a la vez.
+ */
+fn reducir_tramos<'a>(
+    mut tramos: Vec<Tramo>,
+    ruta_tabla: &str,
+    comparar: &Rc<Comparacion<'a>>,
+) -> Result<Vec<Tramo>, ErrorPrograma> {
+    while tramos.len() >= PARTES_POR_MEZCLA {
+        let mut mezclados = Vec::with_capacity(tramos.len().div_ceil(PARTES_POR_MEZCLA));
+        let mut restantes = tramos.into_iter().peekable();
+        while restantes.peek().is_some() {
+            let mut grupo: Vec<Tramo> = restantes.by_ref().take(PARTES_POR_MEZCLA).collect();
+            if grupo.len() == 1 {
+                mezclados.append(&mut grupo);
+                continue;
+            }
+            let partes = grupo
+                .into_iter()
+                .map(|tramo| Box::new(tramo) as FilasTabla<'a>)
+                .collect();
+            mezclados.push(Tramo::escribir(
+                mezclar(partes, Rc::clone(comparar))?,
+                ruta_tabla,
+            )?);
+        }
+        tramos = mezclados;
+    }
+    Ok(tramos)
+}
+
+/*
+ * Ordena las filas de forma estable con la comparación recibida. Mientras entran en la memoria configurada se
+ * ordenan juntas; si no, se ordena cada parte que la llena, se guarda en un archivo temporal y al final se
+ * mezclan las partes, en varias pasadas si son demasiadas para abrirlas a la vez. En la mezcla los empates se
+ * resuelven por la parte que se leyó antes, así el resultado es el mismo que ordenando todo en memoria.
+ */
+pub(crate) fn ordenar_filas_externo<'a, F>(
+    filas: FilasTabla<'a>,
+    ruta_tabla: &str,
+    comparar: F,
+) -> Result<FilasTabla<'a>, ErrorPrograma>
+where
+    F: Fn(&[String], &[String]) -> Ordering + 'a,
+{
+    let comparar: Rc<Comparacion<'a>> = Rc::new(comparar);
+    let memoria = MEMORIA_ORDEN.load(OrdenAtomico::Relaxed);
+    let mut tramos = Vec::new();
+    let mut actuales = Vec::new();
+    let mut ocupada = 0;
+    for fila in filas {
+        let fila = fila?;
+        ocupada += tamanio_fila(&fila);
+        actuales.push(fila);
+        if ocupada > memoria {
+            actuales.sort_by(|a, b| comparar(a, b));
+            tramos.push(Tramo::escribir(actuales.drain(..).map(Ok), ruta_tabla)?);
+            ocupada = 0;
+        }
+    }
+    actuales.sort_by(|a, b| comparar(a, b));
+    if tramos.is_empty() {
+        return Ok(Box::new(actuales.into_iter().map(Ok)));
+    }
+    let mut partes: Vec<FilasTabla<'a>> = reducir_tramos(tramos, ruta_tabla, &comparar)?
+        .into_iter()
+        .map(|tramo| Box::new(tramo) as FilasTabla<'a>)
+        .collect();
+    // Las últimas filas quedan en memoria como la última parte.
+    partes.push(Box::new(actuales.into_iter().map(Ok)));
+    mezclar(partes, comparar)
+}
@@ -0,0 +1,957 @@
+use crate::agregacion::{agregar_filas, Agregado};
+use crate::errors::ErrorPrograma;
+use crate::indices::{elegir_indice, AccesoIndice};
+use crate::mensajes::Mensaje;
+use crate::orden_externo::ordenar_filas_externo;
+use crate::predicado::Predicado;
+use crate::query_identifier::{Order, OrderByClause, Select};
+use crate::read_file::abrir_archivo;
+use crate::recorrido_paralelo::{hilos_para_recorrer, recorrer_en_paralelo};
+use crate::salida::DestinoFilas;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+// Filas de una tabla ya separadas en sus valores, que una operación del plan le entrega a la de arriba.
+pub(crate) type FilasTabla<'a> = Box<dyn Iterator<Item = Result<Vec<String>, ErrorPrograma>> + 'a>;
+
+/*
+ * Tabla que lee un SELECT, con las columnas con las que la nombra la consulta: solas si es la única tabla y
+ * como <tabla>.<columna> si hay JOIN.
+ */
+#[derive(Debug)]
+pub(crate) struct TablaConsulta {
+    pub nombre: String,
+    pub ruta: String,
+    pub columnas: Vec<String>,
+}
+
+/*
+ * Operaciones de un plan de ejecución de un SELECT. El recorrido lee las filas de una tabla, con un índice o
+ * completa, y puede quedarse solo con algunas columnas y con las filas que cumplen un filtro. El resto recibe
+ * las filas de sus operaciones de entrada.
+ */
+#[derive(Debug)]
+pub enum Operacion<'a> {
+    Recorrido {
+        tabla: String,
+        ruta: String,
+        columnas_tabla: Vec<String>,
+        acceso: Option<AccesoIndice>,
+        columnas: Option<Vec<String>>,
+        filtro: Option<Predicado<'a>>,
+        hilos: usize,
+    },
+    // Reemplaza a los recorridos cuando el WHERE nunca se cumple.
+    SinFilas {
+        tabla: String,
+        columnas: Vec<String>,
+    },
+    Filtro {
+        predicado: Predicado<'a>,
+    },
+    // Une las filas de sus dos entradas que tienen el mismo valor en las columnas, buscándolas en una tabla hash
+    // con las filas de la derecha.
+    Join {
+        izquierda: &'a str,
+        derecha: &'a str,
+    },
+    // Devuelve una fila por grupo con sus columnas y el resultado de cada función. Sin GROUP BY hay un solo grupo.
+    Agregacion {
+        grupos: Option<&'a [String]>,
+        agregados: Vec<Agregado>,
+    },
+    Orden {
+        claves: &'a [OrderByClause],
+    },
+    Proyeccion {
+        columnas: &'a [String],
+    },
+    Limite {
+        cantidad: usize,
+    },
+}
+
+/*
+ * Operación del plan con las operaciones que le entregan sus filas. El recorrido y las filas vacías no tienen
+ * entradas, el join tiene dos y el resto una.
+ */
+#[derive(Debug)]
+pub struct Nodo<'a> {
+    pub operacion: Operacion<'a>,
+    pub entradas: Vec<Nodo<'a>>,
+}
+
+impl<'a> Nodo<'a> {
+    fn new(operacion: Operacion<'a>, entradas: Vec<Nodo<'a>>) -> Self {
+        Nodo {
+            operacion,
+            entradas,
+        }
+    }
+
+    // Aplica la función a la operación de cada nodo del árbol, empezando por la raíz.
+    fn visitar(&self, visitar: &mut impl FnMut(&Operacion<'a>)) {
+        visitar(&self.operacion);
+        for entrada in self.entradas.iter() {
+            entrada.visitar(visitar);
+        }
+    }
+
+    fn visitar_mut(&mut self, visitar: &mut impl FnMut(&mut Operacion<'a>)) {
+        visitar(&mut self.operacion);
+        for entrada in self.entradas.iter_mut() {
+            entrada.visitar_mut(visitar);
+        }
+    }
+
+    // Reemplaza el primer nodo de la cadena de operaciones de una entrada que cumple la condición por su entrada.
+    fn quitar(&mut self, quitar: &impl Fn(&Operacion) -> bool) {
+        if quitar(&self.operacion) && self.entradas.len() == 1 {
+            let entrada = self.entradas.remove(0);
+            *self = entrada;
+        } else if self.entradas.len() == 1 {
+            self.entradas[0].quitar(quitar);
+        }
+    }
+}
+
+/*
+ * Plan de ejecución de un SELECT: un árbol de operaciones con la que entrega el resultado en la raíz y los
+ * recorridos de las tablas en las hojas. Cada operación pide las filas a sus entradas a medida que las necesita.
+ */
+#[derive(Debug)]
+pub struct Plan<'a> {
+    pub raiz: Nodo<'a>,
+    // Tabla junto a la que se guardan las partes del orden que no entran en memoria.
+    ruta: String,
+}
+
+// Filas que devolvió una operación y el tiempo que tardó en devolverlas, incluido el de sus entradas.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Metricas {
+    pub filas: u64,
+    pub tiempo: Duration,
+}
+
+fn recorrido<'a>(tabla: &TablaConsulta) -> Nodo<'a> {
+    Nodo::new(
+        Operacion::Recorrido {
+            tabla: tabla.nombre.clone(),
+            ruta: tabla.ruta.clone(),
+            columnas_tabla: tabla.columnas.clone(),
+            acceso: None,
+            columnas: None,
+            filtro: None,
+            hilos: 1,
+        },
+        Vec::new(),
+    )
+}
+
+/*
+ * Arma el plan de un SELECT cuyas columnas ya se verificaron, con las tablas en el orden de la consulta. Primero
+ * se traduce la consulta a un árbol con los recorridos y joins abajo, y arriba el filtro, la agregación, el
+ * orden, la proyección y el límite, y después se optimiza el plan.
+ */
+pub(crate) fn planificar_select<'a>(
+    tablas: &[TablaConsulta],
+    select: &'a Select,
+    agregados: Vec<Agregado>,
+) -> Result<Plan<'a>, ErrorPrograma> {
+    let mut raiz = recorrido(&tablas[0]);
+    for (join, tabla) in select.joins.iter().zip(tablas[1..].iter()) {
+        let operacion = Operacion::Join {
+            izquierda: &join.columna_izquierda,
+            derecha: &join.columna_derecha,
+        };
+        raiz = Nodo::new(operacion, vec![raiz, recorrido(tabla)]);
+    }
+    if let (Some(condiciones), Some(operadores)) = (&select.where_clauses, &select.operacion_logica)
+    {
+        if !condiciones.is_empty() {
+            let predicado = Predicado::desde_where(condiciones, operadores);
+            raiz = Nodo::new(Operacion::Filtro { predicado }, vec![raiz]);
+        }
+    }
+    let agrupa = !agregados.is_empty() || select.group_by.is_some();
+    if agrupa {
+        let operacion = Operacion::Agregacion {
+            grupos: select.group_by.as_deref(),
+            agregados,
+        };
+        raiz = Nodo::new(operacion, vec![raiz]);
+    }
+    if let Some(ref claves) = select.order_by {
+        raiz = Nodo::new(Operacion::Orden { claves }, vec![raiz]);
+    }
+    raiz = Nodo::new(
+        Operacion::Proyeccion {
+            columnas: &select.columnas,
+        },
+        vec![raiz],
+    );
+    if let Some(cantidad) = select.limit {
+        raiz = Nodo::new(Operacion::Limite { cantidad }, vec![raiz]);
+    }
+
+    let columnas: Vec<String> = tablas
+        .iter()
+        .flat_map(|tabla| tabla.columnas.iter().cloned())
+        .collect();
+    let mut plan = Plan {
+        raiz,
+        ruta: tablas[0].ruta.clone(),
+    };
+    plan.plegar_constantes(tablas, &columnas);
+    plan.empujar_filtros(&columnas);
+    plan.podar_columnas(&columnas);
+    // Con varias tablas o agrupando, el índice de una tabla no da el orden del resultado.
+    let orden = select
+        .order_by
+        .as_deref()
+        .filter(|_| tablas.len() == 1 && !agrupa);
+    plan.elegir_acceso(orden)?;
+    plan.paralelizar();
+    Ok(plan)
+}
+
+// Devuelve el nodo del filtro de la cadena de operaciones que empieza en el nodo, si hay uno.
+fn nodo_del_filtro<'n, 'a>(nodo: &'n mut Nodo<'a>) -> Option<&'n mut Nodo<'a>> {
+    match nodo.operacion {
+        Operacion::Filtro { .. } => Some(nodo),
+        _ if nodo.entradas.len() == 1 => nodo_del_filtro(&mut nodo.entradas[0]),
+        _ => None,
+    }
+}
+
+/*
+ * Pasa el predicado al recorrido de la tabla que tiene todas sus columnas, o al primero si no usa ninguna.
+ * Devuelve el predicado si ninguna tabla las tiene todas.
+ */
+fn empujar_a_recorrido<'a>(
+    nodo: &mut Nodo<'a>,
+    predicado: Predicado<'a>,
+    usadas: &[&str],
+) -> Option<Predicado<'a>> {
+    if let Operacion::Recorrido {
+        columnas_tabla,
+        filtro,
+        ..
+    } = &mut nodo.operacion
+    {
+        if !usadas
+            .iter()
+            .all(|usada| columnas_tabla.iter().any(|columna| columna == usada))
+        {
+            return Some(predicado);
+        }
+        *filtro = Some(match filtro.take() {
+            Some(anterior) => anterior.y(predicado),
+            None => predicado,
+        });
+        return None;
+    }
+    let mut predicado = Some(predicado);
+    for entrada in nodo.entradas.iter_mut() {
+        predicado = empujar_a_recorrido(entrada, predicado?, usadas);
+    }
+    predicado
+}
+
+impl<'a> Plan<'a> {
+    /*
+     * Simplifica el filtro con las condiciones que no dependen de la fila. Si siempre se cumple se saca, y si
+     * nunca se cumple las tablas no se leen.
+     */
+    fn plegar_constantes(&mut self, tablas: &[TablaConsulta], columnas: &[String]) {
+        let Some(nodo) = nodo_del_filtro(&mut self.raiz) else {
+            return;
+        };
+        let Operacion::Filtro { predicado } = &mut nodo.operacion else {
+            return;
+        };
+        let plegado = predicado.clone().plegar_constantes(columnas);
+        match plegado {
+            Predicado::Constante(true) => *nodo = nodo.entradas.remove(0),
+            Predicado::Constante(false) => {
+                let nombres: Vec<&str> = tablas.iter().map(|tabla| tabla.nombre.as_str()).collect();
+                *nodo = Nodo::new(
+                    Operacion::SinFilas {
+                        tabla: nombres.join(", "),
+                        columnas: columnas.to_vec(),
+                    },
+                    Vec::new(),
+                );
+            }
+            plegado => *predicado = plegado,
+        }
+    }
+
+    /*
+     * Pasa las condiciones del filtro a los recorridos, que las evalúan sobre los valores de cada línea antes de
+     * copiarlos, así las filas que no las cumplen no llegan a los joins ni a las demás operaciones. Sin OR cada
+     * condición va al recorrido de la tabla cuyas columnas compara; con OR el predicado pasa entero o no pasa.
+     * Las condiciones que comparan columnas de varias tablas quedan en el filtro, sobre los joins.
+     */
+    fn empujar_filtros(&mut self, columnas: &[String]) {
+        let Some(nodo) = nodo_del_filtro(&mut self.raiz) else {
+            return;
+        };
+        let Operacion::Filtro { predicado } = &nodo.operacion else {
+            return;
+        };
+        let partes: Vec<Predicado<'a>> = match predicado {
+            Predicado::Grupos(grupos) if grupos.len() == 1 => grupos[0]
+                .iter()
+                .map(|condicion| Predicado::Grupos(vec![vec![*condicion]]))
+                .collect(),
+            predicado => vec![predicado.clone()],
+        };
+        let mut restante: Option<Predicado<'a>> = None;
+        for parte in partes {
+            let usadas = parte.columnas(columnas);
+            if let Some(parte) = empujar_a_recorrido(&mut nodo.entradas[0], parte, &usadas) {
+                restante = Some(match restante {
+                    Some(anterior) => anterior.y(parte),
+                    None => parte,
+                });
+            }
+        }
+        match restante {
+            Some(predicado) => nodo.operacion = Operacion::Filtro { predicado },
+            None => *nodo = nodo.entradas.remove(0),
+        }
+    }
+
+    /*
+     * Hace que cada recorrido copie solo las columnas de su tabla que usa alguna operación, en el orden de la
+     * tabla. Con `SELECT *` se necesitan todas.
+     */
+    fn podar_columnas(&mut self, columnas_csv: &[String]) {
+        let mut usadas: Vec<String> = Vec::new();
+        let mut todas = false;
+        self.raiz.visitar(&mut |operacion| match operacion {
+            Operacion::Proyeccion { columnas } => {
+                todas |= columnas.iter().any(|columna| columna == "*");
+                usadas.extend(columnas.iter().cloned());
+            }
+            Operacion::Orden { claves } => {
+                usadas.extend(claves.iter().map(|clave| clave.columna.clone()))
+            }
+            Operacion::Filtro { predicado }
+            | Operacion::Recorrido {
+                filtro: Some(predicado),
+                ..
+            } => usadas.extend(
+                predicado
+                    .columnas(columnas_csv)
+                    .into_iter()
+                    .map(|columna| columna.to_string()),
+            ),
+            Operacion::Join { izquierda, derecha } => {
+                usadas.extend([izquierda.to_string(), derecha.to_string()])
+            }
+            Operacion::Agregacion { grupos, agregados } => {
+                usadas.extend(grupos.iter().flat_map(|grupos| grupos.iter().cloned()));
+                usadas.extend(agregados.iter().filter_map(|a| a.columna.clone()));
+            }
+            _ => {}
+        });
+        if todas {
+            return;
+        }
+        self.raiz.visitar_mut(&mut |operacion| {
+            if let Operacion::Recorrido {
+                columnas_tabla,
+                columnas,
+                ..
+            } = operacion
+            {
+                let podadas: Vec<String> = columnas_tabla
+                    .iter()
+                    .filter(|columna| usadas.contains(columna))
+                    .cloned()
+                    .collect();
+                if podadas.len() < columnas_tabla.len() {
+                    *columnas = Some(podadas);
+                }
+            }
+        });
+    }
+
+    /*
+     * Elige si cada recorrido usa un índice para las condiciones que toda fila de su tabla tiene que cumplir o
+     * para el ORDER BY, si se puede resolver con la tabla. Si el índice ya devuelve las filas ordenadas no hace
+     * falta ordenarlas.
+     */
+    fn elegir_acceso(&mut self, orden: Option<&[OrderByClause]>) -> Result<(), ErrorPrograma> {
+        let mut resultado = Ok(());
+        let mut ordenadas = false;
+        self.raiz.visitar_mut(&mut |operacion| {
+            if let Operacion::Recorrido {
+                ruta,
+                columnas_tabla,
+                acceso,
+                filtro,
+                ..
+            } = operacion
+            {
+                let condiciones = filtro
+                    .as_ref()
+                    .map(|filtro| filtro.condiciones_necesarias())
+                    .unwrap_or_default();
+                match elegir_indice(ruta, condiciones, orden, columnas_tabla) {
+                    Ok(elegido) => *acceso = elegido,
+                    Err(e) => resultado = Err(e),
+                }
+                ordenadas |= acceso.as_ref().is_some_and(|acceso| acceso.ordenadas);
+            }
+        });
+        if ordenadas {
+            self.raiz
+                .quitar(&|operacion| matches!(operacion, Operacion::Orden { .. }));
+        }
+        resultado
+    }
+
+    /*
+     * Reparte el recorrido completo de una tabla grande entre varios hilos. El recorrido con un índice lee
+     * pocas filas salteadas y queda en un solo hilo.
+     */
+    fn paralelizar(&mut self) {
+        self.raiz.visitar_mut(&mut |operacion| {
+            if let Operacion::Recorrido {
+                ruta,
+                acceso: None,
+                hilos,
+                ..
+            } = operacion
+            {
+                *hilos = hilos_para_recorrer(ruta);
+            }
+        });
+    }
+}
+
+fn posicion(columnas: &[String], columna: &str) -> usize {
+    columnas.iter().position(|c| c == columna).unwrap_or(0)
+}
+
+/*
+ * Separa los valores de una línea hasta la cantidad pedida, sin recorrer el resto de la línea.
+ */
+fn separar_campos(linea: &str, cantidad: usize) -> Vec<&str> {
+    linea.split(',').take(cantidad).map(|s| s.trim()).collect()
+}
+
+/*
+ * Evalúa el filtro sobre los valores de una fila y, si lo cumple, copia los que están en las posiciones
+ * pedidas o todos si no se pidieron posiciones.
+ */
+fn leer_fila(
+    campos: &[&str],
+    filtro: Option<&Predicado>,
+    posiciones: Option<&[usize]>,
+    columnas_csv: &[String],
+) -> Result<Option<Vec<String>>, ErrorPrograma> {
+    if let Some(filtro) = filtro {
+        if !filtro.evaluar(campos, columnas_csv)? {
+            return Ok(None);
+        }
+    }
+    Ok(Some(match posiciones {
+        Some(posiciones) => posiciones
+            .iter()
+            .map(|posicion| {
+                campos
+                    .get(*posicion)
+                    .copied()
+                    .unwrap_or_default()
+                    .to_string()
+            })
+            .collect(),
+        None => campos.iter().map(|campo| campo.to_string()).collect(),
+    }))
+}
+
+/*
+ * Devuelve las filas de la tabla que cumplen el filtro, con las columnas pedidas. De cada línea se separan solo
+ * los valores hasta la última columna que se copia o que compara el filtro.
+ */
+fn recorrer_tabla<'a>(
+    ruta: &str,
+    columnas_tabla: &'a [String],
+    acceso: Option<&'a AccesoIndice>,
+    columnas: Option<&[String]>,
+    filtro: Option<&'a Predicado<'a>>,
+    hilos: usize,
+) -> Result<FilasTabla<'a>, ErrorPrograma> {
+    let posiciones: Option<Vec<usize>> = columnas.map(|columnas| {
+        columnas
+            .iter()
+            .map(|columna| posicion(columnas_tabla, columna))
+            .collect()
+    });
+    let cantidad = match posiciones {
+        Some(ref posiciones) => filtro
+            .iter()
+            .flat_map(|filtro| filtro.columnas(columnas_tabla))
+            .map(|columna| posicion(columnas_tabla, columna))
+            .chain(posiciones.iter().copied())
+            .max()
+            .map_or(0, |ultima| ultima + 1),
+        None => usize::MAX,
+    };
+    // Si el índice no corresponde a la tabla se la recorre completa.
+    let filas = match acceso {
+        Some(acceso) => acceso.leer_filas(ruta, columnas_tabla)?,
+        None => None,
+    };
+    Ok(match filas {
+        Some(filas) => Box::new(filas.into_iter().filter_map(move |fila| {
+            let campos: Vec<&str> = fila.iter().map(|s| s.as_str()).collect();
+            leer_fila(&campos, filtro, posiciones.as_deref(), columnas_tabla).transpose()
+        })),
+        None if hilos > 1 => recorrer_en_paralelo(ruta, hilos, move |linea| {
+            let campos = separar_campos(linea, cantidad);
+            leer_fila(&campos, filtro, posiciones.as_deref(), columnas_tabla)
+        })?,
+        None => {
+            let (lineas, _) = abrir_archivo(&ruta.to_string())?;
+            Box::new(lineas.filter_map(move |linea| {
+                let linea = match linea {
+                    Ok(linea) => linea,
+                    Err(e) => return Some(Err(e.into())),
+                };
+                let campos = separar_campos(&linea, cantidad);
+                leer_fila(&campos, filtro, posiciones.as_deref(), columnas_tabla).transpose()
+            }))
+        }
+    })
+}
+
+/*
+ * Une cada fila de la izquierda con las filas de la derecha que tienen el mismo valor en la columna del join.
+ * La primera vez que se pide una fila se leen todas las de la derecha en una tabla hash por ese valor, y después
+ * las de la izquierda se leen de a una. Un valor vacío es NULL y no es igual a ningún otro.
+ */
+fn unir_filas<'a>(
+    izquierda: FilasTabla<'a>,
+    derecha: FilasTabla<'a>,
+    posicion_izquierda: usize,
+    posicion_derecha: usize,
+) -> FilasTabla<'a> {
+    let mut derecha = Some(derecha);
+    let mut tabla: HashMap<String, Vec<Vec<String>>> = HashMap::new();
+    Box::new(izquierda.flat_map(move |fila| {
+        let mut unidas = Vec::new();
+        if let Some(derecha) = derecha.take() {
+            for otra in derecha {
+                match otra {
+                    Ok(otra) => match otra.get(posicion_derecha) {
+                        Some(valor) if !valor.is_empty() => {
+                            tabla.entry(valor.clone()).or_default().push(otra)
+                        }
+                        _ => {}
+                    },
+                    Err(e) => {
+                        unidas.push(Err(e));
+                        return unidas;
+                    }
+                }
+            }
+        }
+        let fila = match fila {
+            Ok(fila) => fila,
+            Err(e) => {
+                unidas.push(Err(e));
+                return unidas;
+            }
+        };
+        if let Some(otras) = fila
+            .get(posicion_izquierda)
+            .and_then(|valor| tabla.get(valor))
+        {
+            for otra in otras {
+                let mut unida = fila.clone();
+                unida.extend(otra.iter().cloned());
+                unidas.push(Ok(unida));
+            }
+        }
+        unidas
+    }))
+}
+
+/*
+ * Devuelve la comparación entre dos filas según las claves del ORDER BY, que se comparan como texto en el
+ * orden en que se escribieron.
+ */
+fn comparar_por_claves(
+    claves: &[OrderByClause],
+    columnas: &[String],
+) -> impl Fn(&[String], &[String]) -> std::cmp::Ordering {
+    let indices: Vec<(usize, Order)> = claves
+        .iter()
+        .map(|clave| (posicion(columnas, &clave.columna), clave.orden))
+        .collect();
+    move |a, b| {
+        for (indice, orden) in indices.iter() {
+            let resultado_comparado = match orden {
+                Order::Asc => a[*indice].cmp(&b[*indice]),
+                Order::Desc => b[*indice].cmp(&a[*indice]),
+            };
+            if resultado_comparado != std::cmp::Ordering::Equal {
+                return resultado_comparado;
+            }
+        }
+        std::cmp::Ordering::Equal
+    }
+}
+
+fn proyectar(fila: Vec<String>, columnas: &[String], columnas_fila: &[String]) -> Vec<String> {
+    if columnas[0] == "*" {
+        return fila;
+    }
+    columnas
+        .iter()
+        .map(|col| fila[posicion(columnas_fila, col)].clone())
+        .collect()
+}
+
+/*
+ * Devuelve las filas que resultan de aplicar la operación a las filas de sus entradas, que tienen los valores de
+ * las columnas de cada una, y las columnas de las filas que devuelve. El orden y la agregación juntan todas las
+ * filas de la entrada la primera vez que se les pide una, el orden en memoria o por partes en disco si no entran.
+ */
+fn aplicar_operacion<'a>(
+    operacion: &'a Operacion<'a>,
+    mut entradas: Vec<(FilasTabla<'a>, Vec<String>)>,
+    ruta_plan: &str,
+) -> Result<(FilasTabla<'a>, Vec<String>), ErrorPrograma> {
+    let (entrada, columnas_entrada) = if entradas.is_empty() {
+        (Box::new(std::iter::empty()) as FilasTabla<'a>, Vec::new())
+    } else {
+        entradas.remove(0)
+    };
+    Ok(match operacion {
+        Operacion::Recorrido {
+            ruta,
+            columnas_tabla,
+            acceso,
+            columnas,
+            filtro,
+            hilos,
+            ..
+        } => (
+            recorrer_tabla(
+                ruta,
+                columnas_tabla,
+                acceso.as_ref(),
+                columnas.as_deref(),
+                filtro.as_ref(),
+                *hilos,
+            )?,
+            columnas.clone().unwrap_or_else(|| columnas_tabla.clone()),
+        ),
+        Operacion::SinFilas { columnas, .. } => (Box::new(std::iter::empty()), columnas.clone()),
+        Operacion::Filtro { predicado } => {
+            let columnas = columnas_entrada.clone();
+            let filas = Box::new(entrada.filter_map(move |fila| {
+                let fila = match fila {
+                    Ok(fila) => fila,
+                    Err(e) => return Some(Err(e)),
+                };
+                let valores: Vec<&str> = fila.iter().map(|s| s.as_str()).collect();
+                match predicado.evaluar(&valores, &columnas) {
+                    Ok(true) => Some(Ok(fila)),
+                    Ok(false) => None,
+                    Err(e) => Some(Err(e)),
+                }
+            }));
+            (filas, columnas_entrada)
+        }
+        Operacion::Join { izquierda, derecha } => {
+            let (derecha_filas, columnas_derecha) = entradas.remove(0);
+            let filas = unir_filas(
+                entrada,
+                derecha_filas,
+                posicion(&columnas_entrada, izquierda),
+                posicion(&columnas_derecha, derecha),
+            );
+            let mut columnas = columnas_entrada;
+            columnas.extend(columnas_derecha);
+            (filas, columnas)
+        }
+        Operacion::Agregacion { grupos, agregados } => {
+            let posiciones_grupos = grupos.map(|grupos| {
+                grupos
+                    .iter()
+                    .map(|grupo| posicion(&columnas_entrada, grupo))
+                    .collect()
+            });
+            let con_posicion = agregados
+                .iter()
+                .map(|agregado| {
+                    let columna = agregado
+                        .columna
+                        .as_ref()
+                        .map(|columna| posicion(&columnas_entrada, columna));
+                    (agregado.clone(), columna)
+                })
+                .collect();
+            let mut columnas: Vec<String> = grupos.unwrap_or_default().to_vec();
+            columnas.extend(agregados.iter().map(|agregado| agregado.nombre.clone()));
+            (
+                agregar_filas(entrada, posiciones_grupos, con_posicion),
+                columnas,
+            )
+        }
+        Operacion::Orden { claves } => {
+            let comparar = comparar_por_claves(claves, &columnas_entrada);
+            let ruta = ruta_plan.to_string();
+            let mut entrada = Some((entrada, comparar));
+            let mut ordenadas: FilasTabla = Box::new(std::iter::empty());
+            let filas = Box::new(std::iter::from_fn(move || {
+                if let Some((entrada, comparar)) = entrada.take() {
+                    match ordenar_filas_externo(entrada, &ruta, comparar) {
+                        Ok(filas) => ordenadas = filas,
+                        Err(e) => return Some(Err(e)),
+                    }
+                }
+                ordenadas.next()
+            }));
+            (filas, columnas_entrada)
+        }
+        Operacion::Proyeccion { columnas } => {
+            let columnas_salida = if columnas[0] == "*" {
+                columnas_entrada.clone()
+            } else {
+                columnas.to_vec()
+            };
+            let filas = Box::new(
+                entrada.map(move |fila| fila.map(|f| proyectar(f, columnas, &columnas_entrada))),
+            );
+            (filas, columnas_salida)
+        }
+        Operacion::Limite { cantidad } => (Box::new(entrada.take(*cantidad)), columnas_entrada),
+    })
+}
+
+// Cuenta las filas que devuelve una operación y el tiempo que pasa dentro de ella.
+struct Medicion<'a> {
+    filas: FilasTabla<'a>,
+    metricas: Rc<RefCell<Metricas>>,
+}
+
+impl Iterator for Medicion<'_> {
+    type Item = Result<Vec<String>, ErrorPrograma>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let inicio = Instant::now();
+        let fila = self.filas.next();
+        let mut metricas = self.metricas.borrow_mut();
+        metricas.tiempo += inicio.elapsed();
+        if let Some(Ok(_)) = fila {
+            metricas.filas += 1;
+        }
+        fila
+    }
+}
+
+/*
+ * Abre las entradas del nodo y después el nodo. Si se pide medir agrega las métricas de cada nodo en el orden
+ * en que se describe el plan: primero el nodo y después sus entradas. Lo que tarda en abrirse un nodo, como
+ * leer las filas de un índice, se suma al nodo y a los de arriba.
+ */
+fn abrir_nodo<'a>(
+    nodo: &'a Nodo<'a>,
+    ruta_plan: &str,
+    metricas: Option<&mut Vec<Rc<RefCell<Metricas>>>>,
+) -> Result<(FilasTabla<'a>, Vec<String>), ErrorPrograma> {
+    let inicio = Instant::now();
+    let Some(metricas) = metricas else {
+        let entradas = nodo
+            .entradas
+            .iter()
+            .map(|entrada| abrir_nodo(entrada, ruta_plan, None))
+            .collect::<Result<Vec<_>, _>>()?;
+        return aplicar_operacion(&nodo.operacion, entradas, ruta_plan);
+    };
+    let medicion = Rc::new(RefCell::new(Metricas::default()));
+    metricas.push(Rc::clone(&medicion));
+    let mut entradas = Vec::new();
+    for entrada in nodo.entradas.iter() {
+        entradas.push(abrir_nodo(entrada, ruta_plan, Some(&mut *metricas))?);
+    }
+    let (filas, columnas) = aplicar_operacion(&nodo.operacion, entradas, ruta_plan)?;
+    medicion.borrow_mut().tiempo = inicio.elapsed();
+    let filas = Box::new(Medicion {
+        filas,
+        metricas: medicion,
+    });
+    Ok((filas, columnas))
+}
+
+/*
+ * Ejecuta el plan y le entrega al destino las filas del resultado. Las operaciones se encadenan desde los
+ * recorridos de las tablas, y cada fila pasa por todas antes de leer la siguiente salvo en el orden, la
+ * agregación y las filas de la derecha de un join. Si se pide medir se devuelven las métricas de cada operación,
+ * en el orden del plan.
+ */
+pub(crate) fn ejecutar_plan<'a>(
+    plan: &'a Plan<'a>,
+    destino: &mut dyn DestinoFilas,
+    medir: bool,
+) -> Result<Vec<Metricas>, ErrorPrograma> {
+    let mut metricas = Vec::new();
+    let (filas, _) = abrir_nodo(&plan.raiz, &plan.ruta, medir.then_some(&mut metricas))?;
+    for fila in filas {
+        destino.fila(fila?)?;
+    }
+    Ok(metricas.iter().map(|medicion| *medicion.borrow()).collect())
+}
+
+impl Operacion<'_> {
+    // Línea de la operación en el plan, en el idioma configurado.
+    fn describir(&self) -> String {
+        match self {
+            Operacion::Recorrido {
+                tabla,
+                acceso,
+                columnas,
+                filtro,
+                hilos,
+                ..
+            } => {
+                let mut texto = match acceso {
+                    Some(acceso) => Mensaje::PlanRecorridoIndice.texto(&[
+                        &acceso.indice.nombre,
+                        acceso.indice.tipo.como_str(),
+                        tabla,
+                        &acceso.busqueda,
+                    ]),
+                    None if *hilos > 1 => {
+                        Mensaje::PlanRecorridoParalelo.texto(&[&hilos.to_string(), tabla])
+                    }
+                    None => Mensaje::PlanRecorridoSecuencial.texto(&[tabla]),
+                };
+                if let Some(columnas) = columnas {
+                    texto.push_str(&Mensaje::PlanColumnas.texto(&[&columnas.join(", ")]));
+                }
+                if let Some(filtro) = filtro {
+                    texto.push_str(&Mensaje::PlanFiltroRecorrido.texto(&[&filtro.describir()]));
+                }
+                texto
+            }
+            Operacion::SinFilas { tabla, .. } => Mensaje::PlanSinFilas.texto(&[tabla]),
+            Operacion::Filtro { predicado } => Mensaje::PlanFiltro.texto(&[&predicado.describir()]),
+            Operacion::Join { izquierda, derecha } => {
+                Mensaje::PlanJoin.texto(&[izquierda, derecha])
+            }
+            Operacion::Agregacion { grupos, agregados } => {
+                let mut texto = Mensaje::PlanAgregacion.texto(&[]);
+                if !agregados.is_empty() {
+                    let nombres: Vec<&str> = agregados.iter().map(|a| a.nombre.as_str()).collect();
+                    texto.push_str(&format!(": {}", nombres.join(", ")));
+                }
+                if let Some(grupos) = grupos {
+                    texto.push_str(&Mensaje::PlanGrupos.texto(&[&grupos.join(", ")]));
+                }
+                texto
+            }
+            Operacion::Orden { claves } => {
+                let claves: Vec<String> = claves
+                    .iter()
+                    .map(|clave| format!("{} {}", clave.columna, clave.orden.como_str()))
+                    .collect();
+                Mensaje::PlanOrden.texto(&[&claves.join(", ")])
+            }
+            Operacion::Proyeccion { columnas } => {
+                Mensaje::PlanProyeccion.texto(&[&columnas.join(", ")])
+            }
+            Operacion::Limite { cantidad } => Mensaje::PlanLimite.texto(&[&cantidad.to_string()]),
+        }
+    }
+}
+
+fn formatear_tiempo(tiempo: Duration) -> String {
+    format!("{:.3} ms", tiempo.as_secs_f64() * 1000.0)
+}
+
+impl Nodo<'_> {
+    // Agrega la línea del nodo y debajo las de sus entradas, más indentadas.
+    fn describir(&self, nivel: usize, metricas: Option<&[Metricas]>, lineas: &mut Vec<String>) {
+        let prefijo = if nivel == 0 {
+            String::new()
+        } else {
+            format!("{}-> ", " ".repeat(4 * nivel - 2))
+        };
+        let mut linea = format!("{}{}", prefijo, self.operacion.describir());
+        if let Some(metrica) = metricas.and_then(|metricas| metricas.get(lineas.len())) {
+            linea.push_str(&Mensaje::PlanMetricas.texto(&[
+                &metrica.filas.to_string(),
+                &formatear_tiempo(metrica.tiempo),
+            ]));
+        }
+        lineas.push(linea);
+        for entrada in self.entradas.iter() {
+            entrada.describir(nivel + 1, metricas, lineas);
+        }
+    }
+}
+
+impl Plan<'_> {
+    /*
+     * Devuelve una línea por operación, con las de entrada debajo y más indentadas. Si se reciben
+     * métricas cada operación indica cuántas filas devolvió y cuánto tardó.
+     */
+    pub fn describir(&self, metricas: Option<&[Metricas]>) -> Vec<String> {
+        let mut lineas = Vec::new();
+        self.raiz.describir(0, metricas, &mut lineas);
+        lineas
+    }
+}
+
+// Destino que descarta las filas del resultado, para EXPLAIN ANALYZE.
+struct DestinoDescartado;
+
+impl DestinoFilas for DestinoDescartado {
+    fn encabezado(&mut self, _columnas: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+
+    fn fila(&mut self, _valores: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+}
+
+/*
+ * Escribe el plan en el destino, una línea por fila. Con ANALYZE además se ejecuta la consulta, descartando
+ * su resultado, y se agregan las métricas de cada operación y el tiempo total.
+ */
+pub(crate) fn escribir_explain(
+    plan: &Plan,
+    analizar: bool,
+    destino: &mut dyn DestinoFilas,
+) -> Result<(), ErrorPrograma> {
+    let (lineas_plan, total) = if analizar {
+        let inicio = Instant::now();
+        let metricas = ejecutar_plan(plan, &mut DestinoDescartado, true)?;
+        (plan.describir(Some(&metricas)), Some(inicio.elapsed()))
+    } else {
+        (plan.describir(None), None)
+    };
+
+    destino.encabezado(&["plan".to_string()])?;
+    for linea in lineas_plan {
+        destino.fila(vec![linea])?;
+    }
+    if let Some(total) = total {
+        destino.fila(vec![
+            Mensaje::PlanTiempoTotal.texto(&[&formatear_tiempo(total)])
+        ])?;
+    }
+    Ok(())
+}
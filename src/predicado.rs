@@ -0,0 +1,199 @@
+use crate::errors::ErrorPrograma;
+use crate::query_identifier::{LogicalOperators, WhereClause};
+use crate::read_file::cumple_condicion;
+
+/*
+ * Condición del WHERE lista para evaluar sobre una fila. AND tiene precedencia sobre OR, así que las condiciones
+ * se agrupan en conjunciones unidas por OR: `a AND b OR c` es `(a AND b) OR c`. Al plegar las constantes el
+ * predicado puede quedar en un valor fijo.
+ */
+#[derive(Debug, Clone)]
+pub enum Predicado<'a> {
+    Constante(bool),
+    Grupos(Vec<Vec<&'a WhereClause>>),
+}
+
+fn es_columna(valor: &str, columnas_csv: &[String]) -> bool {
+    let valor = valor.trim_matches('\'');
+    columnas_csv.iter().any(|c| c == valor)
+}
+
+// Un entero negativo en el WHERE es un error al evaluarlo, que podría no llegar a ocurrir si no se evalúa.
+pub(crate) fn valor_sin_error(valor: &str, columnas_csv: &[String]) -> bool {
+    let valor = valor.trim_matches('\'');
+    es_columna(valor, columnas_csv)
+        || valor.parse::<i32>().is_err()
+        || valor.parse::<usize>().is_ok()
+}
+
+impl<'a> Predicado<'a> {
+    /*
+     * Arma el predicado con las condiciones del WHERE y los operadores entre ellas, donde el operador i une la
+     * condición i con la siguiente. Sin condiciones todas las filas lo cumplen.
+     */
+    pub fn desde_where(condiciones: &'a [WhereClause], operadores: &[LogicalOperators]) -> Self {
+        if condiciones.is_empty() {
+            return Predicado::Constante(true);
+        }
+        let mut grupos = vec![Vec::new()];
+        for (indice, condicion) in condiciones.iter().enumerate() {
+            if let Some(grupo) = grupos.last_mut() {
+                grupo.push(condicion);
+            }
+            if indice + 1 < condiciones.len()
+                && operadores.get(indice) != Some(&LogicalOperators::And)
+            {
+                grupos.push(Vec::new());
+            }
+        }
+        Predicado::Grupos(grupos)
+    }
+
+    /*
+     * Evalúa el predicado con los valores de una fila, en el orden de las columnas. Se evalúan todas las
+     * condiciones, así un valor inválido da error aunque el resultado ya se conozca.
+     */
+    pub fn evaluar(
+        &self,
+        valores: &[&str],
+        columnas_csv: &[String],
+    ) -> Result<bool, ErrorPrograma> {
+        let grupos = match self {
+            Predicado::Constante(valor) => return Ok(*valor),
+            Predicado::Grupos(grupos) => grupos,
+        };
+        let mut resultado = false;
+        for grupo in grupos {
+            let mut cumple = true;
+            for condicion in grupo {
+                cumple &= cumple_condicion(valores, columnas_csv, condicion)?;
+            }
+            resultado |= cumple;
+        }
+        Ok(resultado)
+    }
+
+    /*
+     * Evalúa las condiciones que no comparan ninguna columna, como `1 = 1`, y simplifica el predicado con su
+     * resultado. Si alguna condición puede dar error no se pliega nada, para que el error siga ocurriendo.
+     */
+    pub fn plegar_constantes(self, columnas_csv: &[String]) -> Self {
+        let grupos = match self {
+            Predicado::Grupos(ref grupos) => grupos,
+            Predicado::Constante(_) => return self,
+        };
+        let sin_errores = grupos.iter().flatten().all(|condicion| {
+            valor_sin_error(&condicion.valor1, columnas_csv)
+                && valor_sin_error(&condicion.valor2, columnas_csv)
+        });
+        if !sin_errores {
+            return self;
+        }
+
+        let mut plegados = Vec::new();
+        for grupo in grupos {
+            let mut restantes = Vec::new();
+            let mut falso = false;
+            for condicion in grupo {
+                if es_columna(&condicion.valor1, columnas_csv)
+                    || es_columna(&condicion.valor2, columnas_csv)
+                {
+                    restantes.push(*condicion);
+                    continue;
+                }
+                match cumple_condicion(&[], columnas_csv, condicion) {
+                    Ok(true) => {}
+                    Ok(false) => falso = true,
+                    Err(_) => restantes.push(*condicion),
+                }
+            }
+            if falso {
+                continue;
+            }
+            if restantes.is_empty() {
+                return Predicado::Constante(true);
+            }
+            plegados.push(restantes);
+        }
+        if plegados.is_empty() {
+            Predicado::Constante(false)
+        } else {
+            Predicado::Grupos(plegados)
+        }
+    }
+
+    /*
+     * Une dos predicados con AND. Como cada uno es un OR de conjunciones, el resultado tiene una conjunción por
+     * cada par de conjunciones de los dos.
+     */
+    pub fn y(self, otro: Predicado<'a>) -> Self {
+        match (self, otro) {
+            (Predicado::Constante(true), predicado) | (predicado, Predicado::Constante(true)) => {
+                predicado
+            }
+            (Predicado::Constante(false), _) | (_, Predicado::Constante(false)) => {
+                Predicado::Constante(false)
+            }
+            (Predicado::Grupos(grupos), Predicado::Grupos(otros)) => Predicado::Grupos(
+                grupos
+                    .iter()
+                    .flat_map(|grupo| {
+                        otros
+                            .iter()
+                            .map(move |otro| grupo.iter().chain(otro.iter()).copied().collect())
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    // Columnas de la tabla que se comparan en el predicado.
+    pub fn columnas(&self, columnas_csv: &[String]) -> Vec<&'a str> {
+        let grupos = match self {
+            Predicado::Constante(_) => return Vec::new(),
+            Predicado::Grupos(grupos) => grupos,
+        };
+        grupos
+            .iter()
+            .flatten()
+            .flat_map(|condicion| [condicion.valor1.as_str(), condicion.valor2.as_str()])
+            .filter(|valor| es_columna(valor, columnas_csv))
+            .map(|valor| valor.trim_matches('\''))
+            .collect()
+    }
+
+    // Condiciones que tiene que cumplir toda fila del resultado: las del único grupo, si no hay OR.
+    pub fn condiciones_necesarias(&self) -> &[&'a WhereClause] {
+        match self {
+            Predicado::Grupos(grupos) if grupos.len() == 1 => &grupos[0],
+            _ => &[],
+        }
+    }
+
+    pub fn describir(&self) -> String {
+        let grupos = match self {
+            Predicado::Constante(true) => return "TRUE".to_string(),
+            Predicado::Constante(false) => return "FALSE".to_string(),
+            Predicado::Grupos(grupos) => grupos,
+        };
+        let grupos: Vec<String> = grupos
+            .iter()
+            .map(|grupo| {
+                let condiciones: Vec<String> = grupo
+                    .iter()
+                    .map(|condicion| {
+                        format!(
+                            "{}{} {} {}",
+                            if condicion.es_not { "NOT " } else { "" },
+                            condicion.valor1,
+                            condicion.comparacion.como_str(),
+                            condicion.valor2
+                        )
+                    })
+                    .collect();
+                condiciones.join(" AND ")
+            })
+            .collect();
+        grupos.join(" OR ")
+    }
+}
@@ -1,18 +1,38 @@
+use crate::bitacora::{aplicar_recover, recuperar_directorio};
 use crate::errors::{ErrorPrograma, ErrorTipo};
+use crate::esquema::{AccionReferencial, ForeignKey, TipoIndice};
+use crate::indices::{aplicar_create_index, aplicar_drop_index};
+use crate::mensajes::Mensaje;
+use crate::read_file::aplicar_alter_table;
+use crate::read_file::aplicar_copy_from;
 use crate::read_file::aplicar_delete;
 use crate::read_file::aplicar_insert;
-use crate::read_file::aplicar_select;
 use crate::read_file::aplicar_update;
-use std::error::Error;
+use crate::read_file::ejecutar_select;
+use crate::read_file::escribir_returning;
+use crate::read_file::explicar_select;
+use crate::read_file::exportar_select;
+use crate::salida::{DestinoFilas, Formato, SalidaEstandar};
 use std::iter::Peekable;
 use std::str::SplitWhitespace;
 
+const COMPARACIONES: [&str; 6] = ["=", "!=", "<", ">", "<=", ">="];
+
+// Query sin la cláusula RETURNING y las columnas pedidas en ella.
+type ResultSepararReturning<'a> = Result<(&'a str, Option<Vec<String>>), ErrorPrograma>;
+
 #[derive(Debug)]
 enum QueryType {
     Select,
     Insert,
     Update,
     Delete,
+    AlterTable,
+    Recover,
+    Copy,
+    CreateIndex,
+    DropIndex,
+    Explain,
     Desconocido,
 }
 
@@ -22,15 +42,76 @@ pub struct Insert {
     pub tabla: String,
     pub nombre_col: String,
     pub valores: Vec<String>,
+    pub default_values: bool,
+    pub select: Option<Select>,
+    pub returning: Option<Vec<String>>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct Select {
     pub columnas: Vec<String>,
     pub tabla: String,
     pub where_clauses: Option<Vec<WhereClause>>,
     pub order_by: Option<Vec<OrderByClause>>,
     pub operacion_logica: Option<Vec<LogicalOperators>>,
+    pub joins: Vec<JoinClause>,
+    pub group_by: Option<Vec<String>>,
+    pub limit: Option<usize>,
+}
+
+/*
+ * Tabla que se une a las anteriores del SELECT con [INNER] JOIN <tabla> ON <columna> = <columna>. Se quedan las
+ * combinaciones de filas en las que la columna de la izquierda, de alguna tabla anterior, tiene el mismo valor
+ * que la de la derecha, de la tabla que se une.
+ */
+#[derive(Debug, PartialEq, Clone)]
+pub struct JoinClause {
+    pub tabla: String,
+    pub columna_izquierda: String,
+    pub columna_derecha: String,
+}
+
+/*
+ * Archivo en el que se escribe el resultado de un SELECT con INTO OUTFILE o COPY ... TO.
+ */
+#[derive(Debug, PartialEq)]
+pub struct Exportacion {
+    pub ruta: String,
+    pub formato: Formato,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CopyTo {
+    pub select: Select,
+    pub exportacion: Exportacion,
+}
+
+/*
+ * Importación de un archivo csv al final de una tabla. Si el archivo tiene encabezado sus columnas
+ * se mapean por nombre, si no se toman en el orden de las columnas indicadas o de la tabla.
+ */
+#[derive(Debug, PartialEq)]
+pub struct CopyFrom {
+    pub tabla: String,
+    pub columnas: Option<Vec<String>>,
+    pub archivo: String,
+    pub encabezado: bool,
+    pub delimitador: char,
+}
+
+// Índice sobre una columna de la tabla, que se crea con CREATE INDEX.
+#[derive(Debug, PartialEq)]
+pub struct CreateIndex {
+    pub nombre: String,
+    pub tabla: String,
+    pub columna: String,
+    pub tipo: TipoIndice,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct DropIndex {
+    pub nombre: String,
+    pub tabla: Option<String>,
 }
 
 #[derive(Debug)]
@@ -39,6 +120,7 @@ pub struct Update {
     pub set: String,
     pub where_clauses: Option<Vec<WhereClause>>,
     pub operacion_logica: Option<Vec<LogicalOperators>>,
+    pub returning: Option<Vec<String>>,
 }
 
 #[derive(Debug)]
@@ -46,9 +128,24 @@ pub struct Delete {
     pub tabla: String,
     pub where_clauses: Option<Vec<WhereClause>>,
     pub operacion_logica: Option<Vec<LogicalOperators>>,
+    pub returning: Option<Vec<String>>,
 }
 
 #[derive(Debug)]
+pub struct AlterTable {
+    pub tabla: String,
+    pub accion: AccionAlterTable,
+}
+
+#[derive(Debug)]
+pub enum AccionAlterTable {
+    AgregarForeignKey(ForeignKey),
+    AgregarAutoIncrement(String),
+    AgregarDefault(String, String),
+    EliminarDefault(String),
+}
+
+#[derive(Debug, PartialEq, Clone)]
 pub struct WhereClause {
     pub valor1: String,
     pub comparacion: Comparacion,
@@ -56,19 +153,19 @@ pub struct WhereClause {
     pub es_not: bool,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct OrderByClause {
     pub columna: String,
     pub orden: Order,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum LogicalOperators {
     And,
     Or,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum Comparacion {
     Mayor,
     Menor,
@@ -78,18 +175,43 @@ pub enum Comparacion {
     Diferente,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Order {
     Asc,
     Desc,
 }
 
+impl Comparacion {
+    pub fn como_str(&self) -> &str {
+        match self {
+            Comparacion::Mayor => ">",
+            Comparacion::Menor => "<",
+            Comparacion::Igual => "=",
+            Comparacion::MayorIgual => ">=",
+            Comparacion::MenorIgual => "<=",
+            Comparacion::Diferente => "!=",
+        }
+    }
+}
+
+impl Order {
+    pub fn como_str(&self) -> &str {
+        match self {
+            Order::Asc => "ASC",
+            Order::Desc => "DESC",
+        }
+    }
+}
+
 impl Insert {
     pub fn new(tabla: &str, columnas: &str, values: Vec<String>) -> Self {
         Insert {
             tabla: tabla.to_string(),
             nombre_col: columnas.to_string(),
             valores: values,
+            default_values: false,
+            select: None,
+            returning: None,
         }
     }
 
@@ -98,9 +220,15 @@ impl Insert {
         let caracteres_iter = linea_values.chars().peekable();
         let mut string_actual = String::new();
         let mut dentro_parentesis = false;
+        let mut dentro_comillas = false;
 
         for caracter in caracteres_iter {
             match caracter {
+                '\'' if dentro_parentesis => {
+                    dentro_comillas = !dentro_comillas;
+                    string_actual.push(caracter);
+                }
+                _ if dentro_comillas => string_actual.push(caracter),
                 '(' => {
                     dentro_parentesis = true;
                     string_actual = String::new();
@@ -120,37 +248,413 @@ impl Insert {
         valores_a_insertar
     }
 
+    fn verificar_nombre_tabla(nombre_tabla: &str) -> Result<(), ErrorPrograma> {
+        if nombre_tabla.is_empty() || nombre_tabla.contains(char::is_whitespace) {
+            return Err(ErrorPrograma::desde_mensaje(
+                ErrorTipo::Syntax,
+                Mensaje::InsertTablaInvalida,
+                &[nombre_tabla],
+            ));
+        }
+        Ok(())
+    }
+
+    /*
+     * Recibe el inicio de la query hasta VALUES o SELECT y devuelve el nombre de la tabla
+     * y la lista de columnas, que queda vacía si no se indicó.
+     */
+    fn separar_tabla_y_columnas(linea_tabla: &str) -> Result<(&str, &str), ErrorPrograma> {
+        let linea_tabla = linea_tabla["INSERT INTO ".len()..].trim();
+        let (nombre_tabla, nombre_columnas) = match linea_tabla.find("(") {
+            Some(pos_columnas_ini) => {
+                let pos_columnas_fin = linea_tabla.find(")").ok_or_else(|| {
+                    ErrorPrograma::desde_mensaje(
+                        ErrorTipo::Syntax,
+                        Mensaje::InsertColumnasSinCerrar,
+                        &[],
+                    )
+                })?;
+                (
+                    linea_tabla[..pos_columnas_ini].trim(),
+                    linea_tabla[1 + pos_columnas_ini..pos_columnas_fin].trim(),
+                )
+            }
+            None => (linea_tabla.trim(), ""),
+        };
+        Self::verificar_nombre_tabla(nombre_tabla)?;
+        Ok((nombre_tabla, nombre_columnas))
+    }
+
     /*
      * Se recibe un string de la query tipo insert y se parsea para obtener el nombre de la tabla,
-     * las columnas y los valores a insertar.
+     * las columnas y los valores a insertar. La lista de columnas es opcional, si no se indica
+     * los valores se insertan en el orden del encabezado del csv. Con DEFAULT VALUES se inserta
+     * una única fila con los valores por defecto de cada columna. Con INSERT INTO ... SELECT
+     * se guarda el select cuyas filas se van a insertar.
      */
-    pub fn insert_parsear_query(query: &str) -> Result<Self, Box<dyn Error>> {
-        let posicion_values = query.find("VALUES").ok_or(Box::new(ErrorPrograma::new(
-            ErrorTipo::Syntax,
-            "No se pudo encontrar VALUES en la query.",
-        )))?;
+    pub fn insert_parsear_query(query: &str) -> Result<Self, ErrorPrograma> {
+        let (query, returning) = separar_returning(query)?;
+        let mut insert = Self::insert_parsear_sin_returning(query)?;
+        insert.returning = returning;
+        Ok(insert)
+    }
+
+    fn insert_parsear_sin_returning(query: &str) -> Result<Self, ErrorPrograma> {
+        let query_limpia = query.trim().trim_end_matches(';').trim_end();
+        if let Some(linea_tabla) = query_limpia.strip_suffix("DEFAULT VALUES") {
+            let nombre_tabla = linea_tabla["INSERT INTO ".len()..].trim();
+            Self::verificar_nombre_tabla(nombre_tabla)?;
+            let mut insert = Insert::new(nombre_tabla, "", Vec::new());
+            insert.default_values = true;
+            return Ok(insert);
+        }
+
+        let posicion_select = query.find("SELECT");
+        if let Some(posicion_select) = posicion_select.filter(|&pos| {
+            query
+                .find("VALUES")
+                .is_none_or(|posicion_values| pos < posicion_values)
+        }) {
+            let (nombre_tabla, nombre_columnas) =
+                Self::separar_tabla_y_columnas(query[..posicion_select].trim())?;
+            let select = Select::select_parsear_query(query[posicion_select..].trim())?;
+            let mut insert = Insert::new(nombre_tabla, nombre_columnas, Vec::new());
+            insert.select = Some(select);
+            return Ok(insert);
+        }
+
+        let posicion_values = query.find("VALUES").ok_or_else(|| {
+            ErrorPrograma::desde_mensaje(ErrorTipo::Syntax, Mensaje::InsertSinValues, &[])
+        })?;
 
         let linea_tabla = &query[..posicion_values].trim();
         let linea_values = &query[posicion_values + "VALUES".len()..].trim();
 
-        let linea_tabla = &linea_tabla["INSERT INTO ".len()..].trim();
-        let pos_columnas_ini = linea_tabla.find("(").ok_or(Box::new(ErrorPrograma::new(
+        let (nombre_tabla, nombre_columnas) = Self::separar_tabla_y_columnas(linea_tabla)?;
+
+        let valores_a_insertar = Self::separar_insert_clauses(linea_values);
+        if valores_a_insertar.is_empty() {
+            return Err(ErrorPrograma::desde_mensaje(
+                ErrorTipo::Syntax,
+                Mensaje::InsertSinValores,
+                &[],
+            ));
+        }
+
+        let insert = Insert::new(nombre_tabla, nombre_columnas, valores_a_insertar);
+        Ok(insert)
+    }
+}
+
+/*
+ * Separa la cláusula RETURNING del final de una query INSERT, UPDATE o DELETE.
+ * Devuelve la query sin la cláusula y las columnas pedidas, o None si la query no tiene RETURNING.
+ */
+fn separar_returning(query: &str) -> ResultSepararReturning<'_> {
+    let query = query.trim().trim_end_matches(';').trim_end();
+    let posicion_returning = match buscar_palabra_clave(query, "RETURNING") {
+        Some(pos) => pos,
+        None => return Ok((query, None)),
+    };
+
+    let columnas: Vec<String> = query[posicion_returning + "RETURNING".len()..]
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .collect();
+    if columnas.iter().any(|columna| columna.is_empty())
+        || (columnas.len() > 1 && columnas.contains(&"*".to_string()))
+    {
+        return Err(ErrorPrograma::desde_mensaje(
             ErrorTipo::Syntax,
-            "Revise si las columnas de la tabla donde insertará se encuentran entre parentesís.",
-        )))?;
-        let pos_columnas_fin = linea_tabla.find(")").ok_or(Box::new(ErrorPrograma::new(
+            Mensaje::ReturningInvalido,
+            &[],
+        ));
+    }
+    Ok((query[..posicion_returning].trim_end(), Some(columnas)))
+}
+
+/*
+ * Busca la última aparición de la palabra clave en la query que no está dentro de un valor entre comillas simples
+ * y está separada por espacios, así un valor como 'no RETURNING id' no se toma como la cláusula.
+ */
+fn buscar_palabra_clave(query: &str, palabra: &str) -> Option<usize> {
+    let mut encontrado = None;
+    let mut entre_comillas = false;
+    let mut anterior_es_espacio = false;
+    for (posicion, caracter) in query.char_indices() {
+        if caracter == '\'' {
+            entre_comillas = !entre_comillas;
+        } else if !entre_comillas
+            && anterior_es_espacio
+            && query[posicion..].starts_with(palabra)
+            && query[posicion + palabra.len()..]
+                .chars()
+                .next()
+                .is_none_or(char::is_whitespace)
+        {
+            encontrado = Some(posicion);
+        }
+        anterior_es_espacio = caracter.is_whitespace();
+    }
+    encontrado
+}
+
+fn error_exportacion() -> ErrorPrograma {
+    ErrorPrograma::desde_mensaje(ErrorTipo::Syntax, Mensaje::ArchivoSalidaInvalido, &[])
+}
+
+/*
+ * Lee el archivo entre comillas simples y el formato opcional que lo sigue, como FORMAT json o WITH (FORMAT json).
+ * Si no se indica formato se escribe en CSV.
+ */
+fn clasificar_exportacion(texto: &str) -> Result<Exportacion, ErrorPrograma> {
+    let texto = texto.trim().trim_end_matches(';').trim();
+    let resto = texto.strip_prefix('\'').ok_or_else(error_exportacion)?;
+    let fin_ruta = resto.find('\'').ok_or_else(error_exportacion)?;
+    let ruta = &resto[..fin_ruta];
+    if ruta.is_empty() {
+        return Err(error_exportacion());
+    }
+
+    let opciones = resto[fin_ruta + 1..].trim();
+    let opciones = match opciones.strip_prefix("WITH") {
+        Some(opciones) => opciones
+            .trim()
+            .strip_prefix('(')
+            .and_then(|o| o.strip_suffix(')'))
+            .ok_or_else(error_exportacion)?,
+        None => opciones,
+    };
+    let formato = match opciones
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .as_slice()
+    {
+        [] => Formato::Csv,
+        ["FORMAT", formato] => Formato::desde_str(&formato.to_lowercase())?,
+        _ => return Err(error_exportacion()),
+    };
+    Ok(Exportacion {
+        ruta: ruta.to_string(),
+        formato,
+    })
+}
+
+/*
+ * Separa la cláusula INTO OUTFILE del final de un SELECT, sin tomar la que esté dentro de un valor entre comillas.
+ * Devuelve el SELECT sin la cláusula y el archivo de salida, o None si el resultado se muestra por pantalla.
+ */
+fn separar_into_outfile(query: &str) -> Result<(&str, Option<Exportacion>), ErrorPrograma> {
+    match buscar_palabra_clave(query, "INTO OUTFILE") {
+        Some(posicion) => {
+            let exportacion = clasificar_exportacion(&query[posicion + "INTO OUTFILE".len()..])?;
+            Ok((query[..posicion].trim_end(), Some(exportacion)))
+        }
+        None => Ok((query, None)),
+    }
+}
+
+impl CopyTo {
+    /*
+     * Se parsea una query COPY (SELECT ...) TO '<archivo>' [FORMAT <formato>]. El SELECT termina en el
+     * paréntesis que cierra al que lo abre, sin contar los que estén dentro de strings.
+     */
+    pub fn copy_to_parsear_query(query: &str) -> Result<Self, ErrorPrograma> {
+        let error_sintaxis =
+            || ErrorPrograma::desde_mensaje(ErrorTipo::Syntax, Mensaje::CopyToInvalido, &[]);
+        let resto = query["COPY".len()..].trim_start();
+        let resto = resto.strip_prefix('(').ok_or_else(error_sintaxis)?;
+
+        let mut profundidad = 1;
+        let mut en_string = false;
+        let mut fin_select = None;
+        for (posicion, caracter) in resto.char_indices() {
+            match caracter {
+                '\'' => en_string = !en_string,
+                '(' if !en_string => profundidad += 1,
+                ')' if !en_string => {
+                    profundidad -= 1;
+                    if profundidad == 0 {
+                        fin_select = Some(posicion);
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+        let fin_select = fin_select.ok_or_else(error_sintaxis)?;
+        let select = resto[..fin_select].trim();
+        if !select.starts_with("SELECT") {
+            return Err(error_sintaxis());
+        }
+        let destino = resto[fin_select + 1..]
+            .trim_start()
+            .strip_prefix("TO")
+            .ok_or_else(error_sintaxis)?;
+
+        Ok(CopyTo {
+            select: Select::select_parsear_query(select)?,
+            exportacion: clasificar_exportacion(destino)?,
+        })
+    }
+}
+
+impl CopyFrom {
+    /*
+     * Se parsea una query COPY <tabla> [(<columnas>)] FROM '<archivo>' [WITH (HEADER, DELIMITER '<caracter>')].
+     * Sin opciones el archivo no tiene encabezado y sus valores se separan con comas.
+     */
+    pub fn copy_from_parsear_query(query: &str) -> Result<Self, ErrorPrograma> {
+        let error_sintaxis =
+            || ErrorPrograma::desde_mensaje(ErrorTipo::Syntax, Mensaje::CopyFromInvalido, &[]);
+        let query = query.trim().trim_end_matches(';').trim_end();
+        let posicion_from = query.find(" FROM '").ok_or_else(error_sintaxis)?;
+        let destino = query["COPY".len()..posicion_from].trim();
+
+        let (tabla, columnas) = match destino.find('(') {
+            Some(inicio) => {
+                let columnas = destino[inicio + 1..]
+                    .strip_suffix(')')
+                    .ok_or_else(error_sintaxis)?;
+                let columnas: Vec<String> =
+                    columnas.split(',').map(|c| c.trim().to_string()).collect();
+                if columnas.iter().any(|c| c.is_empty()) {
+                    return Err(error_sintaxis());
+                }
+                (destino[..inicio].trim(), Some(columnas))
+            }
+            None => (destino, None),
+        };
+        if tabla.is_empty() || tabla.contains(char::is_whitespace) {
+            return Err(error_sintaxis());
+        }
+
+        let resto = &query[posicion_from + " FROM '".len()..];
+        let fin_archivo = resto.find('\'').ok_or_else(error_sintaxis)?;
+        let archivo = &resto[..fin_archivo];
+        if archivo.is_empty() {
+            return Err(error_sintaxis());
+        }
+
+        let mut copy = CopyFrom {
+            tabla: tabla.to_string(),
+            columnas,
+            archivo: archivo.to_string(),
+            encabezado: false,
+            delimitador: ',',
+        };
+        let opciones = resto[fin_archivo + 1..].trim();
+        if opciones.is_empty() {
+            return Ok(copy);
+        }
+        let opciones = opciones
+            .strip_prefix("WITH")
+            .map(|o| o.trim())
+            .and_then(|o| o.strip_prefix('('))
+            .and_then(|o| o.strip_suffix(')'))
+            .ok_or_else(error_sintaxis)?;
+        for opcion in opciones.split(',') {
+            match opcion.split_whitespace().collect::<Vec<&str>>().as_slice() {
+                ["HEADER"] | ["HEADER", "TRUE"] => copy.encabezado = true,
+                ["HEADER", "FALSE"] => copy.encabezado = false,
+                ["DELIMITER", delimitador] => {
+                    copy.delimitador = match delimitador.chars().collect::<Vec<char>>().as_slice() {
+                        ['\'', caracter, '\''] if *caracter != '"' && *caracter != '\n' => {
+                            *caracter
+                        }
+                        _ => {
+                            return Err(ErrorPrograma::desde_mensaje(
+                                ErrorTipo::Syntax,
+                                Mensaje::DelimitadorInvalido,
+                                &[],
+                            ))
+                        }
+                    }
+                }
+                _ => return Err(error_sintaxis()),
+            }
+        }
+        Ok(copy)
+    }
+}
+
+/*
+ * Verifica que el nombre de un índice tenga solo letras, números y '_', ya que forma parte del nombre de su archivo.
+ */
+fn verificar_nombre_indice(nombre: &str) -> Result<(), ErrorPrograma> {
+    if nombre.is_empty() || !nombre.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return Err(ErrorPrograma::desde_mensaje(
             ErrorTipo::Syntax,
-            "Revise si las columnas de la tabla donde insertará se encuentran entre parentesís.",
-        )))?;
+            Mensaje::NombreIndiceInvalido,
+            &[nombre],
+        )
+        .con_token(nombre));
+    }
+    Ok(())
+}
 
-        let nombre_tabla = &linea_tabla[..pos_columnas_ini].trim();
+impl CreateIndex {
+    /*
+     * Se parsea una query CREATE INDEX <nombre> ON <tabla> [USING <tipo>] (<columna>). Sin USING el índice es HASH.
+     */
+    pub fn create_index_parsear_query(query: &str) -> Result<Self, ErrorPrograma> {
+        let error_sintaxis =
+            || ErrorPrograma::desde_mensaje(ErrorTipo::Syntax, Mensaje::CreateIndexInvalido, &[]);
+        let query = query.trim().trim_end_matches(';').trim_end();
+        let resto = query["CREATE INDEX".len()..].trim();
+        let inicio_columna = resto.find('(').ok_or_else(error_sintaxis)?;
+        let columna = resto[inicio_columna + 1..]
+            .strip_suffix(')')
+            .ok_or_else(error_sintaxis)?
+            .trim();
+        if columna.is_empty() || columna.contains(|c: char| c == ',' || c.is_whitespace()) {
+            return Err(error_sintaxis());
+        }
 
-        let nombre_columnas = &linea_tabla[1 + pos_columnas_ini..pos_columnas_fin].trim();
+        let palabras: Vec<&str> = resto[..inicio_columna].split_whitespace().collect();
+        let (nombre, tabla, tipo) = match palabras.as_slice() {
+            [nombre, "ON", tabla] => (*nombre, *tabla, TipoIndice::Hash),
+            [nombre, "ON", tabla, "USING", tipo] => (
+                *nombre,
+                *tabla,
+                TipoIndice::desde_str(tipo).map_err(|e| ErrorPrograma::from(e).con_token(tipo))?,
+            ),
+            _ => return Err(error_sintaxis()),
+        };
+        verificar_nombre_indice(nombre)?;
 
-        let valores_a_insertar = Self::separar_insert_clauses(linea_values);
+        Ok(CreateIndex {
+            nombre: nombre.to_string(),
+            tabla: tabla.to_string(),
+            columna: columna.to_string(),
+            tipo,
+        })
+    }
+}
 
-        let insert = Insert::new(nombre_tabla, nombre_columnas, valores_a_insertar);
-        Ok(insert)
+impl DropIndex {
+    /*
+     * Se parsea una query DROP INDEX <nombre> [ON <tabla>].
+     */
+    pub fn drop_index_parsear_query(query: &str) -> Result<Self, ErrorPrograma> {
+        let query = query.trim().trim_end_matches(';').trim_end();
+        let palabras: Vec<&str> = query["DROP INDEX".len()..].split_whitespace().collect();
+        let (nombre, tabla) = match palabras.as_slice() {
+            [nombre] => (*nombre, None),
+            [nombre, "ON", tabla] => (*nombre, Some(tabla.to_string())),
+            _ => {
+                return Err(ErrorPrograma::desde_mensaje(
+                    ErrorTipo::Syntax,
+                    Mensaje::DropIndexInvalido,
+                    &[],
+                ))
+            }
+        };
+        verificar_nombre_indice(nombre)?;
+        Ok(DropIndex {
+            nombre: nombre.to_string(),
+            tabla,
+        })
     }
 }
 
@@ -180,39 +684,47 @@ impl WhereClause {
     }
     fn clasificar_valores_clause(
         iter: &mut Peekable<SplitWhitespace>,
-    ) -> Result<(String, Comparacion, String), Box<dyn Error>> {
-        let valor1 = iter.next().ok_or(Box::new(ErrorPrograma::new(
-            ErrorTipo::Syntax,
-            "No se encontró un valor en la cláusula WHERE, recuerde que la clause debe ser \"valor1 comparacion valor2\".",
-        )))?;
+    ) -> Result<(String, Comparacion, String), ErrorPrograma> {
+        let valor1 = iter.next().ok_or_else(|| {
+            ErrorPrograma::desde_mensaje(ErrorTipo::Syntax, Mensaje::WhereSinValor, &[])
+                .con_esperados(&["<valor>"])
+                .al_final_de_la_query()
+        })?;
 
-        let comparacion = match iter.next().ok_or(Box::new(ErrorPrograma::new(
-            ErrorTipo::Syntax,
-            "No se encontró una comparación en la cláusula WHERE, recuerde que la clause debe ser \"valor1 comparacion valor2\".",
-        )))? {
+        let comparacion = match iter.next().ok_or_else(|| {
+            ErrorPrograma::desde_mensaje(ErrorTipo::Syntax, Mensaje::WhereSinComparacion, &[])
+                .con_esperados(&COMPARACIONES)
+                .al_final_de_la_query()
+        })? {
             ">" => Comparacion::Mayor,
             "<" => Comparacion::Menor,
             "=" => Comparacion::Igual,
             ">=" => Comparacion::MayorIgual,
             "<=" => Comparacion::MenorIgual,
             "!=" => Comparacion::Diferente,
-            _ => return Err(Box::new(ErrorPrograma::new(
-                ErrorTipo::Syntax,
-                "El tipo de comparación ingresado no es válido, recuerde que estos pueden ser: =, !=, <, >, <= o >=.",
-            ))),
+            token => {
+                return Err(ErrorPrograma::desde_mensaje(
+                    ErrorTipo::Syntax,
+                    Mensaje::ComparacionInvalida,
+                    &[],
+                )
+                .con_token(token)
+                .con_esperados(&COMPARACIONES))
+            }
         };
 
-        let valor2 = iter.next().ok_or(Box::new(ErrorPrograma::new(
-            ErrorTipo::Syntax,
-            "No se encontró un valor en la cláusula WHERE, recuerde que la clause debe ser \"valor1 comparacion valor2\".",
-        )))?;
+        let valor2 = iter.next().ok_or_else(|| {
+            ErrorPrograma::desde_mensaje(ErrorTipo::Syntax, Mensaje::WhereSinValor, &[])
+                .con_esperados(&["<valor>"])
+                .al_final_de_la_query()
+        })?;
 
         Ok((valor1.to_string(), comparacion, valor2.to_string()))
     }
 
     fn clasificar_clauses(
         where_clause: &str,
-    ) -> Result<(Vec<WhereClause>, Vec<LogicalOperators>), Box<dyn Error>> {
+    ) -> Result<(Vec<WhereClause>, Vec<LogicalOperators>), ErrorPrograma> {
         let mut where_clauses = Vec::new();
         let mut logical_operators = Vec::new();
         let where_clause_separada = separar_parentesis(where_clause);
@@ -240,10 +752,15 @@ impl WhereClause {
                 let operador_logico = match operador {
                     "AND" => LogicalOperators::And,
                     "OR" => LogicalOperators::Or,
-                    _ => return Err(Box::new(ErrorPrograma::new(
-                        ErrorTipo::Syntax,
-                        "El operador lógico ingresado no es válido o la sintaxis fue inválida, los operadores que se pueden manejar son AND, OR y NOT.",
-                    ))),
+                    token => {
+                        return Err(ErrorPrograma::desde_mensaje(
+                            ErrorTipo::Syntax,
+                            Mensaje::OperadorLogicoInvalido,
+                            &[],
+                        )
+                        .con_token(token)
+                        .con_esperados(&["AND", "OR"]))
+                    }
                 };
                 logical_operators.push(operador_logico);
             }
@@ -257,11 +774,10 @@ impl WhereClause {
      */
     pub fn clasificar_where_clause(
         resto: &str,
-    ) -> Result<(Vec<WhereClause>, Vec<LogicalOperators>), Box<dyn Error>> {
-        let posicion_where = resto.find("WHERE").ok_or(Box::new(ErrorPrograma::new(
-            ErrorTipo::Syntax,
-            "No se encontro WHERE en la query, revise si esta en minúscula.",
-        )))?;
+    ) -> Result<(Vec<WhereClause>, Vec<LogicalOperators>), ErrorPrograma> {
+        let posicion_where = resto.find("WHERE").ok_or_else(|| {
+            ErrorPrograma::desde_mensaje(ErrorTipo::Syntax, Mensaje::SinWhere, &[])
+        })?;
         let posicion_order_by = resto.find("ORDER BY");
 
         let posicion_where_clause = match posicion_order_by {
@@ -290,21 +806,19 @@ impl OrderByClause {
      * Se recibe un string que contiene únicamente de la order by clause y se separa en columnas y
      * orden que puede ser Ascendente o Descendente.
      */
-    pub fn clasificar_order_by_clause(resto: &str) -> Result<Vec<OrderByClause>, Box<dyn Error>> {
-        let posicion_order_by = resto.find("ORDER BY").ok_or(Box::new(ErrorPrograma::new(
-            ErrorTipo::Syntax,
-            "No se encontró ORDER BY dentro de la query.",
-        )))?;
+    pub fn clasificar_order_by_clause(resto: &str) -> Result<Vec<OrderByClause>, ErrorPrograma> {
+        let posicion_order_by = resto.find("ORDER BY").ok_or_else(|| {
+            ErrorPrograma::desde_mensaje(ErrorTipo::Syntax, Mensaje::SinOrderBy, &[])
+        })?;
         let order_by_clause = &resto[posicion_order_by + "ORDER BY".len()..].trim();
         let order_by_clause = order_by_clause.replace(";", "");
         let mut order_by_clauses = Vec::new();
         for i in order_by_clause.split(',') {
             let fraccionado: Vec<&str> = i.split_whitespace().collect();
 
-            let columna = fraccionado.first().ok_or(Box::new(ErrorPrograma::new(
-                ErrorTipo::Column,
-                "La columna ingresada en el ORDER BY no fue válida o no se encontró.",
-            )))?;
+            let columna = fraccionado.first().ok_or_else(|| {
+                ErrorPrograma::desde_mensaje(ErrorTipo::Syntax, Mensaje::OrderBySinColumna, &[])
+            })?;
 
             let orden = match fraccionado.get(1) {
                 Some(&"ASC") => Order::Asc,
@@ -336,30 +850,124 @@ impl Select {
             where_clauses,
             order_by,
             operacion_logica,
+            joins: Vec::new(),
+            group_by: None,
+            limit: None,
+        }
+    }
+
+    /*
+     * Separa la cláusula LIMIT del final del SELECT. Devuelve el SELECT sin la cláusula y la cantidad de filas.
+     */
+    fn separar_limit(resto: &str) -> Result<(&str, Option<usize>), ErrorPrograma> {
+        let Some(posicion) = buscar_palabra_clave(resto, "LIMIT") else {
+            return Ok((resto, None));
+        };
+        let cantidad = resto[posicion + "LIMIT".len()..].trim();
+        let limit = cantidad.parse::<usize>().map_err(|_| {
+            ErrorPrograma::desde_mensaje(ErrorTipo::Syntax, Mensaje::LimitInvalido, &[cantidad])
+                .con_token(cantidad)
+                .con_esperados(&["<cantidad>"])
+        })?;
+        Ok((resto[..posicion].trim_end(), Some(limit)))
+    }
+
+    /*
+     * Separa la cláusula GROUP BY, que termina donde empieza el ORDER BY. Devuelve el SELECT sin la cláusula y
+     * las columnas por las que se agrupa.
+     */
+    fn separar_group_by(resto: &str) -> Result<(String, Option<Vec<String>>), ErrorPrograma> {
+        let Some(inicio) = buscar_palabra_clave(resto, "GROUP BY") else {
+            return Ok((resto.to_string(), None));
+        };
+        let fin = resto[inicio..]
+            .find("ORDER BY")
+            .map_or(resto.len(), |posicion| inicio + posicion);
+        let columnas: Vec<String> = resto[inicio + "GROUP BY".len()..fin]
+            .split(',')
+            .map(|columna| columna.trim().to_string())
+            .collect();
+        if columnas
+            .iter()
+            .any(|columna| columna.is_empty() || columna.contains(char::is_whitespace))
+        {
+            return Err(ErrorPrograma::desde_mensaje(
+                ErrorTipo::Syntax,
+                Mensaje::GroupByInvalido,
+                &[],
+            ));
         }
+        let sin_group_by = format!("{} {}", resto[..inicio].trim_end(), &resto[fin..]);
+        Ok((sin_group_by.trim_end().to_string(), Some(columnas)))
     }
 
     /*
-     * Se recibe un string de la query tipo select y se parsea para obtener las columnas, la tabla, las where clauses y los order by clauses.
-     * Se llama a las respectivas funciones que parsean las where y order by clauses a partir de un string.
+     * Se recibe lo que sigue a FROM hasta el WHERE o el ORDER BY: la tabla y las que se le unen con
+     * [INNER] JOIN <tabla> ON <columna> = <columna>.
+     */
+    fn clasificar_from(from: &str) -> Result<(String, Vec<JoinClause>), ErrorPrograma> {
+        let mut tokens = from.split_whitespace();
+        let tabla = tokens.next().ok_or_else(|| {
+            ErrorPrograma::desde_mensaje(ErrorTipo::Syntax, Mensaje::SelectSinTabla, &[])
+                .con_esperados(&["<tabla>"])
+                .al_final_de_la_query()
+        })?;
+        let error_join = |token: Option<&str>, esperados: &[&str]| {
+            let error = ErrorPrograma::desde_mensaje(ErrorTipo::Syntax, Mensaje::JoinInvalido, &[])
+                .con_esperados(esperados);
+            match token {
+                Some(token) => error.con_token(token),
+                None => error.al_final_de_la_query(),
+            }
+        };
+
+        let mut joins = Vec::new();
+        while let Some(token) = tokens.next() {
+            let token = match token {
+                "INNER" => tokens.next(),
+                token => Some(token),
+            };
+            if token != Some("JOIN") {
+                return Err(error_join(token, &["JOIN"]));
+            }
+            let tabla = tokens
+                .next()
+                .ok_or_else(|| error_join(None, &["<tabla>"]))?;
+            match (tokens.next(), tokens.next(), tokens.next(), tokens.next()) {
+                (Some("ON"), Some(izquierda), Some("="), Some(derecha)) => joins.push(JoinClause {
+                    tabla: tabla.to_string(),
+                    columna_izquierda: izquierda.to_string(),
+                    columna_derecha: derecha.to_string(),
+                }),
+                (Some("ON"), _, Some(token), _) => return Err(error_join(Some(token), &["="])),
+                (Some("ON"), ..) => return Err(error_join(None, &["<columna>"])),
+                (token, ..) => return Err(error_join(token, &["ON"])),
+            }
+        }
+        Ok((tabla.to_string(), joins))
+    }
+
+    /*
+     * Se recibe un string de la query tipo select y se parsea para obtener las columnas, la tabla y las que se le
+     * unen con JOIN, las where clauses, el GROUP BY, los order by clauses y el LIMIT. Se llama a las respectivas
+     * funciones que parsean cada cláusula a partir de un string.
      */
     pub fn crear_select(
         query: &str,
         posicion_from: usize,
         columnas: Vec<String>,
-    ) -> Result<Select, Box<dyn Error>> {
+    ) -> Result<Select, ErrorPrograma> {
         let query_limpia = query.trim_end_matches(';');
-        let resto = query_limpia[posicion_from..].trim();
+        let (resto, limit) = Self::separar_limit(query_limpia[posicion_from..].trim())?;
+        let (resto, group_by) = Self::separar_group_by(resto)?;
+        let resto = resto.as_str();
 
-        let tabla = match resto["FROM".len()..].split_whitespace().next() {
-            Some(tabla) => tabla,
-            None => {
-                return Err(Box::new(ErrorPrograma::new(
-                    ErrorTipo::Syntax,
-                    "No se encontro el nombre de la tabla, la sintaxis válidad es: SELECT <columnas> FROM <tabla>.",
-                )))
-            }
-        };
+        let fin_from = ["WHERE", "ORDER BY"]
+            .iter()
+            .filter_map(|clausula| resto.find(clausula))
+            .min()
+            .unwrap_or(resto.len());
+        let (tabla, joins) = Self::clasificar_from(&resto["FROM".len()..fin_from])?;
         let (where_clauses, logical_operators) = if resto.contains("WHERE") {
             WhereClause::clasificar_where_clause(resto)?
         } else {
@@ -371,25 +979,28 @@ impl Select {
             None
         };
 
-        let select = Select::new(
+        let mut select = Select::new(
             columnas,
-            tabla.to_string(),
+            tabla,
             Some(where_clauses),
             order_by_clauses,
             Some(logical_operators),
         );
-
+        select.joins = joins;
+        select.group_by = group_by;
+        select.limit = limit;
         Ok(select)
     }
 
     /*
      * Se parsea la query tipo select a partir del string recibido y se crea una instancia de Select.
      */
-    pub fn select_parsear_query(query: &str) -> Result<Self, Box<dyn Error>> {
-        let posicion_from = query.find("FROM").ok_or(Box::new(ErrorPrograma::new(
-            ErrorTipo::Syntax,
-            "No se encontró el FROM dentro de la query, la sintaxis válida es: SELECT <columnas> FROM <tabla>.",
-        )))?;
+    pub fn select_parsear_query(query: &str) -> Result<Self, ErrorPrograma> {
+        let posicion_from = query.find("FROM").ok_or_else(|| {
+            ErrorPrograma::desde_mensaje(ErrorTipo::Syntax, Mensaje::SelectSinFrom, &[])
+                .con_esperados(&["FROM"])
+                .al_final_de_la_query()
+        })?;
         let columnas: Vec<String> = query["SELECT ".len()..posicion_from]
             .trim()
             .split(',')
@@ -414,17 +1025,19 @@ impl Update {
             set: set.to_string(),
             where_clauses,
             operacion_logica,
+            returning: None,
         }
     }
 
     /*
      * Se parsea la query tipo update a partir del string recibido y se crea una instancia de update.
      */
-    pub fn update_parsear_query(query: &str) -> Result<Self, Box<dyn Error>> {
-        let posicion_set = query.find("SET").ok_or(Box::new(ErrorPrograma::new(
-            ErrorTipo::Syntax,
-            "No se encontró el SET dentro de la query, la sintaxis válida es: UPDATE FROM <tabla> WHERE <condiciones>.",
-        )))?;
+    pub fn update_parsear_query(query: &str) -> Result<Self, ErrorPrograma> {
+        let (query, returning) = separar_returning(query)?;
+        let posicion_set = query.find("SET").ok_or_else(|| {
+            ErrorPrograma::desde_mensaje(ErrorTipo::Syntax, Mensaje::UpdateSinSet, &[])
+                .con_esperados(&["SET"])
+        })?;
         let linea_tabla = &query[..posicion_set].trim();
         let linea_set_where = &query[posicion_set + "SET".len()..].trim();
         let nombre_tabla = &linea_tabla["UPDATE ".len()..].trim();
@@ -445,7 +1058,8 @@ impl Update {
                 (resto.to_string(), None, None)
             };
 
-        let update = Update::new(nombre_tabla, &linea_set, where_clauses, logical_operators);
+        let mut update = Update::new(nombre_tabla, &linea_set, where_clauses, logical_operators);
+        update.returning = returning;
         Ok(update)
     }
 }
@@ -463,21 +1077,25 @@ impl Delete {
             tabla: tabla.to_string(),
             where_clauses,
             operacion_logica,
+            returning: None,
         }
     }
 
-    fn delete_parsear_query(query: &str) -> Result<Self, Box<dyn Error>> {
-        let posicion_where = query.find("WHERE").ok_or(Box::new(ErrorPrograma::new(
-            ErrorTipo::Syntax,
-            "No se encontró WHERE dentro de la query, la sintaxis válida es: DELETE FROM <tabla> WHERE <condición>.",
-        )))?;
+    pub fn delete_parsear_query(query: &str) -> Result<Self, ErrorPrograma> {
+        let (query, returning) = separar_returning(query)?;
+        let posicion_where = query.find("WHERE").ok_or_else(|| {
+            ErrorPrograma::desde_mensaje(ErrorTipo::Syntax, Mensaje::DeleteSinWhere, &[])
+                .con_esperados(&["WHERE"])
+                .al_final_de_la_query()
+        })?;
 
         let linea_tabla = &query[..posicion_where].trim();
         if !query.starts_with("DELETE FROM") {
-            return Err(Box::new(ErrorPrograma::new(
+            return Err(ErrorPrograma::desde_mensaje(
                 ErrorTipo::Syntax,
-                "No se encontró DELETE FROM dentro de la query, la sintaxis válida es: SELECT <columnas> FROM <tabla>.",
-            )));
+                Mensaje::DeleteSinFrom,
+                &[],
+            ));
         }
         let tabla = &linea_tabla["DELETE FROM ".len()..].trim();
 
@@ -489,11 +1107,222 @@ impl Delete {
             (Vec::new(), Vec::new())
         };
 
-        let delete = Delete::new(tabla, Some(where_clauses), Some(logical_operators));
+        let mut delete = Delete::new(tabla, Some(where_clauses), Some(logical_operators));
+        delete.returning = returning;
         Ok(delete)
     }
 }
 
+impl AlterTable {
+    /*
+     * Se crea una instancia del tipo alter table con los valores ingresados
+     */
+    pub fn new(tabla: &str, accion: AccionAlterTable) -> Self {
+        AlterTable {
+            tabla: tabla.to_string(),
+            accion,
+        }
+    }
+
+    fn esperar_token(
+        iter: &mut Peekable<SplitWhitespace>,
+        esperado: &str,
+    ) -> Result<(), ErrorPrograma> {
+        let encontrado = iter.next();
+        if encontrado == Some(esperado) {
+            return Ok(());
+        }
+        let error =
+            ErrorPrograma::desde_mensaje(ErrorTipo::Syntax, Mensaje::AlterSeEsperaba, &[esperado])
+                .con_esperados(&[esperado]);
+        match encontrado {
+            Some(token) => Err(error.con_token(token)),
+            None => Err(error.al_final_de_la_query()),
+        }
+    }
+
+    fn siguiente_identificador(
+        iter: &mut Peekable<SplitWhitespace>,
+    ) -> Result<String, ErrorPrograma> {
+        match iter.next() {
+            Some(token) if token != "(" && token != ")" => Ok(token.to_string()),
+            _ => Err(ErrorPrograma::desde_mensaje(
+                ErrorTipo::Syntax,
+                Mensaje::AlterSinNombre,
+                &[],
+            )),
+        }
+    }
+
+    fn clasificar_accion_referencial(
+        iter: &mut Peekable<SplitWhitespace>,
+    ) -> Result<AccionReferencial, ErrorPrograma> {
+        let accion = match iter.next() {
+            Some(palabra @ ("SET" | "NO")) => {
+                format!("{} {}", palabra, iter.next().unwrap_or_default())
+            }
+            Some(palabra) => palabra.to_string(),
+            None => String::new(),
+        };
+        Ok(AccionReferencial::desde_str(&accion)?)
+    }
+
+    fn clasificar_foreign_key(
+        iter: &mut Peekable<SplitWhitespace>,
+    ) -> Result<ForeignKey, ErrorPrograma> {
+        Self::esperar_token(iter, "KEY")?;
+        Self::esperar_token(iter, "(")?;
+        let columna = Self::siguiente_identificador(iter)?;
+        Self::esperar_token(iter, ")")?;
+        Self::esperar_token(iter, "REFERENCES")?;
+        let tabla_referenciada = Self::siguiente_identificador(iter)?;
+        Self::esperar_token(iter, "(")?;
+        let columna_referenciada = Self::siguiente_identificador(iter)?;
+        Self::esperar_token(iter, ")")?;
+
+        let mut on_delete = AccionReferencial::Restrict;
+        let mut on_update = AccionReferencial::Restrict;
+        while iter.peek().is_some() {
+            Self::esperar_token(iter, "ON")?;
+            match iter.next() {
+                Some("DELETE") => on_delete = Self::clasificar_accion_referencial(iter)?,
+                Some("UPDATE") => on_update = Self::clasificar_accion_referencial(iter)?,
+                encontrado => {
+                    let error = ErrorPrograma::desde_mensaje(
+                        ErrorTipo::Syntax,
+                        Mensaje::AlterOnInvalido,
+                        &[],
+                    )
+                    .con_esperados(&["DELETE", "UPDATE"]);
+                    return Err(match encontrado {
+                        Some(token) => error.con_token(token),
+                        None => error.al_final_de_la_query(),
+                    });
+                }
+            }
+        }
+
+        Ok(ForeignKey {
+            columna,
+            tabla_referenciada,
+            columna_referenciada,
+            on_delete,
+            on_update,
+        })
+    }
+
+    fn clasificar_alter_column(
+        iter: &mut Peekable<SplitWhitespace>,
+    ) -> Result<AccionAlterTable, ErrorPrograma> {
+        let columna = Self::siguiente_identificador(iter)?;
+        match (iter.next(), iter.next()) {
+            (Some("SET"), Some("AUTO_INCREMENT")) | (Some("SET"), Some("SERIAL")) => {
+                Ok(AccionAlterTable::AgregarAutoIncrement(columna))
+            }
+            (Some("SET"), Some("DEFAULT")) => {
+                let valor: Vec<&str> = iter.by_ref().collect();
+                if valor.is_empty() {
+                    return Err(ErrorPrograma::desde_mensaje(
+                        ErrorTipo::Syntax,
+                        Mensaje::AlterSinDefault,
+                        &[],
+                    ));
+                }
+                Ok(AccionAlterTable::AgregarDefault(columna, valor.join(" ")))
+            }
+            (Some("DROP"), Some("DEFAULT")) => Ok(AccionAlterTable::EliminarDefault(columna)),
+            (encontrado, _) => {
+                let error = ErrorPrograma::desde_mensaje(
+                    ErrorTipo::Syntax,
+                    Mensaje::AlterColumnInvalido,
+                    &[],
+                )
+                .con_esperados(&[
+                    "SET AUTO_INCREMENT",
+                    "SET SERIAL",
+                    "SET DEFAULT",
+                    "DROP DEFAULT",
+                ]);
+                Err(match encontrado {
+                    Some(token) => error.con_token(token),
+                    None => error.al_final_de_la_query(),
+                })
+            }
+        }
+    }
+
+    /*
+     * Se parsea la query tipo alter table a partir del string recibido y se crea una instancia de AlterTable.
+     */
+    pub fn alter_table_parsear_query(query: &str) -> Result<Self, ErrorPrograma> {
+        let query_sin_punto_coma = query.trim().trim_end_matches(';');
+        let query_limpia = separar_parentesis(query_sin_punto_coma);
+        let mut iter = query_limpia.split_whitespace().peekable();
+        Self::esperar_token(&mut iter, "ALTER")?;
+        Self::esperar_token(&mut iter, "TABLE")?;
+        let tabla = Self::siguiente_identificador(&mut iter)?;
+
+        let accion = match (iter.next(), iter.next()) {
+            (Some("ADD"), Some("FOREIGN")) => {
+                AccionAlterTable::AgregarForeignKey(Self::clasificar_foreign_key(&mut iter)?)
+            }
+            (Some("ALTER"), Some("COLUMN")) => match Self::clasificar_alter_column(&mut iter)? {
+                // El valor por defecto se toma de la query original para respetar sus espacios y paréntesis.
+                AccionAlterTable::AgregarDefault(columna, _) => {
+                    let posicion = query_sin_punto_coma.rfind("DEFAULT").unwrap_or(0);
+                    let valor = query_sin_punto_coma[posicion + "DEFAULT".len()..].trim();
+                    AccionAlterTable::AgregarDefault(columna, valor.to_string())
+                }
+                accion => accion,
+            },
+            (encontrado, _) => {
+                let error = ErrorPrograma::desde_mensaje(
+                    ErrorTipo::Syntax,
+                    Mensaje::AlterAccionInvalida,
+                    &[],
+                )
+                .con_esperados(&["ADD FOREIGN KEY", "ALTER COLUMN"]);
+                return Err(match encontrado {
+                    Some(token) => error.con_token(token),
+                    None => error.al_final_de_la_query(),
+                });
+            }
+        };
+        if iter.next().is_some() {
+            return Err(ErrorPrograma::desde_mensaje(
+                ErrorTipo::Syntax,
+                Mensaje::AlterValoresDeMas,
+                &[],
+            ));
+        }
+
+        Ok(AlterTable::new(&tabla, accion))
+    }
+}
+
+/*
+ * Separa una query EXPLAIN [ANALYZE] <select> en el SELECT a explicar y si se pidió ANALYZE.
+ */
+fn separar_explain(query: &str) -> Result<(&str, bool), ErrorPrograma> {
+    let resto = query["EXPLAIN".len()..].trim_start();
+    let (select, analizar) = match resto.strip_prefix("ANALYZE") {
+        Some(select) => (select.trim_start(), true),
+        None => (resto, false),
+    };
+    if !select.starts_with("SELECT") {
+        return Err(
+            ErrorPrograma::desde_mensaje(ErrorTipo::Syntax, Mensaje::ExplainInvalido, &[])
+                .con_token(select.split_whitespace().next().unwrap_or(""))
+                .con_esperados(if analizar {
+                    &["SELECT"]
+                } else {
+                    &["SELECT", "ANALYZE"]
+                }),
+        );
+    }
+    Ok((select, analizar))
+}
+
 fn identificar_tipo(query: &str) -> QueryType {
     if query.starts_with("SELECT") {
         QueryType::Select
@@ -503,38 +1332,126 @@ fn identificar_tipo(query: &str) -> QueryType {
         QueryType::Update
     } else if query.starts_with("DELETE") {
         QueryType::Delete
+    } else if query.starts_with("ALTER TABLE") {
+        QueryType::AlterTable
+    } else if query.starts_with("COPY") {
+        QueryType::Copy
+    } else if query.starts_with("CREATE INDEX") {
+        QueryType::CreateIndex
+    } else if query.starts_with("DROP INDEX") {
+        QueryType::DropIndex
+    } else if query.starts_with("EXPLAIN") {
+        QueryType::Explain
+    } else if query.trim_end_matches(';').trim() == "RECOVER" {
+        QueryType::Recover
     } else {
         QueryType::Desconocido
     }
 }
 
+/*
+ * Ejecuta la query sobre el directorio de tablas e imprime su resultado por salida estándar.
+ */
+pub fn analisar_query(ruta: &String, query: &str) -> Result<(), ErrorPrograma> {
+    analisar_query_con_destino(ruta, query, &mut SalidaEstandar)
+}
+
 /*
  * Dependiendo de la query, se identifica que tipo es, y se llama a la función correspondiente para parsearla, y luego
- * se aplica la query a la tabla correspondiente.
+ * se aplica la query a la tabla correspondiente. Las filas que devuelve la query se escriben en el destino.
+ * Los errores devueltos incluyen la query, para poder mostrar en qué parte de ella está el problema.
  */
-pub fn analisar_query(ruta: &String, query: &str) -> Result<(), Box<dyn Error>> {
+pub fn analisar_query_con_destino(
+    ruta: &String,
+    query: &str,
+    destino: &mut dyn DestinoFilas,
+) -> Result<(), ErrorPrograma> {
+    ejecutar_query(ruta, query, destino).map_err(|error| error.con_query(query))
+}
+
+fn ejecutar_query(
+    ruta: &String,
+    query: &str,
+    destino: &mut dyn DestinoFilas,
+) -> Result<(), ErrorPrograma> {
+    // Antes de cada consulta se terminan o deshacen las escrituras que quedaron a medias en el directorio.
+    recuperar_directorio(ruta)?;
     match identificar_tipo(query) {
         QueryType::Insert => {
             let insert = Insert::insert_parsear_query(query)?;
             let ruta_completa = format!("{}/{}.csv", ruta, insert.tabla);
-            aplicar_insert(&ruta_completa, &insert.nombre_col, insert.valores)
+            let (columnas_csv, filas) = aplicar_insert(&ruta_completa, &insert)?;
+            escribir_returning(&insert.returning, &columnas_csv, filas, destino)
         }
         QueryType::Select => {
+            let (query, exportacion) = separar_into_outfile(query)?;
             let select = Select::select_parsear_query(query)?;
             let ruta_completa = format!("{}/{}.csv", ruta, select.tabla);
-            aplicar_select(&ruta_completa, &select)
+            match exportacion {
+                Some(exportacion) => exportar_select(&ruta_completa, &select, &exportacion),
+                None => ejecutar_select(&ruta_completa, &select, destino),
+            }
         }
         QueryType::Update => {
             let update = Update::update_parsear_query(query)?;
             let ruta_completa = format!("{}/{}.csv", ruta, update.tabla);
-            println!("{:?}", update);
-            aplicar_update(&ruta_completa, &update)
+            let (columnas_csv, filas) = aplicar_update(&ruta_completa, &update)?;
+            escribir_returning(&update.returning, &columnas_csv, filas, destino)
         }
         QueryType::Delete => {
             let delete = Delete::delete_parsear_query(query)?;
             let ruta_completa = format!("{}/{}.csv", ruta, delete.tabla);
-            aplicar_delete(&ruta_completa, &delete)
+            let (columnas_csv, filas) = aplicar_delete(&ruta_completa, &delete)?;
+            escribir_returning(&delete.returning, &columnas_csv, filas, destino)
+        }
+        QueryType::AlterTable => {
+            let alter = AlterTable::alter_table_parsear_query(query)?;
+            let ruta_completa = format!("{}/{}.csv", ruta, alter.tabla);
+            aplicar_alter_table(&ruta_completa, &alter)
+        }
+        QueryType::Copy if query["COPY".len()..].trim_start().starts_with('(') => {
+            let copy = CopyTo::copy_to_parsear_query(query)?;
+            let ruta_completa = format!("{}/{}.csv", ruta, copy.select.tabla);
+            exportar_select(&ruta_completa, &copy.select, &copy.exportacion)
+        }
+        QueryType::Copy => {
+            let copy = CopyFrom::copy_from_parsear_query(query)?;
+            let ruta_completa = format!("{}/{}.csv", ruta, copy.tabla);
+            aplicar_copy_from(&ruta_completa, &copy)
+        }
+        QueryType::CreateIndex => {
+            let create = CreateIndex::create_index_parsear_query(query)?;
+            let ruta_completa = format!("{}/{}.csv", ruta, create.tabla);
+            aplicar_create_index(&ruta_completa, &create)
+        }
+        QueryType::DropIndex => {
+            let drop = DropIndex::drop_index_parsear_query(query)?;
+            aplicar_drop_index(ruta, &drop)
+        }
+        QueryType::Explain => {
+            let (select, analizar) = separar_explain(query)?;
+            let select = Select::select_parsear_query(select)?;
+            let ruta_completa = format!("{}/{}.csv", ruta, select.tabla);
+            explicar_select(&ruta_completa, &select, analizar, destino)
+        }
+        QueryType::Recover => Ok(aplicar_recover(ruta, destino)?),
+        QueryType::Desconocido => {
+            Err(
+                ErrorPrograma::desde_mensaje(ErrorTipo::Syntax, Mensaje::QueryDesconocida, &[])
+                    .con_token(query.split_whitespace().next().unwrap_or(""))
+                    .con_esperados(&[
+                        "SELECT",
+                        "INSERT INTO",
+                        "UPDATE",
+                        "DELETE FROM",
+                        "ALTER TABLE",
+                        "COPY",
+                        "CREATE INDEX",
+                        "DROP INDEX",
+                        "EXPLAIN",
+                        "RECOVER",
+                    ]),
+            )
         }
-        QueryType::Desconocido => Err(Box::new(ErrorPrograma::new(ErrorTipo::Syntax, "La query ingresada es desconocida, no corresponde con SELECT, INSERT, UPDATE o DELETE."))),
     }
 }
@@ -1,25 +1,69 @@
+use crate::agregacion::{agregados_de_select, Agregado};
+use crate::archivo_temporal::ArchivoTemporal;
+use crate::bitacora::Bitacora;
+use crate::bloqueo::{bloquear_tabla, BloqueoTabla, ModoBloqueo};
 use crate::errors::{ErrorPrograma, ErrorTipo};
+use crate::esquema::{
+    reservar_auto_increment, separar_ruta_tabla, tablas_del_directorio, AutoIncrement, Esquema,
+};
+use crate::indices::confirmar_con_indices;
+use crate::mensajes::Mensaje;
+use crate::plan::{ejecutar_plan, escribir_explain, planificar_select, TablaConsulta};
+use crate::predicado::Predicado;
 use crate::query_identifier::{
-    Comparacion, Delete, LogicalOperators, Order, Select, Update, WhereClause,
+    AccionAlterTable, AlterTable, Comparacion, CopyFrom, Delete, Exportacion, Insert, JoinClause,
+    LogicalOperators, Select, Update, WhereClause,
+};
+use crate::restricciones::{
+    filas_hijas_invalidas, propagar_cambios_padre, valores_columna, verificar_filas_hijas,
 };
+use crate::salida::{DestinoFilas, SalidaEstandar, SalidaFormateada};
+use crate::sesion::tabla_fuera_de_transaccion;
+use crate::sugerencias::sugerencia_mas_cercana;
+use std::collections::HashSet;
 use std::error::Error;
-use std::fs::rename;
-use std::fs::{File, OpenOptions};
-use std::io::BufWriter;
-use std::io::Write;
+use std::fs::{read_to_string, remove_file, rename, File, OpenOptions};
 use std::io::{BufRead, BufReader, Lines};
+use std::io::{ErrorKind, Write};
 
-type ResultLeerArchivo = Result<(Lines<BufReader<File>>, Vec<String>), Box<dyn Error>>;
+type ResultLeerArchivo = Result<(Lines<BufReader<File>>, Vec<String>), ErrorPrograma>;
+// Encabezado de la tabla y filas afectadas por una consulta de escritura.
+type ResultFilasAfectadas = Result<(Vec<String>, Vec<Vec<String>>), ErrorPrograma>;
 
-fn abrir_archivo(ruta: &String) -> ResultLeerArchivo {
-    let file = File::open(ruta)?;
+/*
+ * Abre el archivo csv de la tabla y lee su encabezado. Si el archivo no existe la tabla es desconocida.
+ */
+pub(crate) fn abrir_archivo(ruta: &String) -> ResultLeerArchivo {
+    let file = File::open(ruta).map_err(|error| match error.kind() {
+        ErrorKind::NotFound => {
+            let tabla = separar_ruta_tabla(ruta).1;
+            let tablas = tablas_del_directorio(&separar_ruta_tabla(ruta).0).unwrap_or_default();
+            let sugerencia = sugerencia_mas_cercana(&tabla, tablas.iter().map(|t| t.as_str()));
+            ErrorPrograma::desde_mensaje(
+                ErrorTipo::UnknownTable {
+                    tabla: tabla.clone(),
+                },
+                Mensaje::TablaInexistente,
+                &[&tabla],
+            )
+            .con_token(&tabla)
+            .con_sugerencia(sugerencia)
+        }
+        _ => ErrorPrograma::from(error),
+    })?;
     let reader = BufReader::new(file);
     let mut lineas = reader.lines();
 
-    let columnas_csv = lineas.next().ok_or(Box::new(ErrorPrograma::new(
-        ErrorTipo::Table,
-        "El archivo csv se encuentra vacío",
-    )))??;
+    let columnas_csv = lineas.next().ok_or_else(|| {
+        ErrorPrograma::desde_mensaje(
+            ErrorTipo::Csv {
+                archivo: ruta.to_string(),
+                linea: Some(1),
+            },
+            Mensaje::CsvVacio,
+            &[],
+        )
+    })??;
 
     let columnas_csv: Vec<String> = columnas_csv
         .split(',')
@@ -32,20 +76,39 @@ fn es_int(valor: &str) -> bool {
     valor.parse::<i32>().is_ok()
 }
 
-fn verificar_existen_columnas_query(
+/*
+ * Error por una columna que no está en el encabezado de la tabla, sugiriendo la columna más parecida.
+ */
+fn error_columna_desconocida(
+    columna: &str,
+    mensaje: Mensaje,
+    datos: &[&str],
+    columnas_csv: &[String],
+) -> ErrorPrograma {
+    let sugerencia = sugerencia_mas_cercana(columna, columnas_csv.iter().map(|c| c.as_str()));
+    ErrorPrograma::desde_mensaje(
+        ErrorTipo::UnknownColumn {
+            columna: columna.to_string(),
+        },
+        mensaje,
+        datos,
+    )
+    .con_token(columna)
+    .con_sugerencia(sugerencia)
+}
+
+pub(crate) fn verificar_existen_columnas_query(
     columnas_query: &[String],
     columnas_csv: &[String],
-) -> Result<(), Box<dyn Error>> {
+) -> Result<(), ErrorPrograma> {
     for columna in columnas_query.iter() {
         if !columnas_csv.contains(columna) {
-            let descripcion = format!(
-                "La columna {} no fue encontrada en el archivo csv.",
-                columna
-            );
-            return Err(Box::new(ErrorPrograma::new(
-                ErrorTipo::Column,
-                descripcion.as_str(),
-            )));
+            return Err(error_columna_desconocida(
+                columna,
+                Mensaje::ColumnaInexistente,
+                &[columna],
+                columnas_csv,
+            ));
         }
     }
 
@@ -55,7 +118,7 @@ fn verificar_existen_columnas_query(
 fn verificar_existen_columnas_where_clause(
     where_clauses: &Option<Vec<WhereClause>>,
     columnas_csv: &[String],
-) -> Result<(), Box<dyn Error>> {
+) -> Result<(), ErrorPrograma> {
     if let Some(ref clauses) = where_clauses {
         for clause in clauses.iter() {
             if columnas_csv.contains(&clause.valor1)
@@ -71,212 +134,387 @@ fn verificar_existen_columnas_where_clause(
             if columnas_csv.contains(&clause.valor2) && columnas_csv.contains(&clause.valor1) {
                 continue;
             }
-            let descripcion = format!("Ninguno de estos dos valores '{}' y '{}' en la WHERE clause fue encontrado como una columna en el csv.", clause.valor1, clause.valor2);
-            return Err(Box::new(ErrorPrograma::new(
-                ErrorTipo::Column,
-                &descripcion,
-            )));
+            // Una condición entre dos valores, como `1 = 1`, no depende de la fila.
+            let es_literal = |valor: &str| valor.contains('\'') || es_int(valor);
+            if es_literal(&clause.valor1) && es_literal(&clause.valor2) {
+                continue;
+            }
+            return Err(error_columna_desconocida(
+                &clause.valor1,
+                Mensaje::ColumnasWhereInexistentes,
+                &[&clause.valor1, &clause.valor2],
+                columnas_csv,
+            ));
         }
     };
     Ok(())
 }
 
-fn filtrar_lineas_que_cumplen_con_where_clause(
-    lineas: Lines<BufReader<File>>,
+fn verificar_existen_columnas_order_by(
     select: &Select,
     columnas_csv: &[String],
-) -> Result<Vec<Vec<String>>, Box<dyn Error>> {
-    let mut lineas_filtradas: Vec<Vec<String>> = vec![];
-
-    for linea in lineas {
-        let linea = linea?;
-        let valores: Vec<String> = linea.split(',').map(|s| s.trim().to_string()).collect();
-
-        if let Some(ref condiciones) = select.where_clauses {
-            if !condiciones.is_empty() {
-                if let Some(ref operadores_logicos) = select.operacion_logica {
-                    if !aplicar_filtro(&valores, columnas_csv, condiciones, operadores_logicos)? {
-                        continue;
-                    }
-                }
+) -> Result<(), ErrorPrograma> {
+    if let Some(ref ordernar) = select.order_by {
+        for ordenamiento_actual in ordernar.iter() {
+            if !columnas_csv.contains(&ordenamiento_actual.columna) {
+                return Err(error_columna_desconocida(
+                    &ordenamiento_actual.columna,
+                    Mensaje::ColumnaOrderByInexistente,
+                    &[&ordenamiento_actual.columna],
+                    columnas_csv,
+                ));
             }
         }
+    }
+    Ok(())
+}
 
-        lineas_filtradas.push(valores.iter().map(|s| s.to_string()).collect());
+/*
+ * Devuelve las rutas de las tablas del SELECT: la del FROM y las de cada JOIN.
+ */
+fn rutas_select(ruta: &str, select: &Select) -> Vec<String> {
+    let directorio = separar_ruta_tabla(ruta).0;
+    let mut rutas = vec![ruta.to_string()];
+    rutas.extend(
+        select
+            .joins
+            .iter()
+            .map(|join| format!("{}/{}.csv", directorio, join.tabla)),
+    );
+    rutas
+}
+
+/*
+ * Bloquea para lectura todas las tablas del SELECT, siempre en el mismo orden para que dos consultas que leen
+ * las mismas tablas no se esperen entre sí.
+ */
+fn bloquear_tablas_select(ruta: &str, select: &Select) -> Result<Vec<BloqueoTabla>, ErrorPrograma> {
+    let mut rutas = rutas_select(ruta, select);
+    rutas.sort();
+    rutas.dedup();
+    let mut bloqueos = Vec::new();
+    for ruta in rutas.iter() {
+        bloqueos.push(bloquear_tabla(ruta, ModoBloqueo::Compartido)?);
     }
-    Ok(lineas_filtradas)
+    Ok(bloqueos)
 }
 
-fn ordenar_lineas_select(
-    select: &Select,
-    mut lineas_filtradas: Vec<Vec<String>>,
-    columnas_csv: &[String],
-) -> Result<Vec<Vec<String>>, Box<dyn Error>> {
-    if let Some(ref ordernar) = select.order_by {
-        for ordenamiento_actual in ordernar.iter() {
-            if !columnas_csv.contains(&ordenamiento_actual.columna) {
-                let descripcion = format!(
-                    "La columna '{}' ingresada en ORDER BY clause no se encuentra en el csv.",
-                    ordenamiento_actual.columna
-                );
-                return Err(Box::new(ErrorPrograma::new(
-                    ErrorTipo::Column,
-                    &descripcion,
-                )));
-            }
+/*
+ * Devuelve la columna de la consulta con el nombre que tiene en las filas del plan. Con una sola tabla se le
+ * saca el <tabla>. del principio, y con JOIN se le agrega la tabla que la tiene; si la tiene más de una el nombre
+ * es ambiguo. Los valores y las columnas que no existen quedan como están.
+ */
+fn resolver_columna(columna: &str, tablas: &[TablaConsulta]) -> Result<String, ErrorPrograma> {
+    if let [tabla] = tablas {
+        let sin_tabla = columna
+            .strip_prefix(&tabla.nombre)
+            .and_then(|columna| columna.strip_prefix('.'))
+            .filter(|columna| tabla.columnas.iter().any(|c| c == columna));
+        return Ok(sin_tabla.unwrap_or(columna).to_string());
+    }
+    if columna.contains(['.', '\'']) {
+        return Ok(columna.to_string());
+    }
+    let con_tabla: Vec<String> = tablas
+        .iter()
+        .map(|tabla| format!("{}.{}", tabla.nombre, columna))
+        .filter(|con_tabla| {
+            tablas
+                .iter()
+                .any(|tabla| tabla.columnas.contains(con_tabla))
+        })
+        .collect();
+    match con_tabla.as_slice() {
+        [con_tabla] => Ok(con_tabla.clone()),
+        [] => Ok(columna.to_string()),
+        _ => Err(ErrorPrograma::desde_mensaje(
+            ErrorTipo::UnknownColumn {
+                columna: columna.to_string(),
+            },
+            Mensaje::ColumnaAmbigua,
+            &[columna, columna],
+        )
+        .con_token(columna)),
+    }
+}
+
+// Igual que `resolver_columna`, pero dentro de la función si es una función de agregación.
+fn resolver_expresion(texto: &str, tablas: &[TablaConsulta]) -> Result<String, ErrorPrograma> {
+    match Agregado::desde_texto(texto)? {
+        Some(Agregado {
+            funcion,
+            columna: Some(columna),
+            ..
+        }) => Ok(Agregado::new(funcion, Some(resolver_columna(&columna, tablas)?)).nombre),
+        Some(agregado) => Ok(agregado.nombre),
+        None => resolver_columna(texto, tablas),
+    }
+}
+
+/*
+ * Deja la columna de la izquierda del join en las tablas anteriores y la de la derecha en la tabla que se une,
+ * cambiándolas de lado si se escribieron al revés.
+ */
+fn ordenar_join(
+    join: &mut JoinClause,
+    anteriores: &[TablaConsulta],
+    tabla: &TablaConsulta,
+) -> Result<(), ErrorPrograma> {
+    let en_anteriores = |columna: &String| {
+        anteriores
+            .iter()
+            .any(|anterior| anterior.columnas.contains(columna))
+    };
+    if en_anteriores(&join.columna_izquierda) && tabla.columnas.contains(&join.columna_derecha) {
+        return Ok(());
+    }
+    if en_anteriores(&join.columna_derecha) && tabla.columnas.contains(&join.columna_izquierda) {
+        std::mem::swap(&mut join.columna_izquierda, &mut join.columna_derecha);
+        return Ok(());
+    }
+    let columnas: Vec<String> = anteriores
+        .iter()
+        .chain(std::iter::once(tabla))
+        .flat_map(|tabla| tabla.columnas.iter().cloned())
+        .collect();
+    for columna in [&join.columna_izquierda, &join.columna_derecha] {
+        if !columnas.contains(columna) {
+            return Err(error_columna_desconocida(
+                columna,
+                Mensaje::ColumnaInexistente,
+                &[columna],
+                &columnas,
+            ));
         }
-        lineas_filtradas.sort_by(|a, b| {
-            for ordenamiento_actual in ordernar.iter() {
-                let indice = columnas_csv
-                    .iter()
-                    .position(|col| *col == ordenamiento_actual.columna)
-                    .unwrap_or(0);
-                let resultado_comparado = match ordenamiento_actual.orden {
-                    Order::Asc => a[indice].cmp(&b[indice]),
-                    Order::Desc => b[indice].cmp(&a[indice]),
-                };
-
-                if resultado_comparado != std::cmp::Ordering::Equal {
-                    return resultado_comparado;
-                }
-            }
-            std::cmp::Ordering::Equal
-        });
     }
-    Ok(lineas_filtradas)
+    Err(
+        ErrorPrograma::desde_mensaje(ErrorTipo::Syntax, Mensaje::JoinInvalido, &[])
+            .con_token(&join.columna_izquierda),
+    )
 }
 
-fn seleccionar_y_escribir_columnas_pedidas(
-    lineas_filtradas: Vec<Vec<String>>,
+/*
+ * Verifica las columnas de un SELECT que agrupa: las que se muestran o se ordenan tienen que ser de GROUP BY o
+ * funciones de agregación, y las de GROUP BY y las de dentro de las funciones tienen que existir.
+ */
+fn verificar_columnas_agrupadas(
     select: &Select,
+    agregados: &[Agregado],
     columnas_csv: &[String],
-) -> Result<(), Box<dyn Error>> {
-    for linea in lineas_filtradas.iter() {
-        if select.columnas[0] == "*" {
-            println!("{}", linea.join(","));
-        } else {
-            let linea: Vec<&str> = select
-                .columnas
-                .iter()
-                .map(|col| {
-                    let indice = columnas_csv
-                        .iter()
-                        .position(|col_csv| col_csv == col)
-                        .unwrap_or(0);
-                    linea[indice].as_str()
-                })
-                .collect();
-            println!("{}", linea.join(","));
+) -> Result<(), ErrorPrograma> {
+    let grupos = select.group_by.clone().unwrap_or_default();
+    verificar_existen_columnas_query(&grupos, columnas_csv)?;
+    let agregadas: Vec<String> = agregados
+        .iter()
+        .filter_map(|agregado| agregado.columna.clone())
+        .collect();
+    verificar_existen_columnas_query(&agregadas, columnas_csv)?;
+    let columnas = select
+        .columnas
+        .iter()
+        .map(|columna| (columna, Mensaje::ColumnaInexistente));
+    let claves = select
+        .order_by
+        .iter()
+        .flatten()
+        .map(|clave| (&clave.columna, Mensaje::ColumnaOrderByInexistente));
+    for (columna, mensaje) in columnas.chain(claves) {
+        if grupos.contains(columna) || agregados.iter().any(|a| a.nombre == *columna) {
+            continue;
+        }
+        if columna != "*" && !columnas_csv.contains(columna) {
+            return Err(error_columna_desconocida(
+                columna,
+                mensaje,
+                &[columna],
+                columnas_csv,
+            ));
         }
+        return Err(ErrorPrograma::desde_mensaje(
+            ErrorTipo::Syntax,
+            Mensaje::ColumnaFueraDeGroupBy,
+            &[columna],
+        )
+        .con_token(columna));
     }
     Ok(())
 }
 
 /*
- * Se recibe la ruta del archivo y la estructura de la consulta SELECT.
- * Se abre el archivo y se lee línea por línea. Se verifica si la linea actual cumple con la condición del where,
- * en caso de que sí se guarda esa línea y luego se las ordena dependiendo lo pedido en la consulta.
- * Finalmente se seleccionan las columnas pedidas y se imprimen.
-*/
-pub fn aplicar_select(ruta: &String, select: &Select) -> Result<(), Box<dyn Error>> {
-    let (lineas, columnas_csv) = abrir_archivo(ruta)?;
+ * Abre las tablas del SELECT y verifica que existan todas las columnas que usa la consulta. Devuelve las
+ * tablas, la consulta con las columnas nombradas como en las filas del plan y las funciones de agregación.
+ */
+fn preparar_select(
+    ruta: &str,
+    select: &Select,
+) -> Result<(Vec<TablaConsulta>, Select, Vec<Agregado>), ErrorPrograma> {
+    let nombres = std::iter::once(&select.tabla).chain(select.joins.iter().map(|join| &join.tabla));
+    let mut tablas: Vec<TablaConsulta> = Vec::new();
+    for (nombre, ruta_tabla) in nombres.zip(rutas_select(ruta, select)) {
+        if tablas.iter().any(|tabla| tabla.nombre == *nombre) {
+            return Err(ErrorPrograma::desde_mensaje(
+                ErrorTipo::Syntax,
+                Mensaje::JoinTablaRepetida,
+                &[nombre],
+            )
+            .con_token(nombre));
+        }
+        let (_, columnas) = abrir_archivo(&ruta_tabla)?;
+        tablas.push(TablaConsulta {
+            nombre: nombre.clone(),
+            ruta: ruta_tabla,
+            columnas,
+        });
+    }
+    if tablas.len() > 1 {
+        for tabla in tablas.iter_mut() {
+            for columna in tabla.columnas.iter_mut() {
+                *columna = format!("{}.{}", tabla.nombre, columna);
+            }
+        }
+    }
+    let columnas_csv: Vec<String> = tablas
+        .iter()
+        .flat_map(|tabla| tabla.columnas.iter().cloned())
+        .collect();
+
     if select.columnas.contains(&"*".to_string()) && select.columnas.len() > 1 {
-        return Err(Box::new(ErrorPrograma::new(
-            ErrorTipo::Column,
-            "No se puede seleccionar todas las columnas '*' y otras columnas al mismo tiempo.",
-        )));
-    } else if !select.columnas.contains(&"*".to_string()) {
-        verificar_existen_columnas_query(&select.columnas, &columnas_csv)?;
+        return Err(ErrorPrograma::desde_mensaje(
+            ErrorTipo::Syntax,
+            Mensaje::AsteriscoConColumnas,
+            &[],
+        )
+        .con_token("*"));
+    }
+    let mut select = select.clone();
+    for columna in select.columnas.iter_mut().filter(|columna| *columna != "*") {
+        *columna = resolver_expresion(columna, &tablas)?;
+    }
+    for clause in select.where_clauses.iter_mut().flatten() {
+        clause.valor1 = resolver_columna(&clause.valor1, &tablas)?;
+        clause.valor2 = resolver_columna(&clause.valor2, &tablas)?;
+    }
+    for clave in select.order_by.iter_mut().flatten() {
+        clave.columna = resolver_expresion(&clave.columna, &tablas)?;
+    }
+    for grupo in select.group_by.iter_mut().flatten() {
+        *grupo = resolver_columna(grupo, &tablas)?;
+    }
+    for (indice, join) in select.joins.iter_mut().enumerate() {
+        join.columna_izquierda = resolver_columna(&join.columna_izquierda, &tablas)?;
+        join.columna_derecha = resolver_columna(&join.columna_derecha, &tablas)?;
+        ordenar_join(join, &tablas[..=indice], &tablas[indice + 1])?;
+    }
+
+    let agregados = agregados_de_select(&select)?;
+    if !agregados.is_empty() || select.group_by.is_some() {
+        verificar_columnas_agrupadas(&select, &agregados, &columnas_csv)?;
+    } else {
+        if select.columnas[0] != "*" {
+            verificar_existen_columnas_query(&select.columnas, &columnas_csv)?;
+        }
+        verificar_existen_columnas_order_by(&select, &columnas_csv)?;
     }
     if select.where_clauses.is_some() {
         verificar_existen_columnas_where_clause(&select.where_clauses, &columnas_csv)?;
     }
+    Ok((tablas, select, agregados))
+}
 
-    let lineas_filtradas =
-        filtrar_lineas_que_cumplen_con_where_clause(lineas, select, &columnas_csv)?;
-
-    let lineas_filtradas = ordenar_lineas_select(select, lineas_filtradas, &columnas_csv)?;
+/*
+ * Se recibe la ruta del archivo, la estructura de la consulta SELECT y el destino de las filas resultantes.
+ * Se arma el plan de la consulta, que indica cómo se recorre cada tabla y cómo se unen, y se lo ejecuta:
+ * cada fila que cumple con la condición del where se le entrega al destino con las columnas pedidas. Si hay
+ * ORDER BY y el índice no da ese orden, primero se guardan todas las filas que cumplen y se las ordena.
+*/
+pub fn ejecutar_select(
+    ruta: &str,
+    select: &Select,
+    destino: &mut dyn DestinoFilas,
+) -> Result<(), ErrorPrograma> {
+    let _bloqueos = bloquear_tablas_select(ruta, select)?;
+    let (tablas, normalizada, agregados) = preparar_select(ruta, select)?;
 
     if select.columnas[0] == "*" {
-        println!("{}", columnas_csv.join(","));
+        let columnas: Vec<String> = tablas
+            .iter()
+            .flat_map(|tabla| tabla.columnas.iter().cloned())
+            .collect();
+        destino.encabezado(&columnas)?;
     } else {
-        println!("{}", select.columnas.join(","));
+        destino.encabezado(&select.columnas)?;
     }
 
-    seleccionar_y_escribir_columnas_pedidas(lineas_filtradas, select, &columnas_csv)?;
-
+    let plan = planificar_select(&tablas, &normalizada, agregados)?;
+    ejecutar_plan(&plan, destino, false)?;
     Ok(())
 }
 
-fn aplicar_filtro_extend(
-    valores: &[String],
-    columnas_csv: &[String],
-    where_clauses: &[WhereClause],
-    operadores_logicos: &[LogicalOperators],
-) -> Result<(bool, Vec<bool>), Box<dyn Error>> {
-    let mut resultado_and = true;
-    let mut or_conditions = Vec::new();
-    let mut evaluando_and = false;
-    for (index, clause) in where_clauses.iter().enumerate() {
-        let resultado_actual = cumple_condicion(
-            &valores.iter().map(|s| s.as_str()).collect::<Vec<&str>>(),
-            columnas_csv,
-            clause,
-        )?;
-        match operadores_logicos.get(index) {
-            Some(LogicalOperators::And) => {
-                if evaluando_and {
-                    resultado_and = resultado_and && resultado_actual;
-                } else {
-                    resultado_and = resultado_actual;
-                    evaluando_and = true;
-                }
-            }
-            Some(LogicalOperators::Or) => {
-                if evaluando_and {
-                    or_conditions.push(resultado_and);
-                    evaluando_and = false;
-                }
-            }
-            None => {
-                if evaluando_and {
-                    resultado_and = resultado_and && resultado_actual;
-                } else {
-                    resultado_and = resultado_actual;
-                }
-                or_conditions.push(resultado_and);
-            }
-        }
+/*
+ * Escribe en el destino el plan con el que se ejecutaría el SELECT. Con ANALYZE además se lo ejecuta
+ * y se indica cuántas filas devolvió y cuánto tardó cada operación.
+ */
+pub fn explicar_select(
+    ruta: &str,
+    select: &Select,
+    analizar: bool,
+    destino: &mut dyn DestinoFilas,
+) -> Result<(), ErrorPrograma> {
+    let _bloqueos = bloquear_tablas_select(ruta, select)?;
+    let (tablas, normalizada, agregados) = preparar_select(ruta, select)?;
+    let plan = planificar_select(&tablas, &normalizada, agregados)?;
+    escribir_explain(&plan, analizar, destino)
+}
+
+/*
+ * Se recibe la ruta del archivo y la estructura de la consulta SELECT.
+ * Se ejecuta la consulta y se imprimen las filas resultantes por salida estándar.
+*/
+pub fn aplicar_select(ruta: &str, select: &Select) -> Result<(), ErrorPrograma> {
+    ejecutar_select(ruta, select, &mut SalidaEstandar)
+}
+
+/*
+ * Escribe el resultado del SELECT en el archivo pedido, con el encabezado y en el formato elegido.
+ * Se escribe en un temporal al lado del archivo que se renombra al terminar, así el archivo
+ * nunca queda escrito a medias y si la consulta falla se conserva el que había antes.
+ */
+pub fn exportar_select(
+    ruta: &str,
+    select: &Select,
+    exportacion: &Exportacion,
+) -> Result<(), ErrorPrograma> {
+    let temporal = ArchivoTemporal::crear_junto_a(&exportacion.ruta)?;
+    let mut destino = SalidaFormateada::new(exportacion.formato, temporal);
+    ejecutar_select(ruta, select, &mut destino)?;
+    destino.terminar()?;
+
+    let ruta_temporal = destino.into_escritor().preparar()?;
+    if let Err(e) = rename(&ruta_temporal, &exportacion.ruta) {
+        let _ = remove_file(&ruta_temporal);
+        return Err(e.into());
     }
-    Ok((resultado_and, or_conditions))
+    Ok(())
 }
 
+/*
+ * Indica si los valores de una fila cumplen el WHERE. AND tiene precedencia sobre OR.
+ */
 fn aplicar_filtro(
     valores: &[String],
     columnas_csv: &[String],
     where_clauses: &[WhereClause],
     operadores_logicos: &[LogicalOperators],
-) -> Result<bool, Box<dyn Error>> {
-    if where_clauses.is_empty() {
-        return Ok(true);
-    }
-
-    let (resultado_and, or_conditions) =
-        aplicar_filtro_extend(valores, columnas_csv, where_clauses, operadores_logicos)?;
-
-    // Evaluar las condiciones OR
-    let mut resultado_final = resultado_and;
-    for cond in or_conditions {
-        resultado_final = resultado_final || cond;
-    }
-    Ok(resultado_final)
+) -> Result<bool, ErrorPrograma> {
+    let valores: Vec<&str> = valores.iter().map(|s| s.as_str()).collect();
+    Predicado::desde_where(where_clauses, operadores_logicos).evaluar(&valores, columnas_csv)
 }
 
-fn cumple_condicion(
+pub(crate) fn cumple_condicion(
     valores: &[&str],
     columnas_csv: &[String],
     where_clause: &WhereClause,
-) -> Result<bool, Box<dyn Error>> {
+) -> Result<bool, ErrorPrograma> {
     let (valor1_string, valor1_int, es_numero1) =
         obtener_valor(valores, columnas_csv, &where_clause.valor1)?;
     let (valor2_string, valor2_int, es_numero2) =
@@ -311,20 +549,36 @@ fn obtener_valor(
     valores: &[&str],
     columnas_csv: &[String],
     valor: &str,
-) -> Result<(String, usize, bool), Box<dyn Error>> {
+) -> Result<(String, usize, bool), ErrorPrograma> {
     let valor = valor.trim_matches('\'');
 
     if columnas_csv.contains(&valor.to_string()) {
         let indice = columnas_csv
             .iter()
             .position(|col| col == valor)
-            .ok_or(Box::new(ErrorPrograma::new(
-                ErrorTipo::Column,
-                "La coumna no se encuentra.",
-            )))?;
+            .ok_or_else(|| {
+                ErrorPrograma::desde_mensaje(
+                    ErrorTipo::UnknownColumn {
+                        columna: valor.to_string(),
+                    },
+                    Mensaje::ColumnaNoEncontrada,
+                    &[],
+                )
+            })?;
         Ok((valores[indice].to_string(), 0, false))
     } else if es_int(valor) {
-        Ok((valor.to_string(), valor.parse::<usize>()?, true))
+        let numero = valor.parse::<usize>().map_err(|_| {
+            ErrorPrograma::desde_mensaje(
+                ErrorTipo::TypeMismatch {
+                    columna: None,
+                    valor: valor.to_string(),
+                },
+                Mensaje::EnteroPositivo,
+                &[valor],
+            )
+            .con_token(valor)
+        })?;
+        Ok((valor.to_string(), numero, true))
     } else {
         Ok((valor.to_string(), 0, false))
     }
@@ -335,7 +589,7 @@ fn verificar_si_linea_cumple_condicion(
     where_clauses: &Option<Vec<WhereClause>>,
     operacion_logica: &Option<Vec<LogicalOperators>>,
     columnas_csv: &[String],
-) -> Result<(bool, Vec<String>), Box<dyn Error>> {
+) -> Result<(bool, Vec<String>), ErrorPrograma> {
     let valores: Vec<String> = linea.split(',').map(|s| s.trim().to_string()).collect();
 
     let cumple_condicion = if let Some(ref condiciones) = where_clauses {
@@ -358,7 +612,7 @@ fn actualizar_valores_fila(
     update: &Update,
     mut valores: Vec<String>,
     columnas_csv: &[String],
-) -> Result<Vec<String>, Box<dyn Error>> {
+) -> Result<Vec<String>, ErrorPrograma> {
     for set_clause in update.set.split(',') {
         let partes_set: Vec<&str> = set_clause.split('=').map(|s| s.trim()).collect();
         if partes_set.len() == 2 {
@@ -371,18 +625,19 @@ fn actualizar_valores_fila(
                 }
                 valores[indice] = valor_a_cambiar.to_string();
             } else {
-                let descripcion =
-                    format!("La columna {} no fue existe en el archivo csv.", columna);
-                return Err(Box::new(ErrorPrograma::new(
-                    ErrorTipo::Column,
-                    &descripcion,
-                )));
+                return Err(error_columna_desconocida(
+                    columna,
+                    Mensaje::ColumnaSetInexistente,
+                    &[columna],
+                    columnas_csv,
+                ));
             }
         } else {
-            return Err(Box::new(ErrorPrograma::new(
+            return Err(ErrorPrograma::desde_mensaje(
                 ErrorTipo::Syntax,
-                "La sintaxis es inválida en la SET clause.",
-            )));
+                Mensaje::SetInvalido,
+                &[],
+            ));
         }
     }
     Ok(valores)
@@ -391,15 +646,16 @@ fn actualizar_valores_fila(
 pub fn crear_escribir_archivo_temporal_update(
     ruta: &String,
     update: &Update,
-    ruta_temporal: &String,
-) -> Result<(), Box<dyn Error>> {
+) -> ResultFilasAfectadas {
+    let _bloqueo = bloquear_tabla(ruta, ModoBloqueo::Exclusivo)?;
     let (lineas, columnas_csv) = abrir_archivo(ruta)?;
     if update.where_clauses.is_some() {
         verificar_existen_columnas_where_clause(&update.where_clauses, &columnas_csv)?;
     }
-    let archivo_temporal = File::create(ruta_temporal)?;
-    let mut temporal_writer = BufWriter::new(archivo_temporal);
+    verificar_columnas_returning(&update.returning, &columnas_csv)?;
+    let mut temporal_writer = ArchivoTemporal::crear_junto_a(ruta)?;
     writeln!(temporal_writer, "{}", columnas_csv.join(","))?;
+    let mut cambios = Vec::new();
 
     for linea in lineas {
         let linea = linea?;
@@ -411,51 +667,64 @@ pub fn crear_escribir_archivo_temporal_update(
         )?;
 
         if cumple_condicion {
-            let valores = actualizar_valores_fila(update, valores, &columnas_csv)?;
-            let nueva_linea = valores.join(",");
+            let valores_nuevos = actualizar_valores_fila(update, valores.clone(), &columnas_csv)?;
+            let nueva_linea = valores_nuevos.join(",");
 
             writeln!(temporal_writer, "{}", nueva_linea)?;
+            cambios.push((valores, Some(valores_nuevos)));
         } else {
             writeln!(temporal_writer, "{}", linea)?;
         }
     }
-    rename(ruta_temporal, ruta)?;
-    drop(temporal_writer);
-    Ok(())
+
+    let filas_actualizadas: Vec<Vec<String>> = cambios
+        .iter()
+        .filter_map(|(_, nueva)| nueva.clone())
+        .collect();
+    verificar_filas_hijas(ruta, &columnas_csv, &filas_actualizadas)?;
+    let mut bitacora = Bitacora::iniciar(&separar_ruta_tabla(ruta).0)?;
+    propagar_cambios_padre(ruta, &columnas_csv, &cambios, &mut bitacora)?;
+
+    bitacora.registrar_reemplazo(temporal_writer, ruta)?;
+    confirmar_con_indices(bitacora)?;
+    Ok((columnas_csv, filas_actualizadas))
 }
 
 /*
  * Se recibe la ruta del archivo y la estructura de la consulta UPDATE.
  * Se abre el archivo y se lee línea por línea. Se verifica si la linea actual cumple con la condición del where,
- * en caso de que sí, se actualizan los valores de la fila y se escribe en un archivo temporal.
- * Luego se renombra el archivo temporal al archivo original.
+ * en caso de que sí, se actualizan los valores de la fila y se escribe en un archivo temporal único al lado de la tabla.
+ * Luego el temporal y los de las tablas hijas modificadas se registran en una bitácora y se renombran sobre los originales;
+ * si hay un error en el medio los temporales se borran.
+ * Devuelve las filas ya actualizadas.
 */
-pub fn aplicar_update(ruta: &String, update: &Update) -> Result<(), Box<dyn Error>> {
-    crear_escribir_archivo_temporal_update(ruta, update, &"datos/temporal.csv".to_string())?;
-    Ok(())
+pub fn aplicar_update(ruta: &String, update: &Update) -> ResultFilasAfectadas {
+    crear_escribir_archivo_temporal_update(ruta, update)
 }
 
 pub fn crear_escribir_archivo_temporal_delete(
-    ruta_temporal: &String,
     delete: &Delete,
     ruta: &String,
-) -> Result<(), Box<dyn Error>> {
-    let archivo_temporal = File::create(ruta_temporal)?;
+) -> ResultFilasAfectadas {
+    let _bloqueo = bloquear_tabla(ruta, ModoBloqueo::Exclusivo)?;
     let (lineas, columnas_csv) = abrir_archivo(ruta)?;
     if delete.where_clauses.is_some() {
         verificar_existen_columnas_where_clause(&delete.where_clauses, &columnas_csv)?;
     } else {
-        Err(Box::new(ErrorPrograma::new(
+        Err(ErrorPrograma::desde_mensaje(
             ErrorTipo::Syntax,
-            "No hay una WHERE clause en la consulta DELETE.",
-        )))?
+            Mensaje::DeleteSinWhereClause,
+            &[],
+        ))?
     }
-    let mut temporal_writer = BufWriter::new(archivo_temporal);
+    verificar_columnas_returning(&delete.returning, &columnas_csv)?;
+    let mut temporal_writer = ArchivoTemporal::crear_junto_a(ruta)?;
     writeln!(temporal_writer, "{}", columnas_csv.join(","))?;
+    let mut cambios = Vec::new();
 
     for linea in lineas {
         let linea = linea?;
-        let (cumple_condicion, _) = verificar_si_linea_cumple_condicion(
+        let (cumple_condicion, valores) = verificar_si_linea_cumple_condicion(
             &linea,
             &delete.where_clauses,
             &delete.operacion_logica,
@@ -463,82 +732,826 @@ pub fn crear_escribir_archivo_temporal_delete(
         )?;
         if !cumple_condicion {
             writeln!(temporal_writer, "{}", linea)?;
+        } else {
+            cambios.push((valores, None));
         }
     }
 
-    rename(ruta_temporal, ruta)?;
-    temporal_writer.flush()?;
-    drop(temporal_writer);
-    Ok(())
+    let mut bitacora = Bitacora::iniciar(&separar_ruta_tabla(ruta).0)?;
+    propagar_cambios_padre(ruta, &columnas_csv, &cambios, &mut bitacora)?;
+
+    bitacora.registrar_reemplazo(temporal_writer, ruta)?;
+    confirmar_con_indices(bitacora)?;
+    let filas_eliminadas = cambios.into_iter().map(|(fila, _)| fila).collect();
+    Ok((columnas_csv, filas_eliminadas))
 }
 
 /*
  * Se recibe la ruta del archivo y la estructura de la consulta DELETE.
  * Se abre el archivo y se lee línea por línea. Se verifica si la linea actual cumple con la condición del where,
- * en caso de que sí, se elimina esa línea del archivo.
+ * en caso de que sí, se elimina esa línea del archivo. Devuelve las filas eliminadas tal como estaban.
 */
-pub fn aplicar_delete(ruta: &String, delete: &Delete) -> Result<(), Box<dyn Error>> {
-    crear_escribir_archivo_temporal_delete(&"datos/temporal.csv".to_string(), delete, ruta)?;
-    Ok(())
+pub fn aplicar_delete(ruta: &String, delete: &Delete) -> ResultFilasAfectadas {
+    crear_escribir_archivo_temporal_delete(delete, ruta)
 }
 
-fn escribir_linea(
-    linea: &str,
-    file: &mut File,
+/*
+ * Separa los valores de una tupla de VALUES por comas, sin cortar los strings entre comillas simples.
+ */
+fn separar_valores_tupla(tupla: &str) -> Vec<String> {
+    let mut valores = Vec::new();
+    let mut valor_actual = String::new();
+    let mut dentro_comillas = false;
+
+    for caracter in tupla.chars() {
+        match caracter {
+            '\'' => {
+                dentro_comillas = !dentro_comillas;
+                valor_actual.push(caracter);
+            }
+            ',' if !dentro_comillas => {
+                valores.push(valor_actual.trim().to_string());
+                valor_actual = String::new();
+            }
+            _ => valor_actual.push(caracter),
+        }
+    }
+    if !valor_actual.trim().is_empty() || !valores.is_empty() {
+        valores.push(valor_actual.trim().to_string());
+    }
+    valores
+}
+
+/*
+ * Convierte un literal de la query en el valor que se guarda en el csv: se quitan las comillas simples
+ * y NULL se guarda como un valor vacío.
+ */
+fn valor_literal(valor: &str) -> String {
+    if valor.len() >= 2 && valor.starts_with('\'') && valor.ends_with('\'') {
+        valor[1..valor.len() - 1].to_string()
+    } else if valor == "NULL" {
+        String::new()
+    } else {
+        valor.to_string()
+    }
+}
+
+/*
+ * Arma la fila completa del csv a partir de los valores de las columnas del insert.
+ * Un valor None (DEFAULT) o una columna que no se indicó toma el valor por defecto de la columna o queda vacía.
+ */
+fn armar_fila(
+    valores: &[Option<String>],
     columnas_csv: &[String],
-    columnas_insert: &[&str],
-) -> Result<(), Box<dyn Error>> {
-    let valores: Vec<&str> = linea.split(',').map(|s| s.trim()).collect();
-    let mut linea_actual = String::new();
+    columnas_insert: &[String],
+    esquema: &Esquema,
+) -> Vec<String> {
+    let mut fila = Vec::new();
 
     for columna_c in columnas_csv.iter() {
-        if let Some(pos) = columnas_insert.iter().position(|&col| col == *columna_c) {
-            let mut valor_actual = valores.get(pos).unwrap_or(&"").to_string();
-            if valor_actual.starts_with('\'') && valor_actual.ends_with('\'') {
-                valor_actual = valor_actual[1..valor_actual.len() - 1].to_string();
+        let valor_actual = match columnas_insert.iter().position(|col| col == columna_c) {
+            Some(pos) if valores[pos].is_some() => valores[pos].clone().unwrap_or_default(),
+            _ => valor_literal(esquema.default_de(columna_c).unwrap_or("")),
+        };
+        fila.push(valor_actual);
+    }
+    fila
+}
+
+fn verificar_cantidad_valores(
+    cantidad_valores: usize,
+    cantidad_columnas: usize,
+    fila: Option<usize>,
+) -> Result<(), ErrorPrograma> {
+    if cantidad_valores != cantidad_columnas {
+        let cantidades = [cantidad_valores.to_string(), cantidad_columnas.to_string()];
+        // Sin número de fila los valores vienen de un INSERT ... SELECT.
+        let error = match fila {
+            Some(fila) => ErrorPrograma::desde_mensaje(
+                ErrorTipo::Syntax,
+                Mensaje::CantidadValoresFila,
+                &[&fila.to_string(), &cantidades[0], &cantidades[1]],
+            ),
+            None => ErrorPrograma::desde_mensaje(
+                ErrorTipo::Syntax,
+                Mensaje::CantidadValoresSelect,
+                &[&cantidades[0], &cantidades[1]],
+            ),
+        };
+        return Err(error);
+    }
+    Ok(())
+}
+
+/*
+ * Completa el auto increment de las filas, verifica sus foreign keys y las escribe al final del archivo csv.
+ */
+fn escribir_filas_insert(
+    ruta: &String,
+    columnas_csv: &[String],
+    filas: &mut [Vec<String>],
+    bitacora: &mut Bitacora,
+) -> Result<(), ErrorPrograma> {
+    if filas.is_empty() {
+        return Ok(());
+    }
+    verificar_valores_guardables(columnas_csv, filas)?;
+    asignar_auto_increment(ruta, columnas_csv, filas)?;
+    verificar_filas_hijas(ruta, columnas_csv, filas)?;
+
+    let mut lineas_nuevas = String::new();
+    for fila in filas.iter() {
+        lineas_nuevas.push_str(&fila.join(","));
+        lineas_nuevas.push('\n');
+    }
+
+    // La tabla está bloqueada durante todo el insert, así la longitud anterior que queda en la bitácora
+    // permite quitar las líneas agregadas si la operación no termina.
+    let mut file = OpenOptions::new().append(true).open(ruta)?;
+    bitacora.registrar_agregado(ruta, file.metadata()?.len())?;
+    file.write_all(lineas_nuevas.as_bytes())?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/*
+ * Verifica que ningún valor tenga comas o saltos de línea: la tabla no usa comillas, así que la fila quedaría
+ * con más valores o partida en varias líneas.
+ */
+fn verificar_valores_guardables(
+    columnas_csv: &[String],
+    filas: &[Vec<String>],
+) -> Result<(), ErrorPrograma> {
+    for fila in filas.iter() {
+        for (columna, valor) in columnas_csv.iter().zip(fila.iter()) {
+            if valor.contains([',', '\n', '\r']) {
+                return Err(ErrorPrograma::desde_mensaje(
+                    ErrorTipo::TypeMismatch {
+                        columna: Some(columna.clone()),
+                        valor: valor.clone(),
+                    },
+                    Mensaje::ValorNoGuardable,
+                    &[valor, columna],
+                )
+                .con_token(valor));
             }
-            linea_actual.push_str(&valor_actual);
         }
-        linea_actual.push(',');
     }
+    Ok(())
+}
 
-    if linea_actual.ends_with(',') {
-        linea_actual.pop();
+fn parsear_auto_increment(columna: &str, valor: &str) -> Result<u64, ErrorPrograma> {
+    valor.parse::<u64>().map_err(|_| {
+        ErrorPrograma::desde_mensaje(
+            ErrorTipo::TypeMismatch {
+                columna: Some(columna.to_string()),
+                valor: valor.to_string(),
+            },
+            Mensaje::AutoIncrementNoEntero,
+            &[valor, columna],
+        )
+    })
+}
+
+/*
+ * Si la tabla tiene una columna auto increment, se completan las filas que no tienen valor en ella
+ * con los siguientes valores del contador. Los valores ingresados a mano se respetan y hacen avanzar el contador.
+ */
+fn asignar_auto_increment(
+    ruta: &str,
+    columnas_csv: &[String],
+    filas: &mut [Vec<String>],
+) -> Result<(), ErrorPrograma> {
+    let auto_increment = match Esquema::cargar(ruta)?.auto_increment {
+        Some(auto_increment) => auto_increment,
+        None => return Ok(()),
+    };
+    let indice = match columnas_csv
+        .iter()
+        .position(|col| *col == auto_increment.columna)
+    {
+        Some(indice) => indice,
+        None => return Ok(()),
+    };
+
+    let mut cantidad_vacios = 0;
+    let mut minimo_siguiente = 0;
+    for fila in filas.iter() {
+        if fila[indice].is_empty() {
+            cantidad_vacios += 1;
+        } else {
+            let valor = parsear_auto_increment(&auto_increment.columna, &fila[indice])?;
+            minimo_siguiente = minimo_siguiente.max(valor + 1);
+        }
+    }
+    if cantidad_vacios == 0 && minimo_siguiente < auto_increment.siguiente {
+        return Ok(());
     }
 
-    writeln!(file, "{}", linea_actual)?;
+    // Dentro de una transacción los valores se reservan en el directorio de tablas, compartido con las otras
+    // sesiones, salvo que la tabla recién tenga auto increment en la transacción.
+    let reservado = match tabla_fuera_de_transaccion(ruta) {
+        Some(original) => reservar_auto_increment(&original, cantidad_vacios, minimo_siguiente)?,
+        None => None,
+    };
+    let reservado = match reservado {
+        Some(primero) => Some(primero),
+        None => reservar_auto_increment(ruta, cantidad_vacios, minimo_siguiente)?,
+    };
+    let mut siguiente = reservado.unwrap_or(auto_increment.siguiente);
+    for fila in filas.iter_mut() {
+        if fila[indice].is_empty() {
+            fila[indice] = siguiente.to_string();
+            siguiente += 1;
+        }
+    }
     Ok(())
 }
 
+// Cantidad de filas de un INSERT ... SELECT que se acumulan antes de escribirlas en la tabla.
+const TAMANIO_LOTE_INSERT: usize = 1000;
+
 /*
- * Se recibe la ruta del archivo, el nombre de las columnas donde se insertan valores y los valores a insertar
- * Se abre el archivo y se escriben los valores en el archivo csv en caso de que las columnas pedidas
- * coincidan con las columnas del archivo.
-*/
-pub fn aplicar_insert(
+ * Destino de las filas de un INSERT ... SELECT: cada fila del select se mapea a las columnas del insert
+ * y se escribe en la tabla de a lotes, sin guardar el resultado completo del select en memoria.
+ */
+struct DestinoInsert<'a> {
+    ruta: &'a String,
+    columnas_csv: &'a [String],
+    columnas_insert: &'a [String],
+    esquema: &'a Esquema,
+    pendientes: Vec<Vec<String>>,
+    tamanio_lote: usize,
+    bitacora: &'a mut Bitacora,
+    // Solo se guardan las filas insertadas si la query tiene RETURNING.
+    insertadas: Option<Vec<Vec<String>>>,
+}
+
+impl DestinoInsert<'_> {
+    fn escribir_pendientes(&mut self) -> Result<(), ErrorPrograma> {
+        escribir_filas_insert(
+            self.ruta,
+            self.columnas_csv,
+            &mut self.pendientes,
+            self.bitacora,
+        )?;
+        match self.insertadas {
+            Some(ref mut insertadas) => insertadas.append(&mut self.pendientes),
+            None => self.pendientes.clear(),
+        }
+        Ok(())
+    }
+}
+
+impl DestinoFilas for DestinoInsert<'_> {
+    fn encabezado(&mut self, columnas: &[String]) -> Result<(), Box<dyn Error>> {
+        Ok(verificar_cantidad_valores(
+            columnas.len(),
+            self.columnas_insert.len(),
+            None,
+        )?)
+    }
+
+    fn fila(&mut self, valores: Vec<String>) -> Result<(), Box<dyn Error>> {
+        let valores: Vec<Option<String>> = valores.into_iter().map(Some).collect();
+        self.pendientes.push(armar_fila(
+            &valores,
+            self.columnas_csv,
+            self.columnas_insert,
+            self.esquema,
+        ));
+        if self.pendientes.len() >= self.tamanio_lote {
+            self.escribir_pendientes()?;
+        }
+        Ok(())
+    }
+}
+
+/*
+ * Se ejecuta el select del insert y sus filas se insertan en la tabla a medida que se generan.
+ * Si el select lee de la misma tabla en la que se inserta, se guardan todas las filas antes de escribir
+ * para no volver a leer las filas recién insertadas.
+ */
+fn insertar_desde_select(
     ruta: &String,
-    nombre_columnas_insertar: &str,
-    valores_a_insertar: Vec<String>,
-) -> Result<(), Box<dyn Error>> {
+    columnas_csv: &[String],
+    columnas_insert: &[String],
+    esquema: &Esquema,
+    insert: &Insert,
+    select: &Select,
+    bitacora: &mut Bitacora,
+) -> Result<Vec<Vec<String>>, ErrorPrograma> {
+    let (directorio, tabla) = separar_ruta_tabla(ruta);
+    let ruta_origen = format!("{}/{}.csv", directorio, select.tabla);
+    let lee_la_tabla = select.tabla == tabla || select.joins.iter().any(|join| join.tabla == tabla);
+    let tamanio_lote = if lee_la_tabla {
+        usize::MAX
+    } else {
+        TAMANIO_LOTE_INSERT
+    };
+
+    let mut destino = DestinoInsert {
+        ruta,
+        columnas_csv,
+        columnas_insert,
+        esquema,
+        pendientes: Vec::new(),
+        tamanio_lote,
+        bitacora,
+        insertadas: insert.returning.as_ref().map(|_| Vec::new()),
+    };
+    ejecutar_select(&ruta_origen, select, &mut destino)?;
+    destino.escribir_pendientes()?;
+    Ok(destino.insertadas.unwrap_or_default())
+}
+
+/*
+ * Se recibe la ruta del archivo y la estructura de la consulta INSERT.
+ * Se verifica que cada fila de VALUES tenga tantos valores como columnas indicadas y se completan las
+ * columnas que no se indicaron con su valor por defecto o con NULL (valor vacío).
+ * Finalmente se escriben las filas al final del archivo csv. Si el insert tiene un SELECT en lugar de VALUES,
+ * las filas se toman del resultado del select. Si la operación no termina, la bitácora quita las filas agregadas.
+*/
+pub fn aplicar_insert(ruta: &String, insert: &Insert) -> ResultFilasAfectadas {
+    let _bloqueo = bloquear_tabla(ruta, ModoBloqueo::Exclusivo)?;
     let (_, columnas_csv) = abrir_archivo(ruta)?;
+    let esquema = Esquema::cargar(ruta)?;
+    verificar_columnas_returning(&insert.returning, &columnas_csv)?;
 
-    let columnas_insert: Vec<&str> = nombre_columnas_insertar
-        .split(',')
-        .map(|s| s.trim())
-        .collect();
+    let columnas_insert: Vec<String> = if insert.nombre_col.is_empty() {
+        columnas_csv.clone()
+    } else {
+        insert
+            .nombre_col
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .collect()
+    };
+    verificar_existen_columnas_query(&columnas_insert, &columnas_csv)?;
+    verificar_columnas_sin_repetir(&columnas_insert, Mensaje::ColumnaRepetidaInsert)?;
+
+    let mut bitacora = Bitacora::iniciar(&separar_ruta_tabla(ruta).0)?;
+    if let Some(ref select) = insert.select {
+        let filas = insertar_desde_select(
+            ruta,
+            &columnas_csv,
+            &columnas_insert,
+            &esquema,
+            insert,
+            select,
+            &mut bitacora,
+        )?;
+        confirmar_con_indices(bitacora)?;
+        return Ok((columnas_csv, filas));
+    }
 
-    verificar_existen_columnas_query(
-        &columnas_insert
+    let tuplas: Vec<Vec<String>> = if insert.default_values {
+        vec![vec!["DEFAULT".to_string(); columnas_insert.len()]]
+    } else {
+        insert
+            .valores
             .iter()
-            .map(|c| c.to_string())
-            .collect::<Vec<String>>(),
-        &columnas_csv,
-    )?;
+            .map(|tupla| separar_valores_tupla(tupla))
+            .collect()
+    };
 
-    let mut file = OpenOptions::new().append(true).open(ruta)?;
+    let mut filas = Vec::new();
+    for (numero, tupla) in tuplas.iter().enumerate() {
+        verificar_cantidad_valores(tupla.len(), columnas_insert.len(), Some(numero + 1))?;
+        let valores: Vec<Option<String>> = tupla
+            .iter()
+            .map(|valor| match valor.as_str() {
+                "DEFAULT" => None,
+                _ => Some(valor_literal(valor)),
+            })
+            .collect();
+        filas.push(armar_fila(
+            &valores,
+            &columnas_csv,
+            &columnas_insert,
+            &esquema,
+        ));
+    }
+    escribir_filas_insert(ruta, &columnas_csv, &mut filas, &mut bitacora)?;
+    confirmar_con_indices(bitacora)?;
+    Ok((columnas_csv, filas))
+}
+
+fn verificar_columnas_sin_repetir(
+    columnas: &[String],
+    mensaje: Mensaje,
+) -> Result<(), ErrorPrograma> {
+    for (i, columna) in columnas.iter().enumerate() {
+        if columnas[..i].contains(columna) {
+            let error = ErrorPrograma::desde_mensaje(ErrorTipo::Syntax, mensaje, &[columna]);
+            return Err(error.con_token(columna));
+        }
+    }
+    Ok(())
+}
+
+// Registro de un archivo importado: la línea en la que empieza y sus valores, o por qué no se pudo leer.
+type RegistroCsv = (usize, Result<Vec<String>, String>);
+
+// Cantidad máxima de filas con error que se describen al fallar una importación, el resto solo se cuenta.
+const MAXIMO_ERRORES_IMPORTACION: usize = 20;
+
+fn terminar_valor(valor: &mut String, con_comillas: &mut bool) -> String {
+    let terminado = if *con_comillas {
+        valor.clone()
+    } else {
+        valor.trim().to_string()
+    };
+    valor.clear();
+    *con_comillas = false;
+    terminado
+}
+
+/*
+ * Separa el texto de un archivo csv en registros, respetando los valores entre comillas dobles, que pueden tener
+ * el delimitador, comillas duplicadas o saltos de línea. Los valores sin comillas se recortan y las líneas vacías se ignoran.
+ */
+fn separar_registros_csv(texto: &str, delimitador: char) -> Vec<RegistroCsv> {
+    let mut registros = Vec::new();
+    let mut valores = Vec::new();
+    let mut valor = String::new();
+    let mut entre_comillas = false;
+    let mut con_comillas = false;
+    let mut linea = 1;
+    let mut linea_inicio = 1;
+    let mut caracteres = texto.chars().peekable();
+
+    while let Some(caracter) = caracteres.next() {
+        if entre_comillas {
+            match caracter {
+                '"' if caracteres.peek() == Some(&'"') => {
+                    caracteres.next();
+                    valor.push('"');
+                }
+                '"' => entre_comillas = false,
+                '\n' => {
+                    linea += 1;
+                    valor.push(caracter);
+                }
+                _ => valor.push(caracter),
+            }
+            continue;
+        }
+        match caracter {
+            '"' if !con_comillas && valor.trim().is_empty() => {
+                valor.clear();
+                entre_comillas = true;
+                con_comillas = true;
+            }
+            c if c == delimitador => valores.push(terminar_valor(&mut valor, &mut con_comillas)),
+            '\r' if caracteres.peek() == Some(&'\n') => {}
+            '\n' => {
+                if !valores.is_empty() || con_comillas || !valor.trim().is_empty() {
+                    valores.push(terminar_valor(&mut valor, &mut con_comillas));
+                    registros.push((linea_inicio, Ok(std::mem::take(&mut valores))));
+                }
+                valor.clear();
+                linea += 1;
+                linea_inicio = linea;
+            }
+            _ => valor.push(caracter),
+        }
+    }
+
+    if entre_comillas {
+        let descripcion = Mensaje::ImportacionComillasSinCerrar.texto(&[]);
+        registros.push((linea_inicio, Err(descripcion)));
+    } else if !valores.is_empty() || con_comillas || !valor.trim().is_empty() {
+        valores.push(terminar_valor(&mut valor, &mut con_comillas));
+        registros.push((linea_inicio, Ok(valores)));
+    }
+    registros
+}
+
+/*
+ * Las tablas no declaran tipos, así que una columna se toma como entera si tiene algún valor
+ * y todos sus valores no vacíos son enteros.
+ */
+fn columnas_enteras(ruta: &String) -> Result<Vec<bool>, ErrorPrograma> {
+    let (lineas, columnas_csv) = abrir_archivo(ruta)?;
+    let mut enteras = vec![true; columnas_csv.len()];
+    let mut con_valores = vec![false; columnas_csv.len()];
+    for linea in lineas {
+        let linea = linea?;
+        for (indice, valor) in linea.split(',').take(columnas_csv.len()).enumerate() {
+            let valor = valor.trim();
+            if !valor.is_empty() {
+                con_valores[indice] = true;
+                enteras[indice] = enteras[indice] && valor.parse::<i64>().is_ok();
+            }
+        }
+    }
+    Ok(enteras
+        .into_iter()
+        .zip(con_valores)
+        .map(|(entera, con_valor)| entera && con_valor)
+        .collect())
+}
+
+/*
+ * Arma la fila de la tabla a partir de un registro del archivo importado, verificando la cantidad de valores,
+ * que se puedan guardar en el csv de la tabla, que las columnas enteras reciban enteros y el auto increment.
+ */
+fn armar_fila_importada(
+    valores: Vec<String>,
+    columnas_copy: &[String],
+    columnas_csv: &[String],
+    esquema: &Esquema,
+    enteras: &[bool],
+) -> Result<Vec<String>, String> {
+    if valores.len() != columnas_copy.len() {
+        return Err(Mensaje::ImportacionCantidadValores
+            .texto(&[&valores.len().to_string(), &columnas_copy.len().to_string()]));
+    }
+    if let Some(valor) = valores.iter().find(|v| v.contains([',', '\n', '\r'])) {
+        return Err(Mensaje::ImportacionValorNoGuardable.texto(&[valor]));
+    }
+
+    let valores: Vec<Option<String>> = valores.into_iter().map(Some).collect();
+    let fila = armar_fila(&valores, columnas_csv, columnas_copy, esquema);
+    for ((columna, valor), entera) in columnas_csv.iter().zip(fila.iter()).zip(enteras) {
+        if *entera && !valor.is_empty() && valor.parse::<i64>().is_err() {
+            return Err(Mensaje::ImportacionNoEntero.texto(&[valor, columna]));
+        }
+    }
+    if let Some(ref auto_increment) = esquema.auto_increment {
+        if let Some(indice) = columnas_csv
+            .iter()
+            .position(|c| *c == auto_increment.columna)
+        {
+            if !fila[indice].is_empty() && fila[indice].parse::<u64>().is_err() {
+                return Err(Mensaje::ImportacionAutoIncrement
+                    .texto(&[&fila[indice], &auto_increment.columna]));
+            }
+        }
+    }
+    Ok(fila)
+}
 
-    for clause in valores_a_insertar.iter() {
-        escribir_linea(clause, &mut file, &columnas_csv, &columnas_insert)?;
+fn error_importacion(archivo: &str, mut errores: Vec<(usize, String)>) -> ErrorPrograma {
+    errores.sort_by_key(|(linea, _)| *linea);
+    let mut detalle = String::new();
+    for (linea, error) in errores.iter().take(MAXIMO_ERRORES_IMPORTACION) {
+        let linea = Mensaje::ImportacionLinea.texto(&[&linea.to_string(), error]);
+        detalle.push_str(&format!("\n  {}", linea));
+    }
+    if errores.len() > MAXIMO_ERRORES_IMPORTACION {
+        let restantes = (errores.len() - MAXIMO_ERRORES_IMPORTACION).to_string();
+        detalle.push_str(&format!(
+            "\n  {}",
+            Mensaje::ImportacionMasFilas.texto(&[&restantes])
+        ));
     }
+    let linea = errores.first().map(|(linea, _)| *linea);
+    ErrorPrograma::desde_mensaje(
+        ErrorTipo::Csv {
+            archivo: archivo.to_string(),
+            linea,
+        },
+        Mensaje::ImportacionFallida,
+        &[archivo, &errores.len().to_string(), &detalle],
+    )
+}
+
+/*
+ * Se agregan al final de la tabla las filas del archivo indicado en COPY ... FROM. Con HEADER las columnas
+ * del archivo se mapean por nombre, y las columnas de la tabla que no están toman su valor por defecto.
+ * Cada fila se valida como en un INSERT; si alguna tiene errores no se agrega ninguna y se informan todas con
+ * su número de línea en el archivo. Si no, se agregan juntas en una única escritura registrada en la bitácora.
+ */
+pub fn aplicar_copy_from(ruta: &String, copy: &CopyFrom) -> Result<(), ErrorPrograma> {
+    let _bloqueo = bloquear_tabla(ruta, ModoBloqueo::Exclusivo)?;
+    let (_, columnas_csv) = abrir_archivo(ruta)?;
+    let esquema = Esquema::cargar(ruta)?;
+    let texto = read_to_string(&copy.archivo)?;
+    let mut registros = separar_registros_csv(&texto, copy.delimitador).into_iter();
+
+    let columnas_copy = match (copy.encabezado, &copy.columnas) {
+        (true, Some(_)) => {
+            return Err(ErrorPrograma::desde_mensaje(
+                ErrorTipo::Syntax,
+                Mensaje::CopyHeaderConColumnas,
+                &[],
+            ))
+        }
+        (true, None) => match registros.next() {
+            Some((_, Ok(encabezado))) => encabezado,
+            Some((linea, Err(error))) => {
+                return Err(error_importacion(&copy.archivo, vec![(linea, error)]))
+            }
+            None => {
+                return Err(ErrorPrograma::desde_mensaje(
+                    ErrorTipo::Csv {
+                        archivo: copy.archivo.to_string(),
+                        linea: Some(1),
+                    },
+                    Mensaje::ImportacionSinEncabezado,
+                    &[&copy.archivo],
+                ));
+            }
+        },
+        (false, Some(columnas)) => columnas.clone(),
+        (false, None) => columnas_csv.clone(),
+    };
+    verificar_existen_columnas_query(&columnas_copy, &columnas_csv)?;
+    verificar_columnas_sin_repetir(&columnas_copy, Mensaje::ColumnaRepetidaCopy)?;
+
+    let enteras = columnas_enteras(ruta)?;
+    let mut errores = Vec::new();
+    let mut filas = Vec::new();
+    let mut lineas_filas = Vec::new();
+    for (linea, registro) in registros {
+        let fila = registro.and_then(|valores| {
+            armar_fila_importada(valores, &columnas_copy, &columnas_csv, &esquema, &enteras)
+        });
+        match fila {
+            Ok(fila) => {
+                filas.push(fila);
+                lineas_filas.push(linea);
+            }
+            Err(error) => errores.push((linea, error)),
+        }
+    }
+    for (posicion, error) in filas_hijas_invalidas(ruta, &columnas_csv, &filas)? {
+        errores.push((lineas_filas[posicion], error.descripcion().to_string()));
+    }
+    if !errores.is_empty() {
+        return Err(error_importacion(&copy.archivo, errores));
+    }
+
+    let mut bitacora = Bitacora::iniciar(&separar_ruta_tabla(ruta).0)?;
+    escribir_filas_insert(ruta, &columnas_csv, &mut filas, &mut bitacora)?;
+    confirmar_con_indices(bitacora)?;
     Ok(())
 }
+
+fn verificar_columnas_returning(
+    returning: &Option<Vec<String>>,
+    columnas_csv: &[String],
+) -> Result<(), ErrorPrograma> {
+    match returning {
+        Some(columnas) if columnas[0] != "*" => {
+            verificar_existen_columnas_query(columnas, columnas_csv)
+        }
+        _ => Ok(()),
+    }
+}
+
+/*
+ * Si la consulta de escritura tiene RETURNING, se entregan al destino las columnas pedidas de las filas
+ * afectadas con el mismo formato que el resultado de un SELECT.
+ */
+pub fn escribir_returning(
+    returning: &Option<Vec<String>>,
+    columnas_csv: &[String],
+    filas: Vec<Vec<String>>,
+    destino: &mut dyn DestinoFilas,
+) -> Result<(), ErrorPrograma> {
+    let columnas = match returning {
+        Some(columnas) => columnas,
+        None => return Ok(()),
+    };
+    if columnas[0] == "*" {
+        destino.encabezado(columnas_csv)?;
+    } else {
+        verificar_existen_columnas_query(columnas, columnas_csv)?;
+        destino.encabezado(columnas)?;
+    }
+
+    for fila in filas {
+        if columnas[0] == "*" {
+            destino.fila(fila)?;
+        } else {
+            destino.fila(
+                columnas
+                    .iter()
+                    .map(|col| {
+                        let indice = columnas_csv.iter().position(|c| c == col).unwrap_or(0);
+                        fila[indice].clone()
+                    })
+                    .collect(),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/*
+ * Se escribe la tabla completa con las filas recibidas en un archivo temporal al lado de la tabla,
+ * que se registra en la bitácora para reemplazar al archivo original cuando se confirme la operación.
+ */
+pub(crate) fn reemplazar_tabla(
+    ruta: &str,
+    columnas_csv: &[String],
+    filas: &[Vec<String>],
+    bitacora: &mut Bitacora,
+) -> Result<(), ErrorPrograma> {
+    let mut temporal_writer = ArchivoTemporal::crear_junto_a(ruta)?;
+    writeln!(temporal_writer, "{}", columnas_csv.join(","))?;
+    for fila in filas.iter() {
+        writeln!(temporal_writer, "{}", fila.join(","))?;
+    }
+    Ok(bitacora.registrar_reemplazo(temporal_writer, ruta)?)
+}
+
+fn verificar_foreign_key_nueva(
+    ruta: &String,
+    columna: &str,
+    ruta_padre: &String,
+    columna_padre: &str,
+) -> Result<(), ErrorPrograma> {
+    let (_, columnas_csv) = abrir_archivo(ruta)?;
+    verificar_existen_columnas_query(&[columna.to_string()], &columnas_csv)?;
+
+    let mut valores_padre = HashSet::new();
+    for valor in valores_columna(ruta_padre, columna_padre)? {
+        if !valor.is_empty() && !valores_padre.insert(valor.clone()) {
+            return Err(ErrorPrograma::desde_mensaje(
+                ErrorTipo::ConstraintViolation {
+                    tabla: separar_ruta_tabla(ruta_padre).1,
+                    columna: Some(columna_padre.to_string()),
+                    valor: Some(valor.clone()),
+                },
+                Mensaje::ValorReferenciadoRepetido,
+                &[columna_padre, &valor],
+            ));
+        }
+    }
+
+    for valor in valores_columna(ruta, columna)? {
+        if !valor.is_empty() && !valores_padre.contains(&valor) {
+            return Err(ErrorPrograma::desde_mensaje(
+                ErrorTipo::ConstraintViolation {
+                    tabla: separar_ruta_tabla(ruta).1,
+                    columna: Some(columna.to_string()),
+                    valor: Some(valor.clone()),
+                },
+                Mensaje::ForeignKeyNuevaInvalida,
+                &[&valor, columna, columna_padre],
+            ));
+        }
+    }
+    Ok(())
+}
+
+/*
+ * Se recibe la ruta del archivo y la estructura de la consulta ALTER TABLE.
+ * Se verifica que la restricción pedida se cumpla con los datos actuales de la tabla
+ * y luego se agrega al esquema de la tabla. Para una columna auto increment el contador
+ * arranca en el siguiente al mayor valor actual de la columna.
+*/
+pub fn aplicar_alter_table(ruta: &String, alter: &AlterTable) -> Result<(), ErrorPrograma> {
+    let _bloqueo = bloquear_tabla(ruta, ModoBloqueo::Exclusivo)?;
+    let mut esquema = Esquema::cargar(ruta)?;
+    match alter.accion {
+        AccionAlterTable::AgregarForeignKey(ref fk) => {
+            let (directorio, tabla) = separar_ruta_tabla(ruta);
+            if fk.tabla_referenciada == tabla {
+                return Err(ErrorPrograma::desde_mensaje(
+                    ErrorTipo::ConstraintViolation {
+                        tabla,
+                        columna: Some(fk.columna.to_string()),
+                        valor: None,
+                    },
+                    Mensaje::ForeignKeyPropia,
+                    &[],
+                ));
+            }
+            let ruta_padre = format!("{}/{}.csv", directorio, fk.tabla_referenciada);
+            verificar_foreign_key_nueva(ruta, &fk.columna, &ruta_padre, &fk.columna_referenciada)?;
+            esquema
+                .foreign_keys
+                .retain(|actual| actual.columna != fk.columna);
+            esquema.foreign_keys.push(fk.clone());
+        }
+        AccionAlterTable::AgregarAutoIncrement(ref columna) => {
+            let mut siguiente = 1;
+            for valor in valores_columna(ruta, columna)? {
+                if !valor.is_empty() {
+                    siguiente = siguiente.max(parsear_auto_increment(columna, &valor)? + 1);
+                }
+            }
+            esquema.auto_increment = Some(AutoIncrement {
+                columna: columna.to_string(),
+                siguiente,
+            });
+        }
+        AccionAlterTable::AgregarDefault(ref columna, ref valor) => {
+            let (_, columnas_csv) = abrir_archivo(ruta)?;
+            verificar_existen_columnas_query(&[columna.to_string()], &columnas_csv)?;
+            esquema.defaults.retain(|(col, _)| col != columna);
+            esquema
+                .defaults
+                .push((columna.to_string(), valor.to_string()));
+        }
+        AccionAlterTable::EliminarDefault(ref columna) => {
+            esquema.defaults.retain(|(col, _)| col != columna);
+        }
+    }
+    Ok(esquema.guardar(ruta)?)
+}
@@ -0,0 +1,261 @@
+use crate::errors::ErrorPrograma;
+use crate::plan::FilasTabla;
+use std::fs::{metadata, File};
+use std::io::{self, BufRead, BufReader, ErrorKind, Read};
+use std::panic::resume_unwind;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::thread;
+
+// Bytes de la tabla que lee cada hilo por vez. Una tabla más chica se recorre en un solo hilo.
+pub const BYTES_POR_HILO_POR_DEFECTO: u64 = 8 * 1024 * 1024;
+
+// Cantidad de hilos para recorrer una tabla, 0 usa los que tenga el equipo.
+static HILOS_RECORRIDO: AtomicUsize = AtomicUsize::new(0);
+static BYTES_POR_HILO: AtomicU64 = AtomicU64::new(BYTES_POR_HILO_POR_DEFECTO);
+
+/*
+ * Cambia la cantidad máxima de hilos con los que se recorre una tabla (0 usa los que tenga el equipo y 1 la
+ * recorre en un solo hilo) y cuántos bytes lee cada hilo por vez.
+ */
+pub fn configurar_recorrido_paralelo(hilos: usize, bytes_por_hilo: u64) {
+    HILOS_RECORRIDO.store(hilos, Ordering::Relaxed);
+    BYTES_POR_HILO.store(bytes_por_hilo.max(1), Ordering::Relaxed);
+}
+
+/*
+ * Devuelve con cuántos hilos conviene recorrer la tabla: uno por cada parte de la tabla que llena lo que lee
+ * un hilo por vez, sin pasar del máximo configurado.
+ */
+pub(crate) fn hilos_para_recorrer(ruta_tabla: &str) -> usize {
+    let hilos = match HILOS_RECORRIDO.load(Ordering::Relaxed) {
+        0 => thread::available_parallelism().map_or(1, |hilos| hilos.get()),
+        hilos => hilos,
+    };
+    let longitud = metadata(ruta_tabla).map_or(0, |datos| datos.len());
+    let partes = longitud / BYTES_POR_HILO.load(Ordering::Relaxed);
+    hilos.min(partes as usize).max(1)
+}
+
+/*
+ * Busca dónde cortar los datos en partes de tamaño parecido para repartirlas entre los hilos. Solo se corta
+ * después de un salto de línea que no está dentro de un valor entre comillas dobles, así ningún registro queda
+ * partido. Devuelve el final de cada parte; lo que queda después del último corte se lee con la próxima tanda.
+ * Si no es el final del archivo y no hay un salto de línea fuera de comillas se corta en el último salto de
+ * línea, para que unas comillas sin cerrar no obliguen a leer el resto del archivo de una vez.
+ */
+fn buscar_cortes(datos: &[u8], partes: usize, fin_archivo: bool) -> Vec<usize> {
+    let paso = (datos.len() / partes.max(1)).max(1);
+    let mut cortes = Vec::new();
+    let mut ultimo_registro = None;
+    let mut ultimo_salto = None;
+    let mut entre_comillas = false;
+    let mut inicio_valor = true;
+    let mut posicion = 0;
+    while posicion < datos.len() {
+        let byte = datos[posicion];
+        if entre_comillas {
+            if byte == b'"' {
+                if datos.get(posicion + 1) == Some(&b'"') {
+                    posicion += 1;
+                } else {
+                    entre_comillas = false;
+                    inicio_valor = false;
+                }
+            } else if byte == b'\n' {
+                ultimo_salto = Some(posicion + 1);
+            }
+            posicion += 1;
+            continue;
+        }
+        match byte {
+            b'"' if inicio_valor => entre_comillas = true,
+            b'\n' => {
+                ultimo_salto = Some(posicion + 1);
+                ultimo_registro = Some(posicion + 1);
+                if posicion + 1 >= paso * (cortes.len() + 1) && cortes.len() + 1 < partes {
+                    cortes.push(posicion + 1);
+                }
+            }
+            _ => {}
+        }
+        inicio_valor = match byte {
+            b',' | b'\n' => true,
+            b' ' | b'\t' | b'\r' => inicio_valor,
+            _ => false,
+        };
+        posicion += 1;
+    }
+
+    let fin = if fin_archivo {
+        datos.len()
+    } else {
+        ultimo_registro.or(ultimo_salto).unwrap_or(0)
+    };
+    cortes.retain(|corte| *corte < fin);
+    if fin > 0 {
+        cortes.push(fin);
+    }
+    cortes
+}
+
+/*
+ * Procesa las líneas de una parte de la tabla igual que al recorrerla línea por línea. Devuelve las filas
+ * que resultaron hasta la primera línea con error y ese error.
+ */
+fn procesar_parte<F>(parte: &[u8], procesar: &F) -> (Vec<Vec<String>>, Option<ErrorPrograma>)
+where
+    F: Fn(&str) -> Result<Option<Vec<String>>, ErrorPrograma>,
+{
+    let (texto, error_utf8) = match std::str::from_utf8(parte) {
+        Ok(texto) => (texto, None),
+        Err(error) => {
+            // Las líneas anteriores a la inválida se procesan, como al leer el archivo línea por línea.
+            let valido = &parte[..error.valid_up_to()];
+            let hasta = valido
+                .iter()
+                .rposition(|byte| *byte == b'\n')
+                .map_or(0, |p| p + 1);
+            let texto = std::str::from_utf8(&parte[..hasta]).unwrap_or_default();
+            let error =
+                io::Error::new(ErrorKind::InvalidData, "stream did not contain valid UTF-8");
+            (texto, Some(ErrorPrograma::from(error)))
+        }
+    };
+    let mut filas = Vec::new();
+    for linea in texto.split_inclusive('\n') {
+        let linea = linea.strip_suffix('\n').unwrap_or(linea);
+        let linea = linea.strip_suffix('\r').unwrap_or(linea);
+        match procesar(linea) {
+            Ok(Some(fila)) => filas.push(fila),
+            Ok(None) => {}
+            Err(error) => return (filas, Some(error)),
+        }
+    }
+    (filas, error_utf8)
+}
+
+/*
+ * Recorrido de una tabla por tandas: cada tanda lee del archivo lo que leen todos los hilos por vez, la corta
+ * en una parte por hilo y procesa las partes en paralelo. Las filas se devuelven en el orden del archivo.
+ */
+struct RecorridoParalelo<F> {
+    lector: BufReader<File>,
+    procesar: F,
+    hilos: usize,
+    pendiente: Vec<u8>,
+    fin_archivo: bool,
+    filas: std::vec::IntoIter<Vec<String>>,
+    error: Option<ErrorPrograma>,
+    terminado: bool,
+}
+
+impl<F> RecorridoParalelo<F>
+where
+    F: Fn(&str) -> Result<Option<Vec<String>>, ErrorPrograma> + Sync,
+{
+    fn leer_tanda(&mut self) -> Result<(), ErrorPrograma> {
+        let mut tamanio = self.hilos as u64 * BYTES_POR_HILO.load(Ordering::Relaxed);
+        let cortes = loop {
+            let faltan = tamanio.saturating_sub(self.pendiente.len() as u64);
+            let leidos = (&mut self.lector)
+                .take(faltan)
+                .read_to_end(&mut self.pendiente)?;
+            if leidos == 0 {
+                self.fin_archivo = true;
+            }
+            let cortes = buscar_cortes(&self.pendiente, self.hilos, self.fin_archivo);
+            if !cortes.is_empty() || self.fin_archivo {
+                break cortes;
+            }
+            // Una línea más larga que la tanda: se sigue leyendo hasta encontrar su final.
+            tamanio *= 2;
+        };
+
+        let procesar = &self.procesar;
+        let pendiente = &self.pendiente;
+        let resultados = thread::scope(|alcance| {
+            let mut inicio = 0;
+            let hilos: Vec<_> = cortes
+                .iter()
+                .map(|fin| {
+                    let parte = &pendiente[inicio..*fin];
+                    inicio = *fin;
+                    alcance.spawn(move || procesar_parte(parte, procesar))
+                })
+                .collect();
+            hilos
+                .into_iter()
+                .map(|hilo| hilo.join().unwrap_or_else(|panico| resume_unwind(panico)))
+                .collect::<Vec<_>>()
+        });
+
+        let mut filas = Vec::new();
+        for (filas_parte, error) in resultados {
+            filas.extend(filas_parte);
+            if error.is_some() {
+                self.error = error;
+                break;
+            }
+        }
+        self.filas = filas.into_iter();
+        let procesados = cortes.last().copied().unwrap_or(0);
+        self.pendiente.drain(..procesados);
+        if self.fin_archivo && self.pendiente.is_empty() {
+            self.terminado = true;
+        }
+        Ok(())
+    }
+}
+
+impl<F> Iterator for RecorridoParalelo<F>
+where
+    F: Fn(&str) -> Result<Option<Vec<String>>, ErrorPrograma> + Sync,
+{
+    type Item = Result<Vec<String>, ErrorPrograma>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(fila) = self.filas.next() {
+                return Some(Ok(fila));
+            }
+            if let Some(error) = self.error.take() {
+                self.terminado = true;
+                return Some(Err(error));
+            }
+            if self.terminado {
+                return None;
+            }
+            if let Err(error) = self.leer_tanda() {
+                self.terminado = true;
+                return Some(Err(error));
+            }
+        }
+    }
+}
+
+/*
+ * Recorre la tabla con varios hilos, salteando el encabezado, y devuelve lo que `procesar` obtiene de cada
+ * línea en el orden del archivo. `procesar` devuelve None para descartar la línea.
+ */
+pub(crate) fn recorrer_en_paralelo<'a, F>(
+    ruta_tabla: &str,
+    hilos: usize,
+    procesar: F,
+) -> Result<FilasTabla<'a>, ErrorPrograma>
+where
+    F: Fn(&str) -> Result<Option<Vec<String>>, ErrorPrograma> + Sync + 'a,
+{
+    let mut lector = BufReader::new(File::open(ruta_tabla)?);
+    let mut encabezado = Vec::new();
+    lector.read_until(b'\n', &mut encabezado)?;
+    Ok(Box::new(RecorridoParalelo {
+        lector,
+        procesar,
+        hilos,
+        pendiente: Vec::new(),
+        fin_archivo: false,
+        filas: Vec::new().into_iter(),
+        error: None,
+        terminado: false,
+    }))
+}
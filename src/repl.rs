@@ -0,0 +1,304 @@
+use crate::errors::{ErrorPrograma, ErrorTipo};
+use crate::esquema::{tablas_del_directorio, Esquema};
+use crate::mensajes::Mensaje;
+use crate::salida::{Formato, SalidaFormateada};
+use crate::sesion::{sentencias_completas, separar_sentencias, Sesion};
+use crate::sugerencias::sugerencia_mas_cercana;
+use std::error::Error;
+use std::fs::{read_to_string, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::time::Instant;
+
+const PROMPT: &str = "sql> ";
+const PROMPT_CONTINUACION: &str = "...> ";
+
+/*
+ * Intérprete interactivo sobre un directorio de tablas. Lee consultas que pueden ocupar varias líneas
+ * hasta un ';', las ejecuta en una misma sesión y muestra los errores sin terminar el programa.
+ * Las líneas que empiezan con '.' son comandos propios del intérprete.
+ */
+pub struct Repl<W: Write> {
+    sesion: Sesion,
+    salida: W,
+    formato: Formato,
+    timer: bool,
+    historial: Vec<String>,
+    ruta_historial: Option<PathBuf>,
+}
+
+impl<W: Write> Repl<W> {
+    pub fn new(ruta: &str, salida: W) -> Self {
+        Repl {
+            sesion: Sesion::new(ruta),
+            salida,
+            formato: Formato::Csv,
+            timer: false,
+            historial: Vec::new(),
+            ruta_historial: None,
+        }
+    }
+
+    pub fn con_formato(mut self, formato: Formato) -> Self {
+        self.formato = formato;
+        self
+    }
+
+    /*
+     * Guarda las consultas ejecutadas en el archivo recibido, y carga las que ya tenía de sesiones anteriores.
+     */
+    pub fn con_historial(mut self, ruta_historial: PathBuf) -> Self {
+        if let Ok(contenido) = read_to_string(&ruta_historial) {
+            self.historial = contenido.lines().map(|l| l.to_string()).collect();
+        }
+        self.ruta_historial = Some(ruta_historial);
+        self
+    }
+
+    /*
+     * Lee la entrada línea por línea hasta que termina o se pide salir.
+     */
+    pub fn ejecutar<R: BufRead>(&mut self, entrada: R) -> Result<(), Box<dyn Error>> {
+        let mut buffer = String::new();
+        write!(self.salida, "{}", PROMPT)?;
+        self.salida.flush()?;
+
+        for linea in entrada.lines() {
+            let linea = linea?;
+            if buffer.is_empty() && linea.trim().starts_with('.') {
+                match self.ejecutar_comando(linea.trim()) {
+                    Ok(true) => return Ok(()),
+                    Ok(false) => {}
+                    Err(e) => writeln!(self.salida, "{:#}", e)?,
+                }
+            } else if buffer.is_empty() && linea.trim().starts_with('!') {
+                self.repetir_historial(linea.trim())?;
+            } else if !linea.trim().is_empty() || !buffer.is_empty() {
+                buffer.push_str(&linea);
+                buffer.push('\n');
+                if sentencias_completas(&buffer) {
+                    self.ejecutar_texto(&buffer)?;
+                    buffer.clear();
+                }
+            }
+
+            let prompt = if buffer.is_empty() {
+                PROMPT
+            } else {
+                PROMPT_CONTINUACION
+            };
+            write!(self.salida, "{}", prompt)?;
+            self.salida.flush()?;
+        }
+
+        // Al terminar la entrada se ejecuta lo que haya quedado sin ';'.
+        if !buffer.trim().is_empty() {
+            self.ejecutar_texto(&buffer)?;
+        }
+        writeln!(self.salida)?;
+        Ok(())
+    }
+
+    fn ejecutar_texto(&mut self, texto: &str) -> Result<(), Box<dyn Error>> {
+        for sentencia in separar_sentencias(texto) {
+            self.agregar_al_historial(&linea_de_historial(&sentencia));
+            self.ejecutar_sentencia(&sentencia)?;
+        }
+        Ok(())
+    }
+
+    /*
+     * Ejecuta una consulta mostrando su resultado en el formato elegido. Un error en la consulta
+     * se muestra y no corta el intérprete; solo se devuelven los errores al escribir la salida.
+     */
+    fn ejecutar_sentencia(&mut self, sentencia: &str) -> Result<(), Box<dyn Error>> {
+        let inicio = Instant::now();
+        let resultado = {
+            let mut destino = SalidaFormateada::new(self.formato, &mut self.salida);
+            self.sesion.ejecutar_con_destino(sentencia, &mut destino)
+        };
+        if let Err(e) = resultado {
+            writeln!(self.salida, "{:#}", e)?;
+        }
+        if self.timer {
+            let milisegundos = inicio.elapsed().as_secs_f64() * 1000.0;
+            let milisegundos = format!("{:.3}", milisegundos);
+            writeln!(
+                self.salida,
+                "{}",
+                Mensaje::TiempoConsulta.texto(&[&milisegundos])
+            )?;
+        }
+        Ok(())
+    }
+
+    // Si no se puede escribir el archivo de historial la consulta igual queda en el historial de la sesión.
+    fn agregar_al_historial(&mut self, sentencia: &str) {
+        self.historial.push(sentencia.to_string());
+        if let Some(ref ruta) = self.ruta_historial {
+            if let Ok(mut archivo) = OpenOptions::new().create(true).append(true).open(ruta) {
+                let _ = writeln!(archivo, "{}", sentencia);
+            }
+        }
+    }
+
+    fn repetir_historial(&mut self, linea: &str) -> Result<(), Box<dyn Error>> {
+        let sentencia = linea[1..]
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .and_then(|numero| numero.checked_sub(1))
+            .and_then(|indice| self.historial.get(indice))
+            .cloned();
+        match sentencia {
+            Some(sentencia) => {
+                writeln!(self.salida, "{}", sentencia)?;
+                self.ejecutar_texto(&sentencia)
+            }
+            None => {
+                writeln!(
+                    self.salida,
+                    "{}",
+                    ErrorPrograma::desde_mensaje(
+                        ErrorTipo::Syntax,
+                        Mensaje::HistorialSinConsulta,
+                        &[]
+                    )
+                )?;
+                Ok(())
+            }
+        }
+    }
+
+    /*
+     * Ejecuta un comando del intérprete. Devuelve true si el comando pide salir.
+     */
+    fn ejecutar_comando(&mut self, linea: &str) -> Result<bool, Box<dyn Error>> {
+        let partes: Vec<&str> = linea.split_whitespace().collect();
+        match partes.as_slice() {
+            [".quit"] | [".exit"] => return Ok(true),
+            [".help"] => writeln!(self.salida, "{}", Mensaje::AyudaInterprete.texto(&[]))?,
+            [".tables"] => {
+                for tabla in self.tablas()? {
+                    writeln!(self.salida, "{}", tabla)?;
+                }
+            }
+            [".schema"] => {
+                for tabla in self.tablas()? {
+                    self.escribir_esquema(&tabla)?;
+                }
+            }
+            [".schema", tabla] => self.escribir_esquema(tabla)?,
+            [".mode", formato] => self.formato = Formato::desde_str(formato)?,
+            [".timer", "on"] => self.timer = true,
+            [".timer", "off"] => self.timer = false,
+            [".history"] => {
+                for (numero, sentencia) in self.historial.iter().enumerate() {
+                    writeln!(self.salida, "{:>4}  {}", numero + 1, sentencia)?;
+                }
+            }
+            _ => {
+                return Err(Box::new(ErrorPrograma::desde_mensaje(
+                    ErrorTipo::Syntax,
+                    Mensaje::ComandoDesconocido,
+                    &[linea],
+                )));
+            }
+        }
+        Ok(false)
+    }
+
+    fn tablas(&self) -> Result<Vec<String>, Box<dyn Error>> {
+        tablas_del_directorio(&self.sesion.ruta)
+    }
+
+    /*
+     * Muestra las columnas de la tabla y sus restricciones como las consultas ALTER TABLE que las declaran.
+     */
+    fn escribir_esquema(&mut self, tabla: &str) -> Result<(), Box<dyn Error>> {
+        let ruta_tabla = format!("{}/{}.csv", self.sesion.ruta, tabla);
+        let archivo = File::open(&ruta_tabla).map_err(|_| {
+            let tablas = self.tablas().unwrap_or_default();
+            let sugerencia = sugerencia_mas_cercana(tabla, tablas.iter().map(|t| t.as_str()));
+            let tabla = tabla.to_string();
+            Box::new(
+                ErrorPrograma::desde_mensaje(
+                    ErrorTipo::UnknownTable {
+                        tabla: tabla.clone(),
+                    },
+                    Mensaje::TablaInexistente,
+                    &[&tabla],
+                )
+                .con_sugerencia(sugerencia),
+            ) as Box<dyn Error>
+        })?;
+        let mut encabezado = String::new();
+        BufReader::new(archivo).read_line(&mut encabezado)?;
+        let columnas: Vec<&str> = encabezado.trim().split(',').map(|c| c.trim()).collect();
+        writeln!(self.salida, "{} ({})", tabla, columnas.join(", "))?;
+
+        let esquema = Esquema::cargar(&ruta_tabla)?;
+        for fk in esquema.foreign_keys.iter() {
+            writeln!(
+                self.salida,
+                "ALTER TABLE {} ADD FOREIGN KEY ({}) REFERENCES {} ({}) ON DELETE {} ON UPDATE {};",
+                tabla,
+                fk.columna,
+                fk.tabla_referenciada,
+                fk.columna_referenciada,
+                fk.on_delete.como_str(),
+                fk.on_update.como_str()
+            )?;
+        }
+        if let Some(ref auto_increment) = esquema.auto_increment {
+            writeln!(
+                self.salida,
+                "ALTER TABLE {} ALTER COLUMN {} SET AUTO_INCREMENT;",
+                tabla, auto_increment.columna
+            )?;
+        }
+        for (columna, valor) in esquema.defaults.iter() {
+            writeln!(
+                self.salida,
+                "ALTER TABLE {} ALTER COLUMN {} SET DEFAULT {};",
+                tabla, columna, valor
+            )?;
+        }
+        for indice in esquema.indices.iter() {
+            writeln!(
+                self.salida,
+                "CREATE INDEX {} ON {} USING {} ({});",
+                indice.nombre,
+                tabla,
+                indice.tipo.como_str(),
+                indice.columna
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/*
+ * Deja la consulta en una sola línea para el historial, que guarda una por línea: los espacios y saltos de línea
+ * seguidos quedan como un espacio, salvo dentro de los strings entre comillas simples, que no se cambian.
+ */
+fn linea_de_historial(sentencia: &str) -> String {
+    let mut linea = String::new();
+    let mut entre_comillas = false;
+    let mut espacio_pendiente = false;
+    for caracter in sentencia.chars() {
+        if !entre_comillas && caracter.is_whitespace() {
+            espacio_pendiente = true;
+            continue;
+        }
+        if espacio_pendiente && !linea.is_empty() {
+            linea.push(' ');
+        }
+        espacio_pendiente = false;
+        if caracter == '\'' {
+            entre_comillas = !entre_comillas;
+        }
+        linea.push(caracter);
+    }
+    linea
+}
@@ -0,0 +1,297 @@
+use crate::bitacora::Bitacora;
+use crate::bloqueo::{bloquear_tabla, ModoBloqueo};
+use crate::errors::{ErrorPrograma, ErrorTipo};
+use crate::esquema::{
+    foreign_keys_que_referencian, separar_ruta_tabla, AccionReferencial, Esquema,
+};
+use crate::mensajes::Mensaje;
+use crate::read_file::{abrir_archivo, reemplazar_tabla};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+
+// Límite de tablas encadenadas al propagar cambios, evita ciclos infinitos entre foreign keys.
+const PROFUNDIDAD_MAXIMA: usize = 32;
+
+// Fila anterior al cambio y fila nueva, None si la fila fue eliminada.
+pub type Cambio = (Vec<String>, Option<Vec<String>>);
+
+fn posicion_columna(columnas_csv: &[String], columna: &str) -> Result<usize, Box<dyn Error>> {
+    columnas_csv
+        .iter()
+        .position(|col| col == columna)
+        .ok_or_else(|| {
+            Box::new(ErrorPrograma::desde_mensaje(
+                ErrorTipo::UnknownColumn {
+                    columna: columna.to_string(),
+                },
+                Mensaje::ColumnaForeignKeyInexistente,
+                &[columna],
+            )) as Box<dyn Error>
+        })
+}
+
+/*
+ * Devuelve todos los valores que toma una columna de la tabla.
+ */
+pub fn valores_columna(ruta: &String, columna: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let _bloqueo = bloquear_tabla(ruta, ModoBloqueo::Compartido)?;
+    let (lineas, columnas_csv) = abrir_archivo(ruta)?;
+    let indice = posicion_columna(&columnas_csv, columna)?;
+    let mut valores = Vec::new();
+    for linea in lineas {
+        let linea = linea?;
+        let valor = linea.split(',').nth(indice).unwrap_or("").trim();
+        valores.push(valor.to_string());
+    }
+    Ok(valores)
+}
+
+/*
+ * Se verifica que cada fila que se va a escribir en la tabla cumpla con las foreign keys declaradas,
+ * es decir que el valor de la columna exista en la tabla referenciada. Un valor vacío se toma como NULL.
+ */
+pub fn verificar_filas_hijas(
+    ruta: &str,
+    columnas_csv: &[String],
+    filas: &[Vec<String>],
+) -> Result<(), Box<dyn Error>> {
+    match filas_hijas_invalidas(ruta, columnas_csv, filas)?
+        .into_iter()
+        .next()
+    {
+        Some((_, error)) => Err(Box::new(error)),
+        None => Ok(()),
+    }
+}
+
+/*
+ * Devuelve la posición de cada fila que no cumple alguna foreign key, con el error que le corresponde,
+ * ordenadas por posición. Se usa para informar todas las filas con error de una importación.
+ */
+pub fn filas_hijas_invalidas(
+    ruta: &str,
+    columnas_csv: &[String],
+    filas: &[Vec<String>],
+) -> Result<Vec<(usize, ErrorPrograma)>, Box<dyn Error>> {
+    let esquema = Esquema::cargar(ruta)?;
+    let (directorio, tabla) = separar_ruta_tabla(ruta);
+    let mut invalidas = Vec::new();
+
+    for fk in esquema.foreign_keys.iter() {
+        let indice = posicion_columna(columnas_csv, &fk.columna)?;
+        let ruta_padre = format!("{}/{}.csv", directorio, fk.tabla_referenciada);
+        let valores_padre: HashSet<String> =
+            valores_columna(&ruta_padre, &fk.columna_referenciada)?
+                .into_iter()
+                .collect();
+
+        for (posicion, fila) in filas.iter().enumerate() {
+            let valor = fila.get(indice).map(|s| s.as_str()).unwrap_or("");
+            if !valor.is_empty() && !valores_padre.contains(valor) {
+                let tipo = ErrorTipo::ConstraintViolation {
+                    tabla: tabla.to_string(),
+                    columna: Some(fk.columna.to_string()),
+                    valor: Some(valor.to_string()),
+                };
+                let datos = [
+                    valor,
+                    &tabla,
+                    &fk.columna,
+                    &fk.tabla_referenciada,
+                    &fk.columna_referenciada,
+                ];
+                let mensaje = Mensaje::ValorReferenciadoInexistente;
+                invalidas.push((
+                    posicion,
+                    ErrorPrograma::desde_mensaje(tipo, mensaje, &datos),
+                ));
+            }
+        }
+    }
+    invalidas.sort_by_key(|(posicion, _)| *posicion);
+    Ok(invalidas)
+}
+
+/*
+ * Valor de la columna en una fila de la tabla. Una fila con menos valores que columnas es un error del archivo:
+ * con el número de línea si se conoce, o con el nombre de la tabla si no.
+ */
+fn valor_de_fila<'f>(
+    fila: &'f [String],
+    indice: usize,
+    ruta: &str,
+    linea: Option<usize>,
+    columnas: usize,
+) -> Result<&'f String, Box<dyn Error>> {
+    fila.get(indice).ok_or_else(|| {
+        let tipo = ErrorTipo::Csv {
+            archivo: ruta.to_string(),
+            linea,
+        };
+        let error = match linea {
+            Some(linea) => ErrorPrograma::desde_mensaje(
+                tipo,
+                Mensaje::LineaConOtraCantidad,
+                &[
+                    &linea.to_string(),
+                    &fila.len().to_string(),
+                    &columnas.to_string(),
+                ],
+            ),
+            None => ErrorPrograma::desde_mensaje(
+                tipo,
+                Mensaje::TablasConFilasInvalidas,
+                &[&separar_ruta_tabla(ruta).1],
+            ),
+        };
+        Box::new(error) as Box<dyn Error>
+    })
+}
+
+fn propagar_en_tablas_hijas(
+    ruta: &str,
+    columnas_csv: &[String],
+    cambios: &[Cambio],
+    mut bitacora: Option<&mut Bitacora>,
+    profundidad: usize,
+) -> Result<(), Box<dyn Error>> {
+    if cambios.is_empty() {
+        return Ok(());
+    }
+    let (directorio, tabla) = separar_ruta_tabla(ruta);
+    if profundidad > PROFUNDIDAD_MAXIMA {
+        return Err(Box::new(ErrorPrograma::desde_mensaje(
+            ErrorTipo::ConstraintViolation {
+                tabla,
+                columna: None,
+                valor: None,
+            },
+            Mensaje::ForeignKeysEnCiclo,
+            &[],
+        )));
+    }
+
+    for (ruta_hija, fk) in foreign_keys_que_referencian(&directorio, &tabla)? {
+        let indice_padre = posicion_columna(columnas_csv, &fk.columna_referenciada)?;
+
+        // Valor referenciado anterior -> valor nuevo, None si la fila padre se eliminó.
+        let mut reemplazos: HashMap<String, Option<String>> = HashMap::new();
+        for (anterior, nueva) in cambios.iter() {
+            let valor_anterior =
+                valor_de_fila(anterior, indice_padre, ruta, None, columnas_csv.len())?;
+            if valor_anterior.is_empty() {
+                continue;
+            }
+            match nueva {
+                None => {
+                    reemplazos.insert(valor_anterior.to_string(), None);
+                }
+                Some(nueva) => {
+                    let valor_nuevo =
+                        valor_de_fila(nueva, indice_padre, ruta, None, columnas_csv.len())?;
+                    if valor_nuevo != valor_anterior {
+                        reemplazos.insert(valor_anterior.to_string(), Some(valor_nuevo.clone()));
+                    }
+                }
+            }
+        }
+        if reemplazos.is_empty() {
+            continue;
+        }
+
+        let bloqueo = bloquear_tabla(&ruta_hija, ModoBloqueo::Exclusivo)?;
+        let (lineas, columnas_hija) = abrir_archivo(&ruta_hija)?;
+        let indice_hija = posicion_columna(&columnas_hija, &fk.columna)?;
+        let mut filas_resultantes = Vec::new();
+        let mut cambios_hija = Vec::new();
+
+        // La primera línea del archivo es el encabezado.
+        for (numero, linea) in lineas.enumerate() {
+            let linea = linea?;
+            let fila: Vec<String> = linea.split(',').map(|s| s.trim().to_string()).collect();
+            let numero_linea = Some(numero + 2);
+            let valor = valor_de_fila(
+                &fila,
+                indice_hija,
+                &ruta_hija,
+                numero_linea,
+                columnas_hija.len(),
+            )?
+            .to_string();
+            let reemplazo = match reemplazos.get(&valor) {
+                Some(reemplazo) => reemplazo,
+                None => {
+                    filas_resultantes.push(fila);
+                    continue;
+                }
+            };
+            let accion = match reemplazo {
+                None => &fk.on_delete,
+                Some(_) => &fk.on_update,
+            };
+            match (accion, reemplazo) {
+                (AccionReferencial::Restrict, _) => {
+                    let tabla_hija = separar_ruta_tabla(&ruta_hija).1;
+                    return Err(Box::new(ErrorPrograma::desde_mensaje(
+                        ErrorTipo::ConstraintViolation {
+                            tabla: tabla.to_string(),
+                            columna: Some(fk.columna_referenciada.to_string()),
+                            valor: Some(valor.to_string()),
+                        },
+                        Mensaje::ValorReferenciado,
+                        &[
+                            &tabla,
+                            &fk.columna_referenciada,
+                            &valor,
+                            &tabla_hija,
+                            &fk.columna,
+                        ],
+                    )));
+                }
+                (AccionReferencial::Cascade, None) => cambios_hija.push((fila, None)),
+                (AccionReferencial::Cascade, Some(valor_nuevo)) => {
+                    let mut nueva = fila.clone();
+                    nueva[indice_hija] = valor_nuevo.to_string();
+                    filas_resultantes.push(nueva.clone());
+                    cambios_hija.push((fila, Some(nueva)));
+                }
+                (AccionReferencial::SetNull, _) => {
+                    let mut nueva = fila.clone();
+                    nueva[indice_hija] = String::new();
+                    filas_resultantes.push(nueva.clone());
+                    cambios_hija.push((fila, Some(nueva)));
+                }
+            }
+        }
+
+        propagar_en_tablas_hijas(
+            &ruta_hija,
+            &columnas_hija,
+            &cambios_hija,
+            bitacora.as_deref_mut(),
+            profundidad + 1,
+        )?;
+        // La tabla hija queda bloqueada hasta que la bitácora publique su nueva versión.
+        if let Some(ref mut bitacora) = bitacora {
+            reemplazar_tabla(&ruta_hija, &columnas_hija, &filas_resultantes, bitacora)?;
+            bitacora.retener_bloqueo(bloqueo);
+        }
+    }
+    Ok(())
+}
+
+/*
+ * Se recibe la ruta de la tabla padre y las filas que se van a modificar o eliminar en ella.
+ * Primero se recorren las tablas hijas sin modificarlas para verificar que ninguna RESTRICT lo impida,
+ * y luego se escriben las tablas hijas con las acciones CASCADE y SET NULL aplicadas. Las tablas hijas quedan
+ * registradas en la bitácora y se publican junto con la tabla padre al confirmarla.
+ */
+pub fn propagar_cambios_padre(
+    ruta: &str,
+    columnas_csv: &[String],
+    cambios: &[Cambio],
+    bitacora: &mut Bitacora,
+) -> Result<(), Box<dyn Error>> {
+    propagar_en_tablas_hijas(ruta, columnas_csv, cambios, None, 0)?;
+    propagar_en_tablas_hijas(ruta, columnas_csv, cambios, Some(bitacora), 0)
+}
@@ -0,0 +1,316 @@
+use crate::errors::{ErrorPrograma, ErrorTipo};
+use crate::mensajes::Mensaje;
+use std::error::Error;
+use std::io::{stdout, Write};
+
+/*
+ * Destino de las filas que produce una consulta. Primero se recibe el encabezado
+ * con los nombres de las columnas y luego cada fila a medida que se genera.
+ * Al terminar la consulta se llama a terminar, para los destinos que necesitan todas las filas antes de escribir.
+ */
+pub trait DestinoFilas {
+    fn encabezado(&mut self, columnas: &[String]) -> Result<(), Box<dyn Error>>;
+    fn fila(&mut self, valores: Vec<String>) -> Result<(), Box<dyn Error>>;
+    fn terminar(&mut self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
+
+/*
+ * Imprime las filas por salida estándar separando los valores con comas.
+ */
+pub struct SalidaEstandar;
+
+impl DestinoFilas for SalidaEstandar {
+    fn encabezado(&mut self, columnas: &[String]) -> Result<(), Box<dyn Error>> {
+        writeln!(stdout(), "{}", unir_csv(columnas, ','))?;
+        Ok(())
+    }
+
+    fn fila(&mut self, valores: Vec<String>) -> Result<(), Box<dyn Error>> {
+        writeln!(stdout(), "{}", unir_csv(&valores, ','))?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Formato {
+    Csv,
+    Tsv,
+    Tabla,
+    Json,
+    Ndjson,
+    Markdown,
+    Html,
+}
+
+impl Formato {
+    pub fn desde_str(formato: &str) -> Result<Self, Box<dyn Error>> {
+        match formato {
+            "csv" => Ok(Formato::Csv),
+            "tsv" => Ok(Formato::Tsv),
+            "table" => Ok(Formato::Tabla),
+            "json" => Ok(Formato::Json),
+            "ndjson" => Ok(Formato::Ndjson),
+            "markdown" => Ok(Formato::Markdown),
+            "html" => Ok(Formato::Html),
+            _ => Err(Box::new(ErrorPrograma::desde_mensaje(
+                ErrorTipo::Syntax,
+                Mensaje::FormatoInvalido,
+                &[formato],
+            ))),
+        }
+    }
+}
+
+/*
+ * Escribe las filas de una consulta en el formato elegido. El formato tabla necesita el ancho de todas
+ * las filas antes de escribir, así que las guarda hasta terminar; el resto escribe cada fila al recibirla.
+ * En CSV y TSV un valor vacío (NULL) queda vacío, en JSON es null y en el resto una celda vacía.
+ */
+pub struct SalidaFormateada<W: Write> {
+    formato: Formato,
+    escritor: W,
+    columnas: Vec<String>,
+    filas: Vec<Vec<String>>,
+    cantidad_filas: usize,
+    // Las consultas que no devuelven filas nunca llaman a encabezado y no se escribe nada al terminar.
+    con_encabezado: bool,
+}
+
+/*
+ * Agrega comillas al valor si tiene el separador, comillas o saltos de línea, duplicando las comillas internas.
+ */
+pub fn escapar_csv(valor: &str, separador: char) -> String {
+    if valor.contains([separador, '"', '\n', '\r']) {
+        format!("\"{}\"", valor.replace('"', "\"\""))
+    } else {
+        valor.to_string()
+    }
+}
+
+fn unir_csv(valores: &[String], separador: char) -> String {
+    valores
+        .iter()
+        .map(|v| escapar_csv(v, separador))
+        .collect::<Vec<String>>()
+        .join(&separador.to_string())
+}
+
+// TSV no tiene comillas, los caracteres especiales se escriben como secuencias de escape.
+fn escapar_tsv(valor: &str) -> String {
+    valor
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn escapar_markdown(valor: &str) -> String {
+    valor.replace('|', "\\|").replace('\n', "<br>")
+}
+
+fn escapar_html(valor: &str) -> String {
+    valor
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/*
+ * Cantidad de columnas de la terminal que ocupa el texto: las marcas que se combinan con la letra anterior
+ * (como el acento de una 'a' descompuesta) no ocupan lugar y los caracteres de Asia oriental y los emoji ocupan dos.
+ */
+pub fn ancho_visible(texto: &str) -> usize {
+    texto
+        .chars()
+        .map(|caracter| match caracter as u32 {
+            0x0300..=0x036F
+            | 0x1AB0..=0x1AFF
+            | 0x1DC0..=0x1DFF
+            | 0x200B..=0x200F
+            | 0x20D0..=0x20FF
+            | 0xFE00..=0xFE0F
+            | 0xFE20..=0xFE2F => 0,
+            0x1100..=0x115F
+            | 0x2E80..=0x303E
+            | 0x3041..=0xA4CF
+            | 0xAC00..=0xD7A3
+            | 0xF900..=0xFAFF
+            | 0xFE30..=0xFE4F
+            | 0xFF00..=0xFF60
+            | 0xFFE0..=0xFFE6
+            | 0x1F300..=0x1F64F
+            | 0x1F900..=0x1F9FF
+            | 0x20000..=0x3FFFD => 2,
+            _ => 1,
+        })
+        .sum()
+}
+
+fn objeto_json(columnas: &[String], valores: &[String]) -> String {
+    // Un valor vacío es NULL en la tabla.
+    let campos: Vec<String> = columnas
+        .iter()
+        .zip(valores.iter())
+        .map(|(columna, valor)| match valor.as_str() {
+            "" => format!("\"{}\": null", escapar_json(columna)),
+            _ => format!("\"{}\": \"{}\"", escapar_json(columna), escapar_json(valor)),
+        })
+        .collect();
+    format!("{{{}}}", campos.join(", "))
+}
+
+fn escapar_json(valor: &str) -> String {
+    let mut escapado = String::new();
+    for caracter in valor.chars() {
+        match caracter {
+            '"' => escapado.push_str("\\\""),
+            '\\' => escapado.push_str("\\\\"),
+            '\n' => escapado.push_str("\\n"),
+            '\r' => escapado.push_str("\\r"),
+            '\t' => escapado.push_str("\\t"),
+            c if (c as u32) < 0x20 => escapado.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escapado.push(c),
+        }
+    }
+    escapado
+}
+
+impl<W: Write> SalidaFormateada<W> {
+    pub fn new(formato: Formato, escritor: W) -> Self {
+        SalidaFormateada {
+            formato,
+            escritor,
+            columnas: Vec::new(),
+            filas: Vec::new(),
+            cantidad_filas: 0,
+            con_encabezado: false,
+        }
+    }
+
+    pub fn into_escritor(self) -> W {
+        self.escritor
+    }
+
+    fn escribir_tabla(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut anchos: Vec<usize> = self.columnas.iter().map(|c| ancho_visible(c)).collect();
+        for fila in self.filas.iter() {
+            for (ancho, valor) in anchos.iter_mut().zip(fila.iter()) {
+                *ancho = (*ancho).max(ancho_visible(valor));
+            }
+        }
+
+        let separador: Vec<String> = anchos.iter().map(|ancho| "-".repeat(ancho + 2)).collect();
+        let separador = format!("+{}+", separador.join("+"));
+        writeln!(self.escritor, "{}", separador)?;
+        escribir_fila_tabla(&mut self.escritor, &self.columnas, &anchos)?;
+        writeln!(self.escritor, "{}", separador)?;
+        for fila in self.filas.iter() {
+            escribir_fila_tabla(&mut self.escritor, fila, &anchos)?;
+        }
+        writeln!(self.escritor, "{}", separador)?;
+        Ok(())
+    }
+}
+
+fn escribir_fila_tabla<W: Write>(
+    escritor: &mut W,
+    valores: &[String],
+    anchos: &[usize],
+) -> Result<(), Box<dyn Error>> {
+    let celdas: Vec<String> = anchos
+        .iter()
+        .enumerate()
+        .map(|(i, ancho)| {
+            let valor = valores.get(i).map(|v| v.as_str()).unwrap_or("");
+            let relleno = ancho - ancho_visible(valor);
+            format!(" {}{} ", valor, " ".repeat(relleno))
+        })
+        .collect();
+    writeln!(escritor, "|{}|", celdas.join("|"))?;
+    Ok(())
+}
+
+impl<W: Write> DestinoFilas for SalidaFormateada<W> {
+    fn encabezado(&mut self, columnas: &[String]) -> Result<(), Box<dyn Error>> {
+        self.columnas = columnas.to_vec();
+        self.filas.clear();
+        self.cantidad_filas = 0;
+        self.con_encabezado = true;
+        match self.formato {
+            Formato::Csv => writeln!(self.escritor, "{}", unir_csv(columnas, ','))?,
+            Formato::Tsv => {
+                let columnas: Vec<String> = columnas.iter().map(|c| escapar_tsv(c)).collect();
+                writeln!(self.escritor, "{}", columnas.join("\t"))?
+            }
+            Formato::Json => write!(self.escritor, "[")?,
+            Formato::Markdown => {
+                let columnas: Vec<String> = columnas.iter().map(|c| escapar_markdown(c)).collect();
+                writeln!(self.escritor, "| {} |", columnas.join(" | "))?;
+                writeln!(self.escritor, "|{}", " --- |".repeat(columnas.len()))?;
+            }
+            Formato::Html => {
+                writeln!(self.escritor, "<table>\n  <thead>\n    <tr>")?;
+                for columna in columnas {
+                    writeln!(self.escritor, "      <th>{}</th>", escapar_html(columna))?;
+                }
+                writeln!(self.escritor, "    </tr>\n  </thead>\n  <tbody>")?;
+            }
+            Formato::Tabla | Formato::Ndjson => {}
+        }
+        Ok(())
+    }
+
+    fn fila(&mut self, valores: Vec<String>) -> Result<(), Box<dyn Error>> {
+        match self.formato {
+            Formato::Csv => writeln!(self.escritor, "{}", unir_csv(&valores, ','))?,
+            Formato::Tsv => {
+                let valores: Vec<String> = valores.iter().map(|v| escapar_tsv(v)).collect();
+                writeln!(self.escritor, "{}", valores.join("\t"))?
+            }
+            Formato::Json => {
+                let separador = if self.cantidad_filas == 0 { "" } else { "," };
+                let objeto = objeto_json(&self.columnas, &valores);
+                write!(self.escritor, "{}\n  {}", separador, objeto)?;
+            }
+            Formato::Ndjson => {
+                writeln!(self.escritor, "{}", objeto_json(&self.columnas, &valores))?
+            }
+            Formato::Markdown => {
+                let valores: Vec<String> = valores.iter().map(|v| escapar_markdown(v)).collect();
+                writeln!(self.escritor, "| {} |", valores.join(" | "))?;
+            }
+            Formato::Html => {
+                writeln!(self.escritor, "    <tr>")?;
+                for valor in valores.iter() {
+                    writeln!(self.escritor, "      <td>{}</td>", escapar_html(valor))?;
+                }
+                writeln!(self.escritor, "    </tr>")?;
+            }
+            Formato::Tabla => self.filas.push(valores),
+        }
+        self.cantidad_filas += 1;
+        Ok(())
+    }
+
+    fn terminar(&mut self) -> Result<(), Box<dyn Error>> {
+        if !self.con_encabezado {
+            return Ok(());
+        }
+        self.con_encabezado = false;
+        match self.formato {
+            Formato::Csv | Formato::Tsv | Formato::Ndjson | Formato::Markdown => {}
+            Formato::Html => writeln!(self.escritor, "  </tbody>\n</table>")?,
+            Formato::Json if self.cantidad_filas == 0 => writeln!(self.escritor, "]")?,
+            Formato::Json => writeln!(self.escritor, "\n]")?,
+            Formato::Tabla => {
+                self.escribir_tabla()?;
+                self.filas.clear();
+            }
+        }
+        self.escritor.flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,127 @@
+use crate::mensajes::Mensaje;
+use crate::salida::DestinoFilas;
+use crate::sesion::{separar_sentencias_con_lineas, Sesion};
+use std::error::Error;
+use std::fmt;
+use std::io::{sink, Write};
+use std::time::Instant;
+
+/*
+ * Consulta de un script que terminó con error, con la línea del script en la que empieza.
+ */
+#[derive(Debug)]
+pub struct ErrorScript {
+    pub linea: usize,
+    pub sentencia: String,
+    pub error: Box<dyn Error>,
+}
+
+/*
+ * Resumen de la ejecución de un script: cuántas consultas tenía, cuántas se ejecutaron bien
+ * y los errores encontrados. Si el script se detuvo en un error, las consultas siguientes no se cuentan como ejecutadas.
+ */
+#[derive(Debug, Default)]
+pub struct ResumenScript {
+    pub total: usize,
+    pub correctas: usize,
+    pub errores: Vec<ErrorScript>,
+}
+
+impl ResumenScript {
+    pub fn sin_errores(&self) -> bool {
+        self.errores.is_empty()
+    }
+}
+
+// Con el formato alternativo ({:#}) se muestra el diagnóstico del error, que ya incluye la consulta.
+impl fmt::Display for ErrorScript {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let linea = self.linea.to_string();
+        let texto = if f.alternate() {
+            let error = format!("{:#}", self.error);
+            Mensaje::ErrorEnScriptDetallado.texto(&[&linea, &error])
+        } else {
+            let error = self.error.to_string();
+            Mensaje::ErrorEnScript.texto(&[&linea, &self.sentencia, &error])
+        };
+        write!(f, "{}", texto)
+    }
+}
+
+impl fmt::Display for ResumenScript {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let no_ejecutadas = self.total - self.correctas - self.errores.len();
+        let cantidades = [
+            self.total,
+            self.correctas,
+            self.errores.len(),
+            no_ejecutadas,
+        ];
+        let cantidades = cantidades.map(|cantidad| cantidad.to_string());
+        let datos = cantidades.each_ref().map(|cantidad| cantidad.as_str());
+        write!(f, "{}", Mensaje::ResumenScript.texto(&datos))
+    }
+}
+
+/*
+ * Ejecuta en orden las consultas del script en la sesión, escribiendo en el destino las filas que devuelvan.
+ * Si una consulta falla se detiene, salvo que se pida continuar con las siguientes.
+ */
+pub fn ejecutar_script(
+    sesion: &mut Sesion,
+    script: &str,
+    continuar_con_error: bool,
+    destino: &mut dyn DestinoFilas,
+) -> ResumenScript {
+    ejecutar_script_con_registro(sesion, script, continuar_con_error, destino, &mut sink())
+}
+
+/*
+ * Igual que ejecutar_script, pero escribe en el registro cada consulta antes de ejecutarla
+ * y cuánto tardó o el error con el que terminó. Los errores al escribir el registro se ignoran.
+ */
+pub fn ejecutar_script_con_registro(
+    sesion: &mut Sesion,
+    script: &str,
+    continuar_con_error: bool,
+    destino: &mut dyn DestinoFilas,
+    registro: &mut dyn Write,
+) -> ResumenScript {
+    let sentencias = separar_sentencias_con_lineas(script);
+    let mut resumen = ResumenScript {
+        total: sentencias.len(),
+        ..Default::default()
+    };
+
+    for (linea, sentencia) in sentencias {
+        let numero = linea.to_string();
+        let _ = writeln!(
+            registro,
+            "{}",
+            Mensaje::RegistroConsulta.texto(&[&numero, &sentencia])
+        );
+        let inicio = Instant::now();
+        let resultado = sesion.ejecutar_con_destino(&sentencia, destino);
+        let milisegundos = format!("{:.3}", inicio.elapsed().as_secs_f64() * 1000.0);
+        match resultado {
+            Ok(()) => {
+                let texto = Mensaje::RegistroCorrecta.texto(&[&numero, &milisegundos]);
+                let _ = writeln!(registro, "{}", texto);
+                resumen.correctas += 1;
+            }
+            Err(error) => {
+                let texto = Mensaje::RegistroFallida.texto(&[&numero, &milisegundos]);
+                let _ = writeln!(registro, "{}", texto);
+                resumen.errores.push(ErrorScript {
+                    linea,
+                    sentencia,
+                    error,
+                });
+                if !continuar_con_error {
+                    break;
+                }
+            }
+        }
+    }
+    resumen
+}
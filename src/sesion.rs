@@ -0,0 +1,604 @@
+use crate::bitacora::Bitacora;
+use crate::bloqueo::{bloquear_tabla, ModoBloqueo};
+use crate::errors::{ErrorPrograma, ErrorTipo};
+use crate::esquema::{bloquear_esquema, separar_ruta_tabla, Esquema};
+use crate::indices::buscar_indice;
+use crate::mensajes::Mensaje;
+use crate::query_identifier::analisar_query_con_destino;
+use crate::salida::{DestinoFilas, SalidaEstandar};
+use std::error::Error;
+use std::fs::{
+    copy, create_dir, hard_link, metadata, read_dir, remove_dir_all, remove_file, rename, File,
+};
+use std::path::Path;
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static CONTADOR_TRANSACCIONES: AtomicU64 = AtomicU64::new(0);
+
+// Comienzo del nombre del directorio sombra de una transacción.
+const PREFIJO_SOMBRA: &str = ".transaccion.";
+
+/*
+ * Archivo del directorio de tablas al comenzar la transacción: su nombre, el identificador de su enlace en el
+ * directorio sombra y la huella del original, con la que al confirmar se sabe si otro proceso lo modificó.
+ */
+struct ArchivoInicial {
+    nombre: String,
+    identificador: Option<(u64, u64)>,
+    huella: Option<Huella>,
+}
+
+/*
+ * Transacción abierta en una sesión. Al comenzar se crea un directorio sombra dentro del directorio
+ * de tablas con un hard link a cada archivo, y todas las consultas de la transacción se ejecutan sobre él.
+ * Las escrituras reemplazan los archivos del directorio sombra, por lo que los originales no cambian hasta el COMMIT.
+ * Si otro proceso modificó alguno de los archivos que cambió la transacción, el COMMIT falla y la descarta.
+ */
+struct Transaccion {
+    directorio_sombra: String,
+    archivos_iniciales: Vec<ArchivoInicial>,
+    // Esquema de cada tabla al comenzar, para saber si otro proceso lo cambió.
+    esquemas_iniciales: Vec<(String, Esquema)>,
+    tablas_separadas: Vec<String>,
+    // Huella de cada copia propia de la transacción al separarla: si no cambió, no hay nada que publicar.
+    copias: Vec<(String, Option<Huella>)>,
+}
+
+/*
+ * Sesión de trabajo sobre un directorio de tablas. Ejecuta consultas de a una y mantiene el estado
+ * de la transacción abierta con BEGIN hasta el COMMIT o ROLLBACK.
+ */
+pub struct Sesion {
+    pub(crate) ruta: String,
+    transaccion: Option<Transaccion>,
+}
+
+fn es_archivo_de_datos(nombre: &str) -> bool {
+    !nombre.starts_with('.')
+}
+
+/*
+ * Devuelve el nombre de la tabla que modifica una consulta de escritura, o None si la consulta no escribe.
+ */
+fn tabla_modificada(query: &str) -> Option<String> {
+    let palabras: Vec<&str> = query.split_whitespace().collect();
+    let posicion = match palabras.as_slice() {
+        ["INSERT", "INTO", ..] | ["DELETE", "FROM", ..] | ["ALTER", "TABLE", ..] => 2,
+        ["UPDATE", ..] => 1,
+        ["COPY", tabla, ..] if !tabla.starts_with('(') => 1,
+        ["CREATE", "INDEX", _, "ON", ..] | ["DROP", "INDEX", _, "ON", ..] => 4,
+        _ => return None,
+    };
+    palabras
+        .get(posicion)
+        .map(|tabla| tabla.split('(').next().unwrap_or("").trim_end_matches(';'))
+        .map(|tabla| tabla.to_string())
+}
+
+/*
+ * DROP INDEX sin ON no nombra la tabla que modifica, se la busca entre los índices del directorio.
+ */
+fn tabla_del_indice_eliminado(
+    directorio: &str,
+    query: &str,
+) -> Result<Option<String>, Box<dyn Error>> {
+    let palabras: Vec<&str> = query.trim_end_matches(';').split_whitespace().collect();
+    let nombre = match palabras.as_slice() {
+        ["DROP", "INDEX", nombre] => nombre,
+        _ => return Ok(None),
+    };
+    Ok(buscar_indice(directorio, nombre)?.map(|(ruta_tabla, _)| separar_ruta_tabla(&ruta_tabla).1))
+}
+
+/*
+ * Identifica al archivo en disco, un archivo de la transacción que conserva el identificador que tenía
+ * al comenzar no fue modificado. Sin este dato todos los archivos se consideran modificados.
+ */
+#[cfg(unix)]
+fn identificador_archivo(ruta: &Path) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    metadata(ruta).ok().map(|m| (m.dev(), m.ino()))
+}
+
+#[cfg(not(unix))]
+fn identificador_archivo(_ruta: &Path) -> Option<(u64, u64)> {
+    None
+}
+
+// Identificador, tamaño y fecha de modificación de un archivo: cambia con cada escritura, en el lugar o por rename.
+type Huella = ((u64, u64), u64, Option<std::time::SystemTime>);
+
+fn huella_archivo(ruta: &Path) -> Option<Huella> {
+    let datos = metadata(ruta).ok()?;
+    let identificador = identificador_archivo(ruta)?;
+    Some((identificador, datos.len(), datos.modified().ok()))
+}
+
+/*
+ * Si la tabla está en el directorio sombra de una transacción devuelve la ruta de la misma tabla en el directorio
+ * de tablas, donde se reservan los valores de auto increment para que no se repitan con otras sesiones.
+ */
+pub(crate) fn tabla_fuera_de_transaccion(ruta_tabla: &str) -> Option<String> {
+    let ruta = Path::new(ruta_tabla);
+    let sombra = ruta.parent()?;
+    if !sombra
+        .file_name()?
+        .to_string_lossy()
+        .starts_with(PREFIJO_SOMBRA)
+    {
+        return None;
+    }
+    let original = sombra.parent()?.join(ruta.file_name()?);
+    Some(original.to_string_lossy().to_string())
+}
+
+fn error_conflicto(nombre: &str) -> Box<dyn Error> {
+    let tabla = separar_ruta_tabla(nombre).1;
+    Box::new(ErrorPrograma::desde_mensaje(
+        ErrorTipo::Lock {
+            tabla: tabla.clone(),
+        },
+        Mensaje::TransaccionEnConflicto,
+        &[&tabla],
+    ))
+}
+
+// Dos esquemas son iguales si lo único que cambió es el contador del auto increment.
+fn mismo_esquema(a: &Esquema, b: &Esquema) -> bool {
+    let sin_contador = |esquema: &Esquema| {
+        let mut esquema = esquema.clone();
+        if let Some(auto_increment) = esquema.auto_increment.as_mut() {
+            auto_increment.siguiente = 0;
+        }
+        esquema
+    };
+    sin_contador(a) == sin_contador(b)
+}
+
+impl Transaccion {
+    fn comenzar(ruta: &str) -> Result<Self, Box<dyn Error>> {
+        let directorio_sombra = format!(
+            "{}/{}{}.{}",
+            ruta,
+            PREFIJO_SOMBRA,
+            process::id(),
+            CONTADOR_TRANSACCIONES.fetch_add(1, Ordering::Relaxed)
+        );
+        create_dir(&directorio_sombra)?;
+
+        let mut archivos_iniciales = Vec::new();
+        let mut esquemas_iniciales = Vec::new();
+        for entrada in read_dir(ruta)? {
+            let entrada = entrada?;
+            let nombre = entrada.file_name().to_string_lossy().to_string();
+            if !entrada.file_type()?.is_file() || !es_archivo_de_datos(&nombre) {
+                continue;
+            }
+            let destino = format!("{}/{}", directorio_sombra, nombre);
+            if hard_link(entrada.path(), &destino).is_err() {
+                copy(entrada.path(), &destino)?;
+            }
+            if let Some(tabla) = nombre.strip_suffix(".esquema") {
+                let ruta_tabla = format!("{}/{}.csv", ruta, tabla);
+                esquemas_iniciales.push((nombre.clone(), Esquema::cargar(&ruta_tabla)?));
+            }
+            archivos_iniciales.push(ArchivoInicial {
+                identificador: identificador_archivo(Path::new(&destino)),
+                huella: huella_archivo(&entrada.path()),
+                nombre,
+            });
+        }
+
+        Ok(Transaccion {
+            directorio_sombra,
+            archivos_iniciales,
+            esquemas_iniciales,
+            tablas_separadas: Vec::new(),
+            copias: Vec::new(),
+        })
+    }
+
+    /*
+     * Las escrituras que agregan al final del csv o de los índices lo hacen sobre el mismo archivo, así que antes
+     * de escribir en una tabla se reemplazan sus hard links por copias propias de la transacción. El esquema no se
+     * copia porque siempre se reemplaza por otro archivo.
+     */
+    fn separar_tabla(&mut self, tabla: &str) -> Result<(), Box<dyn Error>> {
+        if self.tablas_separadas.iter().any(|t| t == tabla) {
+            return Ok(());
+        }
+        let prefijo = format!("{}.", tabla);
+        for entrada in read_dir(&self.directorio_sombra)? {
+            let nombre = entrada?.file_name().to_string_lossy().to_string();
+            if !nombre.starts_with(&prefijo) || nombre.ends_with(".esquema") {
+                continue;
+            }
+            let ruta_archivo = format!("{}/{}", self.directorio_sombra, nombre);
+            let ruta_copia = format!("{}/.{}.copia", self.directorio_sombra, nombre);
+            copy(&ruta_archivo, &ruta_copia)?;
+            rename(&ruta_copia, &ruta_archivo)?;
+            self.copias
+                .push((nombre, huella_archivo(Path::new(&ruta_archivo))));
+        }
+        self.tablas_separadas.push(tabla.to_string());
+        Ok(())
+    }
+
+    /*
+     * Publica en el directorio de tablas todos los archivos que cambiaron durante la transacción. Si otro proceso
+     * modificó alguno de ellos después del BEGIN la transacción se descarta con un error, así sus cambios no pisan
+     * los del otro. Los archivos modificados se registran en una bitácora y se renombran sobre los originales al
+     * confirmarla, así un corte en el medio del COMMIT se completa en la próxima consulta sobre el directorio.
+     */
+    fn confirmar(self, ruta: &str) -> Result<(), Box<dyn Error>> {
+        let mut cambiados = Vec::new();
+        for entrada in read_dir(&self.directorio_sombra)? {
+            let entrada = entrada?;
+            let nombre = entrada.file_name().to_string_lossy().to_string();
+            if !es_archivo_de_datos(&nombre) {
+                continue;
+            }
+            let identificador_inicial = self
+                .archivo_inicial(&nombre)
+                .and_then(|inicial| inicial.identificador);
+            let copia_sin_cambios = self.copias.iter().any(|(copia, huella)| {
+                *copia == nombre && huella.is_some() && *huella == huella_archivo(&entrada.path())
+            });
+            if (identificador_inicial.is_none()
+                || identificador_inicial != identificador_archivo(&entrada.path()))
+                && !copia_sin_cambios
+            {
+                cambiados.push(nombre);
+            }
+        }
+        let eliminados: Vec<&String> = self
+            .archivos_iniciales
+            .iter()
+            .map(|inicial| &inicial.nombre)
+            .filter(|nombre| {
+                !Path::new(&format!("{}/{}", self.directorio_sombra, nombre)).exists()
+                    && !cambiados.contains(nombre)
+            })
+            .collect();
+
+        // Cada archivo es el csv, el esquema o un índice de la tabla cuyo nombre está antes del primer punto.
+        let mut tablas: Vec<&str> = cambiados
+            .iter()
+            .chain(eliminados.iter().copied())
+            .map(|nombre| nombre.split('.').next().unwrap_or_default())
+            .collect();
+        tablas.sort();
+        tablas.dedup();
+
+        let mut bitacora = Bitacora::iniciar(ruta)?;
+        let mut bloqueos_esquemas = Vec::new();
+        for tabla in tablas {
+            let ruta_tabla = format!("{}/{}.csv", ruta, tabla);
+            bitacora.retener_bloqueo(bloquear_tabla(&ruta_tabla, ModoBloqueo::Exclusivo)?);
+            bloqueos_esquemas.push(bloquear_esquema(&ruta_tabla)?);
+            let publica_esquema = cambiados.contains(&format!("{}.esquema", tabla));
+            if let Err(error) = self.verificar_sin_conflicto(ruta, tabla, publica_esquema) {
+                drop(bitacora);
+                self.descartar()?;
+                return Err(error);
+            }
+        }
+        for nombre in cambiados.iter() {
+            let ruta_sombra = format!("{}/{}", self.directorio_sombra, nombre);
+            File::open(&ruta_sombra)?.sync_all()?;
+            bitacora
+                .registrar_renombre(Path::new(&ruta_sombra), &format!("{}/{}", ruta, nombre))?;
+        }
+        bitacora.confirmar()?;
+        drop(bloqueos_esquemas);
+        for nombre in eliminados {
+            let _ = remove_file(format!("{}/{}", ruta, nombre));
+        }
+        self.descartar()
+    }
+
+    fn archivo_inicial(&self, nombre: &str) -> Option<&ArchivoInicial> {
+        self.archivos_iniciales
+            .iter()
+            .find(|inicial| inicial.nombre == nombre)
+    }
+
+    /*
+     * Verifica que nadie más haya modificado la tabla desde el BEGIN: ni su csv ni sus índices, que la transacción
+     * reemplazaría por los suyos. Del esquema solo importa lo que declara, porque el contador del auto increment
+     * avanza con los inserts de todas las sesiones, incluida esta; si la transacción publica su esquema se le
+     * deja el mayor contador entre el del directorio y el propio.
+     */
+    fn verificar_sin_conflicto(
+        &self,
+        ruta: &str,
+        tabla: &str,
+        publica_esquema: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        let prefijo = format!("{}.", tabla);
+        let nombre_esquema = format!("{}.esquema", tabla);
+        let mut archivos: Vec<String> = self
+            .archivos_iniciales
+            .iter()
+            .map(|inicial| inicial.nombre.clone())
+            .filter(|nombre| nombre.starts_with(&prefijo))
+            .collect();
+        for entrada in read_dir(ruta)? {
+            let nombre = entrada?.file_name().to_string_lossy().to_string();
+            if nombre.starts_with(&prefijo) && !archivos.contains(&nombre) {
+                archivos.push(nombre);
+            }
+        }
+        for nombre in archivos.iter().filter(|nombre| **nombre != nombre_esquema) {
+            let inicial = self
+                .archivo_inicial(nombre)
+                .and_then(|inicial| inicial.huella);
+            if inicial != huella_archivo(Path::new(&format!("{}/{}", ruta, nombre))) {
+                return Err(error_conflicto(nombre));
+            }
+        }
+
+        let actual = Esquema::cargar(&format!("{}/{}.csv", ruta, tabla))?;
+        let inicial = self
+            .esquemas_iniciales
+            .iter()
+            .find(|(inicial, _)| *inicial == nombre_esquema)
+            .map(|(_, esquema)| esquema.clone())
+            .unwrap_or_default();
+        if !mismo_esquema(&actual, &inicial) {
+            return Err(error_conflicto(&nombre_esquema));
+        }
+        if publica_esquema {
+            let ruta_tabla_sombra = format!("{}/{}.csv", self.directorio_sombra, tabla);
+            let mut sombra = Esquema::cargar(&ruta_tabla_sombra)?;
+            if let (Some(propio), Some(actual)) =
+                (sombra.auto_increment.as_mut(), actual.auto_increment)
+            {
+                if propio.columna == actual.columna {
+                    propio.siguiente = propio.siguiente.max(actual.siguiente);
+                }
+            }
+            sombra.guardar(&ruta_tabla_sombra)?;
+        }
+        Ok(())
+    }
+
+    fn descartar(&self) -> Result<(), Box<dyn Error>> {
+        remove_dir_all(&self.directorio_sombra)?;
+        Ok(())
+    }
+}
+
+impl Sesion {
+    pub fn new(ruta: &str) -> Self {
+        Sesion {
+            ruta: ruta.to_string(),
+            transaccion: None,
+        }
+    }
+
+    /*
+     * Ejecuta una consulta en la sesión e imprime su resultado por salida estándar.
+     */
+    pub fn ejecutar(&mut self, query: &str) -> Result<(), Box<dyn Error>> {
+        self.ejecutar_con_destino(query, &mut SalidaEstandar)
+    }
+
+    /*
+     * Ejecuta una consulta en la sesión y escribe las filas que devuelve en el destino. BEGIN abre una transacción,
+     * COMMIT publica sus cambios y ROLLBACK los descarta; el resto de las consultas se ejecutan sobre la transacción
+     * si hay una abierta.
+     */
+    pub fn ejecutar_con_destino(
+        &mut self,
+        query: &str,
+        destino: &mut dyn DestinoFilas,
+    ) -> Result<(), Box<dyn Error>> {
+        let resultado = self.ejecutar_sentencia(query, destino);
+        destino.terminar()?;
+        resultado
+    }
+
+    fn ejecutar_sentencia(
+        &mut self,
+        query: &str,
+        destino: &mut dyn DestinoFilas,
+    ) -> Result<(), Box<dyn Error>> {
+        let query = query.trim();
+        let comando = query.trim_end_matches(';').trim();
+        match comando {
+            "BEGIN" | "BEGIN TRANSACTION" | "START TRANSACTION" => self.comenzar(),
+            "COMMIT" | "COMMIT TRANSACTION" | "END" => self.confirmar(),
+            "ROLLBACK" | "ROLLBACK TRANSACTION" => self.deshacer(),
+            _ => match self.transaccion {
+                Some(ref mut transaccion) => {
+                    if let Some(tabla) = tabla_modificada(query) {
+                        transaccion.separar_tabla(&tabla)?;
+                    } else if let Some(tabla) =
+                        tabla_del_indice_eliminado(&transaccion.directorio_sombra, query)?
+                    {
+                        transaccion.separar_tabla(&tabla)?;
+                    }
+                    Ok(analisar_query_con_destino(
+                        &transaccion.directorio_sombra,
+                        query,
+                        destino,
+                    )?)
+                }
+                None => Ok(analisar_query_con_destino(&self.ruta, query, destino)?),
+            },
+        }
+    }
+
+    fn comenzar(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.transaccion.is_some() {
+            return Err(Box::new(ErrorPrograma::desde_mensaje(
+                ErrorTipo::Syntax,
+                Mensaje::TransaccionAnidada,
+                &[],
+            )));
+        }
+        self.transaccion = Some(Transaccion::comenzar(&self.ruta)?);
+        Ok(())
+    }
+
+    fn confirmar(&mut self) -> Result<(), Box<dyn Error>> {
+        match self.transaccion.take() {
+            Some(transaccion) => transaccion.confirmar(&self.ruta),
+            None => Err(Box::new(ErrorPrograma::desde_mensaje(
+                ErrorTipo::Syntax,
+                Mensaje::CommitSinTransaccion,
+                &[],
+            ))),
+        }
+    }
+
+    fn deshacer(&mut self) -> Result<(), Box<dyn Error>> {
+        match self.transaccion.take() {
+            Some(transaccion) => transaccion.descartar(),
+            None => Err(Box::new(ErrorPrograma::desde_mensaje(
+                ErrorTipo::Syntax,
+                Mensaje::RollbackSinTransaccion,
+                &[],
+            ))),
+        }
+    }
+}
+
+impl Drop for Sesion {
+    // Una transacción que no se confirmó se descarta al terminar la sesión.
+    fn drop(&mut self) {
+        if let Some(transaccion) = self.transaccion.take() {
+            let _ = transaccion.descartar();
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EstadoLectura {
+    Normal,
+    String,
+    ComentarioLinea,
+    ComentarioBloque,
+}
+
+/*
+ * Resultado de recorrer un texto con consultas: las consultas completas junto a la línea en la que empieza
+ * cada una, lo que quedó después del último ';' y si el texto terminó dentro de un string o un comentario de bloque.
+ */
+struct TextoSeparado {
+    sentencias: Vec<(usize, String)>,
+    resto: (usize, String),
+    abierto: bool,
+}
+
+/*
+ * Recorre el texto separando las consultas por ';'. Los ';' dentro de strings entre comillas simples no cortan
+ * la consulta, y los comentarios de línea (--) y de bloque (/* */) se descartan.
+ */
+fn recorrer_sentencias(texto: &str) -> TextoSeparado {
+    let caracteres: Vec<char> = texto.chars().collect();
+    let mut sentencias = Vec::new();
+    let mut actual = String::new();
+    let mut linea_actual = 1;
+    let mut linea_inicio = 1;
+    let mut estado = EstadoLectura::Normal;
+    let mut i = 0;
+
+    while i < caracteres.len() {
+        let caracter = caracteres[i];
+        let siguiente = caracteres.get(i + 1).copied();
+        if caracter == '\n' {
+            linea_actual += 1;
+        }
+        match estado {
+            EstadoLectura::Normal => match (caracter, siguiente) {
+                ('-', Some('-')) => {
+                    estado = EstadoLectura::ComentarioLinea;
+                    i += 1;
+                }
+                ('/', Some('*')) => {
+                    estado = EstadoLectura::ComentarioBloque;
+                    actual.push(' ');
+                    i += 1;
+                }
+                (';', _) => {
+                    if !actual.trim().is_empty() {
+                        sentencias.push((linea_inicio, actual.trim().to_string()));
+                    }
+                    actual.clear();
+                }
+                _ => {
+                    if actual.trim().is_empty() && !caracter.is_whitespace() {
+                        linea_inicio = linea_actual;
+                    }
+                    if caracter == '\'' {
+                        estado = EstadoLectura::String;
+                    }
+                    actual.push(caracter);
+                }
+            },
+            EstadoLectura::String => {
+                if caracter == '\'' {
+                    estado = EstadoLectura::Normal;
+                }
+                actual.push(caracter);
+            }
+            EstadoLectura::ComentarioLinea => {
+                if caracter == '\n' {
+                    estado = EstadoLectura::Normal;
+                    actual.push('\n');
+                }
+            }
+            EstadoLectura::ComentarioBloque => {
+                if caracter == '*' && siguiente == Some('/') {
+                    estado = EstadoLectura::Normal;
+                    i += 1;
+                }
+            }
+        }
+        i += 1;
+    }
+
+    TextoSeparado {
+        sentencias,
+        resto: (linea_inicio, actual.trim().to_string()),
+        abierto: matches!(
+            estado,
+            EstadoLectura::String | EstadoLectura::ComentarioBloque
+        ),
+    }
+}
+
+/*
+ * Separa un texto con varias consultas en consultas individuales usando ';', sin cortar los strings
+ * entre comillas simples y descartando los comentarios. Devuelve cada consulta con la línea en la que empieza.
+ */
+pub fn separar_sentencias_con_lineas(texto: &str) -> Vec<(usize, String)> {
+    let mut separado = recorrer_sentencias(texto);
+    if !separado.resto.1.is_empty() {
+        separado.sentencias.push(separado.resto);
+    }
+    separado.sentencias
+}
+
+/*
+ * Separa un texto con varias consultas en consultas individuales usando ';',
+ * sin cortar los strings entre comillas simples y descartando los comentarios.
+ */
+pub fn separar_sentencias(texto: &str) -> Vec<String> {
+    separar_sentencias_con_lineas(texto)
+        .into_iter()
+        .map(|(_, sentencia)| sentencia)
+        .collect()
+}
+
+/*
+ * Indica si el texto tiene al menos una consulta y termina en un ';', sin contar los comentarios
+ * que haya después. Un texto que termina dentro de un string o de un comentario de bloque no está completo.
+ */
+pub fn sentencias_completas(texto: &str) -> bool {
+    let separado = recorrer_sentencias(texto);
+    !separado.abierto && separado.resto.1.is_empty() && !separado.sentencias.is_empty()
+}
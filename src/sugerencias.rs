@@ -0,0 +1,48 @@
+/*
+ * Distancia de edición entre dos palabras: la cantidad mínima de caracteres a insertar, borrar o reemplazar,
+ * o de pares de caracteres vecinos a intercambiar, para pasar de una a otra. Se comparan caracteres, no bytes,
+ * así las tildes cuentan como uno.
+ */
+pub fn distancia_edicion(palabra: &str, otra: &str) -> usize {
+    let palabra: Vec<char> = palabra.chars().collect();
+    let otra: Vec<char> = otra.chars().collect();
+    let mut anteanterior = vec![0; otra.len() + 1];
+    let mut anterior: Vec<usize> = (0..=otra.len()).collect();
+    let mut actual = vec![0; otra.len() + 1];
+
+    for i in 1..=palabra.len() {
+        actual[0] = i;
+        for j in 1..=otra.len() {
+            let reemplazo = anterior[j - 1] + usize::from(palabra[i - 1] != otra[j - 1]);
+            actual[j] = reemplazo.min(anterior[j] + 1).min(actual[j - 1] + 1);
+            if i > 1 && j > 1 && palabra[i - 1] == otra[j - 2] && palabra[i - 2] == otra[j - 1] {
+                actual[j] = actual[j].min(anteanterior[j - 2] + 1);
+            }
+        }
+        std::mem::swap(&mut anteanterior, &mut anterior);
+        std::mem::swap(&mut anterior, &mut actual);
+    }
+    anterior[otra.len()]
+}
+
+/*
+ * Devuelve el candidato más parecido a la palabra, sin distinguir mayúsculas, si está lo bastante cerca
+ * como para que sea un error de tipeo: a lo sumo una edición cada tres caracteres, y como mínimo una.
+ * Ante un empate se elige el primero de los candidatos.
+ */
+pub fn sugerencia_mas_cercana<'a, I>(palabra: &str, candidatos: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let palabra = palabra.to_lowercase();
+    let maxima = (palabra.chars().count() / 3).max(1);
+    let mut mejor: Option<(usize, &str)> = None;
+
+    for candidato in candidatos {
+        let distancia = distancia_edicion(&palabra, &candidato.to_lowercase());
+        if distancia <= maxima && mejor.is_none_or(|(menor, _)| distancia < menor) {
+            mejor = Some((distancia, candidato));
+        }
+    }
+    mejor.map(|(_, candidato)| candidato)
+}
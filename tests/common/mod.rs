@@ -0,0 +1,81 @@
+// Funciones compartidas por los tests. Cada archivo de tests usa solo algunas.
+#![allow(dead_code)]
+
+use std::fmt;
+use std::fs::{copy, create_dir_all, remove_dir_all};
+use std::ops::Deref;
+use std::path::Path;
+use taller_tp_individual::query_identifier::analisar_query_con_destino;
+use taller_tp_individual::salida::{Formato, SalidaFormateada};
+
+/*
+ * Directorio temporal de un test, en tests/<modulo>/datos_temp/<test>. Se elimina cuando termina el test,
+ * también si falla, y si quedó de una ejecución anterior se vacía antes de usarlo.
+ */
+pub struct DirectorioTemporal {
+    ruta: String,
+}
+
+impl DirectorioTemporal {
+    pub fn new(modulo: &str, nombre_test: &str) -> Self {
+        let ruta = format!("tests/{}/datos_temp/{}", modulo, nombre_test);
+        let _ = remove_dir_all(&ruta);
+        create_dir_all(&ruta).expect("No se pudo crear el directorio temporal");
+        DirectorioTemporal { ruta }
+    }
+
+    // Copia el archivo de tests/<modulo>/datos con otro nombre en el directorio.
+    pub fn copiar(&self, origen: &str, destino: &str) {
+        copy(origen, format!("{}/{}", self.ruta, destino)).expect("No se pudo copiar el archivo");
+    }
+}
+
+impl Drop for DirectorioTemporal {
+    fn drop(&mut self) {
+        let _ = remove_dir_all(&self.ruta);
+    }
+}
+
+impl Deref for DirectorioTemporal {
+    type Target = String;
+
+    fn deref(&self) -> &String {
+        &self.ruta
+    }
+}
+
+impl AsRef<Path> for DirectorioTemporal {
+    fn as_ref(&self) -> &Path {
+        Path::new(&self.ruta)
+    }
+}
+
+impl fmt::Display for DirectorioTemporal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.ruta)
+    }
+}
+
+/*
+ * Crea el directorio temporal del test con una copia de las tablas de tests/<modulo>/datos.
+ */
+pub fn preparar_directorio(modulo: &str, nombre_test: &str, tablas: &[&str]) -> DirectorioTemporal {
+    let directorio = DirectorioTemporal::new(modulo, nombre_test);
+    for tabla in tablas {
+        directorio.copiar(
+            &format!("tests/{}/datos/{}.csv", modulo, tabla),
+            &format!("{}.csv", tabla),
+        );
+    }
+    directorio
+}
+
+// Ejecuta la query y devuelve su resultado en csv, con el encabezado.
+pub fn consultar(ruta: &str, query: &str) -> String {
+    let mut salida = Vec::new();
+    {
+        let mut destino = SalidaFormateada::new(Formato::Csv, &mut salida);
+        analisar_query_con_destino(&ruta.to_string(), query, &mut destino).unwrap();
+    }
+    String::from_utf8(salida).unwrap()
+}
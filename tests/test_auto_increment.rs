@@ -0,0 +1,110 @@
+mod common;
+
+use common::DirectorioTemporal;
+use std::collections::HashSet;
+use std::fs::{read_dir, read_to_string};
+use std::thread;
+use taller_tp_individual::esquema::Esquema;
+use taller_tp_individual::query_identifier::analisar_query;
+
+fn preparar_directorio(nombre_test: &str) -> DirectorioTemporal {
+    common::preparar_directorio("test_auto_increment", nombre_test, &["ordenes"])
+}
+
+fn ids_ordenes(ruta: &str) -> Vec<String> {
+    read_to_string(format!("{}/ordenes.csv", ruta))
+        .unwrap()
+        .lines()
+        .skip(1)
+        .map(|linea| linea.split(',').next().unwrap_or("").to_string())
+        .collect()
+}
+
+// Test 34: Si no se ingresa el id, se asigna el siguiente al mayor de la tabla
+#[test]
+fn test_auto_increment_asigna_siguiente_id() {
+    let ruta = preparar_directorio("test34");
+    let alter = "ALTER TABLE ordenes ALTER COLUMN id SET AUTO_INCREMENT";
+    assert!(analisar_query(&ruta, alter).is_ok());
+
+    let query = "INSERT INTO ordenes (id_cliente, producto, cantidad) VALUES (6, 'Laptop', 3), (4, 'Cargador', 4);";
+    assert!(analisar_query(&ruta, query).is_ok());
+    let query =
+        "INSERT INTO ordenes (id, id_cliente, producto, cantidad) VALUES (200, 1, 'Mouse', 1);";
+    assert!(analisar_query(&ruta, query).is_ok());
+    let query = "INSERT INTO ordenes (id_cliente, producto, cantidad) VALUES (2, 'Monitor', 1);";
+    assert!(analisar_query(&ruta, query).is_ok());
+
+    let ids = ids_ordenes(&ruta);
+    assert_eq!(ids[ids.len() - 4..], ["110", "111", "200", "201"]);
+}
+
+// Test 35: Varios procesos insertando a la vez nunca reciben el mismo id
+#[test]
+fn test_auto_increment_inserts_concurrentes() {
+    let ruta = preparar_directorio("test35");
+    let alter = "ALTER TABLE ordenes ALTER COLUMN id SET SERIAL";
+    assert!(analisar_query(&ruta, alter).is_ok());
+
+    let hilos: Vec<_> = (0..8)
+        .map(|i| {
+            let ruta = ruta.clone();
+            thread::spawn(move || {
+                let query = format!(
+                    "INSERT INTO ordenes (id_cliente, producto, cantidad) VALUES ({}, 'Teclado', 1);",
+                    i
+                );
+                analisar_query(&ruta, &query).is_ok()
+            })
+        })
+        .collect();
+    for hilo in hilos {
+        assert!(hilo.join().unwrap());
+    }
+
+    let ids = ids_ordenes(&ruta);
+    let ids_unicos: HashSet<&String> = ids.iter().collect();
+    assert_eq!(ids.len(), 17);
+    assert_eq!(ids_unicos.len(), 17);
+}
+
+// Test 90: Guardar un esquema leído antes de reservar ids no hace volver atrás el contador ni deja temporales
+#[test]
+fn test_auto_increment_guardar_esquema_desactualizado() {
+    let ruta = preparar_directorio("test90");
+    let alter = "ALTER TABLE ordenes ALTER COLUMN id SET AUTO_INCREMENT";
+    assert!(analisar_query(&ruta, alter).is_ok());
+    let ruta_tabla = format!("{}/ordenes.csv", ruta);
+    let desactualizado = Esquema::cargar(&ruta_tabla).unwrap();
+
+    let query = "INSERT INTO ordenes (id_cliente, producto, cantidad) VALUES (6, 'Laptop', 3), (4, 'Cargador', 4);";
+    assert!(analisar_query(&ruta, query).is_ok());
+    let siguiente = Esquema::cargar(&ruta_tabla)
+        .unwrap()
+        .auto_increment
+        .unwrap()
+        .siguiente;
+    desactualizado.guardar(&ruta_tabla).unwrap();
+    assert_eq!(
+        Esquema::cargar(&ruta_tabla)
+            .unwrap()
+            .auto_increment
+            .unwrap()
+            .siguiente,
+        siguiente
+    );
+
+    let query = "INSERT INTO ordenes (id_cliente, producto, cantidad) VALUES (2, 'Monitor', 1);";
+    assert!(analisar_query(&ruta, query).is_ok());
+    let ids = ids_ordenes(&ruta);
+    let ids_unicos: HashSet<&String> = ids.iter().collect();
+    assert_eq!(ids.len(), ids_unicos.len());
+    let temporales = read_dir(&ruta)
+        .unwrap()
+        .filter(|entrada| {
+            let nombre = entrada.as_ref().unwrap().file_name();
+            nombre.to_string_lossy().ends_with(".tmp")
+        })
+        .count();
+    assert_eq!(temporales, 0);
+}
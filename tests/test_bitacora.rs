@@ -0,0 +1,108 @@
+mod common;
+
+use common::DirectorioTemporal;
+use std::fs::{read_dir, read_to_string, write, OpenOptions};
+use std::io::Write;
+use std::path::{absolute, Path};
+use taller_tp_individual::bitacora::{recuperar_directorio, verificar_tablas};
+use taller_tp_individual::query_identifier::analisar_query;
+
+fn preparar_directorio(nombre_test: &str) -> DirectorioTemporal {
+    common::preparar_directorio("test_bitacora", nombre_test, &["ordenes"])
+}
+
+fn ruta_absoluta(ruta: &str) -> String {
+    absolute(ruta).unwrap().to_string_lossy().to_string()
+}
+
+fn quedan_bitacoras(ruta: &str) -> bool {
+    read_dir(ruta).unwrap().any(|e| {
+        e.unwrap()
+            .file_name()
+            .to_string_lossy()
+            .starts_with(".bitacora")
+    })
+}
+
+// Test 49: Un insert que se cortó antes de confirmarse se deshace en la próxima consulta
+#[test]
+fn test_bitacora_deshace_insert_cortado() {
+    let ruta = preparar_directorio("test49");
+    let ruta_tabla = format!("{}/ordenes.csv", ruta);
+    let original = read_to_string(&ruta_tabla).unwrap();
+
+    // Simula un proceso que murió en el medio de agregar una fila.
+    let bitacora = format!(
+        "AGREGADO\t{}\t{}\n",
+        original.len(),
+        ruta_absoluta(&ruta_tabla)
+    );
+    write(format!("{}/.bitacora.999999.0", ruta), bitacora).unwrap();
+    let mut tabla = OpenOptions::new().append(true).open(&ruta_tabla).unwrap();
+    tabla.write_all(b"110,3,Mon").unwrap();
+
+    assert!(analisar_query(&ruta, "SELECT id FROM ordenes WHERE id = 101").is_ok());
+    assert_eq!(read_to_string(&ruta_tabla).unwrap(), original);
+    assert!(!quedan_bitacoras(&ruta));
+}
+
+// Test 50: Un reemplazo confirmado se completa y uno sin confirmar se descarta
+#[test]
+fn test_bitacora_rehace_reemplazo_confirmado() {
+    let ruta = preparar_directorio("test50");
+    let ruta_tabla = format!("{}/ordenes.csv", ruta);
+    let original = read_to_string(&ruta_tabla).unwrap();
+
+    let temporal = format!("{}/.ordenes.csv.1.tmp", ruta);
+    write(
+        &temporal,
+        "id,id_cliente,producto,cantidad\n101,1,Laptop,1\n",
+    )
+    .unwrap();
+    let bitacora = format!(
+        "REEMPLAZO\t{}\t{}\n",
+        ruta_absoluta(&temporal),
+        ruta_absoluta(&ruta_tabla)
+    );
+    write(format!("{}/.bitacora.999999.1", ruta), &bitacora).unwrap();
+    assert_eq!(recuperar_directorio(&ruta).unwrap(), 1);
+    assert_eq!(read_to_string(&ruta_tabla).unwrap(), original);
+    assert!(!Path::new(&temporal).exists());
+
+    write(
+        &temporal,
+        "id,id_cliente,producto,cantidad\n101,1,Laptop,1\n",
+    )
+    .unwrap();
+    write(
+        format!("{}/.bitacora.999999.2", ruta),
+        format!("{}CONFIRMADA\n", bitacora),
+    )
+    .unwrap();
+    assert!(analisar_query(&ruta, "RECOVER").is_ok());
+    assert_eq!(
+        read_to_string(&ruta_tabla).unwrap(),
+        "id,id_cliente,producto,cantidad\n101,1,Laptop,1\n"
+    );
+    assert!(!quedan_bitacoras(&ruta));
+}
+
+// Test 51: RECOVER informa las tablas con filas que no tienen la cantidad de columnas del encabezado
+#[test]
+fn test_bitacora_verificar_tablas() {
+    let ruta = preparar_directorio("test51");
+    write(
+        format!("{}/clientes.csv", ruta),
+        "id,nombre\n1,Juan\n2\n3,Ana\n",
+    )
+    .unwrap();
+
+    let tablas = verificar_tablas(&ruta).unwrap();
+    assert_eq!(tablas.len(), 2);
+    assert_eq!(tablas[0].0, "clientes");
+    assert_eq!(tablas[0].1, 3);
+    assert!(tablas[0].2.as_ref().unwrap().contains("línea 3"));
+    assert_eq!(tablas[1], ("ordenes".to_string(), 9, None));
+
+    assert!(analisar_query(&ruta, "RECOVER").is_err());
+}
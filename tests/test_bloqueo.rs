@@ -0,0 +1,85 @@
+mod common;
+
+use common::DirectorioTemporal;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use taller_tp_individual::bloqueo::{bloquear_tabla, configurar_espera_bloqueo, ModoBloqueo};
+use taller_tp_individual::query_identifier::analisar_query;
+
+fn preparar_directorio(nombre_test: &str) -> DirectorioTemporal {
+    common::preparar_directorio("test_bloqueo", nombre_test, &["ordenes"])
+}
+
+/*
+ * Bloquea la tabla desde otro hilo, que la mantiene bloqueada hasta recibir un mensaje por el canal devuelto.
+ */
+fn bloquear_en_otro_hilo(
+    ruta_tabla: &str,
+    modo: ModoBloqueo,
+) -> (mpsc::Sender<()>, thread::JoinHandle<()>) {
+    let (tomado_tx, tomado_rx) = mpsc::channel();
+    let (liberar_tx, liberar_rx) = mpsc::channel::<()>();
+    let ruta_tabla = ruta_tabla.to_string();
+    let hilo = thread::spawn(move || {
+        let _bloqueo = bloquear_tabla(&ruta_tabla, modo).unwrap();
+        tomado_tx.send(()).unwrap();
+        let _ = liberar_rx.recv();
+    });
+    tomado_rx.recv().unwrap();
+    (liberar_tx, hilo)
+}
+
+// Test 52: Mientras otro escribe en la tabla, una consulta espera y falla con un error de bloqueo
+#[test]
+fn test_bloqueo_exclusivo_impide_leer() {
+    configurar_espera_bloqueo(Duration::from_millis(100));
+    let ruta = preparar_directorio("test52");
+    let ruta_tabla = format!("{}/ordenes.csv", ruta);
+
+    let (liberar, hilo) = bloquear_en_otro_hilo(&ruta_tabla, ModoBloqueo::Exclusivo);
+    let error = analisar_query(&ruta, "SELECT id FROM ordenes WHERE id = 101").unwrap_err();
+    assert!(error.to_string().starts_with("Invalid Lock"));
+    liberar.send(()).unwrap();
+    hilo.join().unwrap();
+
+    assert!(analisar_query(&ruta, "SELECT id FROM ordenes WHERE id = 101").is_ok());
+}
+
+// Test 53: Varias lecturas comparten la tabla, pero una escritura espera a que terminen
+#[test]
+fn test_bloqueo_compartido_permite_leer() {
+    configurar_espera_bloqueo(Duration::from_millis(100));
+    let ruta = preparar_directorio("test53");
+    let ruta_tabla = format!("{}/ordenes.csv", ruta);
+
+    let (liberar, hilo) = bloquear_en_otro_hilo(&ruta_tabla, ModoBloqueo::Compartido);
+    assert!(analisar_query(&ruta, "SELECT id FROM ordenes WHERE id = 101").is_ok());
+    let error = analisar_query(&ruta, "DELETE FROM ordenes WHERE id = 101").unwrap_err();
+    assert!(error.to_string().starts_with("Invalid Lock"));
+    liberar.send(()).unwrap();
+    hilo.join().unwrap();
+
+    assert!(analisar_query(&ruta, "DELETE FROM ordenes WHERE id = 101").is_ok());
+}
+
+// Test 92: Si se suelta primero el bloqueo de afuera, la tabla sigue bloqueada hasta soltar el reutilizado
+#[test]
+fn test_bloqueo_reutilizado_sobrevive_al_primero() {
+    configurar_espera_bloqueo(Duration::from_millis(100));
+    let ruta = preparar_directorio("test92");
+    let ruta_tabla = format!("{}/ordenes.csv", ruta);
+
+    let primero = bloquear_tabla(&ruta_tabla, ModoBloqueo::Exclusivo).unwrap();
+    let reutilizado = bloquear_tabla(&ruta_tabla, ModoBloqueo::Exclusivo).unwrap();
+    drop(primero);
+    let leer_en_otro_hilo = || {
+        let ruta = ruta.clone();
+        thread::spawn(move || analisar_query(&ruta, "SELECT id FROM ordenes").is_ok())
+            .join()
+            .unwrap()
+    };
+    assert!(!leer_en_otro_hilo());
+    drop(reutilizado);
+    assert!(leer_en_otro_hilo());
+}
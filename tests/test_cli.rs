@@ -0,0 +1,185 @@
+mod common;
+
+use common::DirectorioTemporal;
+use std::fs::write;
+use std::process::{Command, Output};
+
+fn preparar_directorio(nombre_test: &str) -> DirectorioTemporal {
+    common::preparar_directorio("test_cli", nombre_test, &["clientes"])
+}
+
+fn ejecutar(argumentos: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_taller_tp_individual"))
+        .args(argumentos)
+        .output()
+        .expect("No se pudo ejecutar el programa")
+}
+
+// Test 66: Cada tipo de error termina con su propio código y los mensajes van a la salida de error
+#[test]
+fn test_cli_codigos_de_salida_y_salida_de_error() {
+    let ruta = preparar_directorio("test66");
+
+    let salida = ejecutar(&[&ruta, "UPDATE clientes SET nombre = 'Pedro' WHERE id = 1"]);
+    assert_eq!(salida.status.code(), Some(0));
+    assert!(salida.stdout.is_empty());
+
+    let salida = ejecutar(&[&ruta, "SELECT telefono FROM clientes"]);
+    assert_eq!(salida.status.code(), Some(4));
+    assert!(salida.stdout.is_empty());
+    assert!(
+        String::from_utf8_lossy(&salida.stderr).contains("La columna telefono no fue encontrada")
+    );
+
+    let salida = ejecutar(&[&ruta, "SELEC * FROM clientes"]);
+    assert_eq!(salida.status.code(), Some(2));
+
+    let salida = ejecutar(&[&ruta, "--file", "tests/test_cli/no_existe.sql"]);
+    assert_eq!(salida.status.code(), Some(7));
+
+    let salida = ejecutar(&[]);
+    assert_eq!(salida.status.code(), Some(2));
+    assert!(salida.stdout.is_empty());
+}
+
+// Test 67: --quiet oculta el resumen del script y --verbose muestra cada consulta, siempre por salida de error
+#[test]
+fn test_cli_quiet_y_verbose() {
+    let ruta = preparar_directorio("test67");
+    let script = format!("{}/script.sql", ruta);
+    write(
+        &script,
+        "SELECT nombre FROM clientes WHERE id = 2;\nSELECT nombre FROM clientes WHERE id = 3;\n",
+    )
+    .unwrap();
+
+    let salida = ejecutar(&[&ruta, "--file", &script, "--quiet"]);
+    assert_eq!(salida.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8_lossy(&salida.stdout),
+        "nombre\nAna\nnombre\nCarlos\n"
+    );
+    assert!(salida.stderr.is_empty());
+
+    let salida = ejecutar(&[&ruta, "--file", &script, "--verbose"]);
+    let errores = String::from_utf8_lossy(&salida.stderr);
+    assert!(errores.contains("[línea 2] SELECT nombre FROM clientes WHERE id = 3"));
+    assert!(errores.contains("[línea 2] correcta en"));
+    assert!(errores.contains("2 consultas: 2 correctas, 0 con error, 0 sin ejecutar."));
+    assert_eq!(
+        String::from_utf8_lossy(&salida.stdout),
+        "nombre\nAna\nnombre\nCarlos\n"
+    );
+}
+
+// Test 76: Los mensajes se muestran en inglés con --lang en o con la variable SQL_RUSTICO_IDIOMA, el flag tiene prioridad
+#[test]
+fn test_cli_idioma_de_los_mensajes() {
+    let ruta = preparar_directorio("test76");
+
+    let salida = ejecutar(&[&ruta, "--lang", "en", "SELECT nombr FROM clientes"]);
+    assert_eq!(salida.status.code(), Some(4));
+    let error = String::from_utf8_lossy(&salida.stderr);
+    assert!(error.contains("The column nombr was not found in the csv file."));
+    assert!(error.contains("--> line 1, column 8"));
+    assert!(error.contains("= did you mean nombre?"));
+
+    let salida = Command::new(env!("CARGO_BIN_EXE_taller_tp_individual"))
+        .args([&ruta, "SELECT * FROM client"])
+        .env("SQL_RUSTICO_IDIOMA", "en")
+        .output()
+        .expect("No se pudo ejecutar el programa");
+    assert_eq!(salida.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&salida.stderr).contains("The table client does not exist."));
+
+    let salida = Command::new(env!("CARGO_BIN_EXE_taller_tp_individual"))
+        .args([&ruta, "--lang", "es", "SELECT * FROM client"])
+        .env("SQL_RUSTICO_IDIOMA", "en")
+        .output()
+        .expect("No se pudo ejecutar el programa");
+    assert!(String::from_utf8_lossy(&salida.stderr).contains("La tabla client no existe."));
+
+    let salida = ejecutar(&[&ruta, "--lang", "fr", "SELECT * FROM clientes"]);
+    assert_eq!(salida.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&salida.stderr).contains("El idioma 'fr' no es válido"));
+}
+
+// Test 95: El uso del programa, los avisos de las variables de entorno y los errores de COPY también se traducen
+#[test]
+fn test_cli_textos_del_programa_en_ingles() {
+    let ruta = preparar_directorio("test95");
+
+    let salida = Command::new(env!("CARGO_BIN_EXE_taller_tp_individual"))
+        .env("SQL_RUSTICO_IDIOMA", "en")
+        .output()
+        .expect("No se pudo ejecutar el programa");
+    assert_eq!(salida.status.code(), Some(2));
+    let uso = String::from_utf8_lossy(&salida.stderr);
+    assert!(uso.starts_with("The command is not valid"));
+    assert!(!uso.contains("recuerde"));
+
+    let salida = Command::new(env!("CARGO_BIN_EXE_taller_tp_individual"))
+        .args([
+            &ruta,
+            "--lang",
+            "en",
+            "SELECT id FROM clientes WHERE id = 1",
+        ])
+        .env("SQL_RUSTICO_HILOS", "muchos")
+        .env("SQL_RUSTICO_MEMORIA_ORDEN_KB", "poca")
+        .output()
+        .expect("No se pudo ejecutar el programa");
+    assert_eq!(salida.status.code(), Some(0));
+    let avisos = String::from_utf8_lossy(&salida.stderr);
+    assert!(avisos.contains("SQL_RUSTICO_HILOS must be a number of threads."));
+    assert!(avisos.contains("SQL_RUSTICO_MEMORIA_ORDEN_KB must be a number of kilobytes."));
+
+    let archivo = format!("{}/sin_cerrar.csv", ruta);
+    write(&archivo, "10,\"Ana,López,ana@email.com\n").unwrap();
+    let query = format!("COPY clientes FROM '{}'", archivo);
+    let salida = ejecutar(&[&ruta, "--lang", "en", &query]);
+    assert_ne!(salida.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&salida.stderr)
+        .contains("the quotes of the value are not closed before the end of the file."));
+}
+
+// Test 103: EXPLAIN muestra las operaciones del plan y sus métricas en el idioma de los mensajes
+#[test]
+fn test_cli_explain_en_ingles() {
+    let ruta = preparar_directorio("test103");
+
+    let salida = ejecutar(&[
+        &ruta,
+        "--lang",
+        "en",
+        "--format",
+        "tsv",
+        "EXPLAIN SELECT nombre, COUNT(*) FROM clientes WHERE id > 1 AND 1 = 1 GROUP BY nombre ORDER BY nombre LIMIT 2",
+    ]);
+    assert_eq!(salida.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8_lossy(&salida.stdout),
+        "plan\n\
+         Limit: 2\n  \
+         -> Projection: nombre, COUNT(*)\n      \
+         -> Sort: nombre ASC\n          \
+         -> Aggregate: COUNT(*) [groups: nombre]\n              \
+         -> Sequential scan on clientes [columns: id, nombre] [filter: id > 1]\n"
+    );
+
+    let salida = ejecutar(&[
+        &ruta,
+        "--lang",
+        "en",
+        "--format",
+        "tsv",
+        "EXPLAIN ANALYZE SELECT nombre FROM clientes WHERE 1 = 2",
+    ]);
+    let plan = String::from_utf8_lossy(&salida.stdout);
+    let lineas: Vec<&str> = plan.lines().collect();
+    assert!(lineas[1].starts_with("Projection: nombre (rows=0 time="));
+    assert!(
+        lineas[2].starts_with("  -> No rows on clientes: the WHERE is never true (rows=0 time=")
+    );
+    assert!(lineas[3].starts_with("Total time: "));
+}
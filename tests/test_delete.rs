@@ -1,4 +1,7 @@
-use std::fs::{copy, create_dir_all, remove_dir_all};
+mod common;
+
+use common::DirectorioTemporal;
+use std::fs::{copy, read_dir, read_to_string};
 use taller_tp_individual::query_identifier::analisar_query;
 use taller_tp_individual::query_identifier::Comparacion;
 use taller_tp_individual::query_identifier::Delete;
@@ -33,11 +36,7 @@ fn test_delete_elimina_multiples() {
     let order_by = None;
     let delete = Delete::new("clientes_test_delete2", where_clauses, order_by);
 
-    let resultado = crear_escribir_archivo_temporal_delete(
-        &"datos/temporal2.csv".to_string(),
-        &delete,
-        &ruta.to_string(),
-    );
+    let resultado = crear_escribir_archivo_temporal_delete(&delete, &ruta.to_string());
     assert!(resultado.is_ok());
 }
 
@@ -71,11 +70,7 @@ fn test_delete_varias_where_clause() {
     let where_clauses = Some(vec![where_clause, where_clause2]);
     let operacion_logica = Some(vec![LogicalOperators::And]);
     let delete = Delete::new("clientes_test_delete2", where_clauses, operacion_logica);
-    let resultado = crear_escribir_archivo_temporal_delete(
-        &"datos/temporal3.csv".to_string(),
-        &delete,
-        &ruta.to_string(),
-    );
+    let resultado = crear_escribir_archivo_temporal_delete(&delete, &ruta.to_string());
     println!("{:?}", resultado);
     assert!(resultado.is_ok());
 }
@@ -88,3 +83,49 @@ fn test_delete_archivo_vacío() {
     let resultado = analisar_query(&ruta, query);
     assert!(resultado.is_err());
 }
+
+// Test 45: Se puede eliminar en un directorio de tablas sin carpeta datos/ y no quedan temporales
+#[test]
+fn test_delete_temporal_al_lado_de_la_tabla() {
+    let ruta = DirectorioTemporal::new("test_delete", "test45");
+    ruta.copiar(
+        "tests/test_delete/datos/clientes_test_delete_copia.csv",
+        "clientes.csv",
+    );
+
+    let query = "DELETE FROM clientes WHERE id > 2;";
+    assert!(analisar_query(&ruta, query).is_ok());
+    let mut archivos: Vec<String> = read_dir(&ruta)
+        .unwrap()
+        .map(|entrada| entrada.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    archivos.sort();
+    assert_eq!(archivos, vec![".clientes.lock", "clientes.csv"]);
+    assert_eq!(
+        read_to_string(format!("{}/clientes.csv", ruta))
+            .unwrap()
+            .lines()
+            .count(),
+        3
+    );
+}
+
+// Test 102: Un DELETE con OR elimina las filas que cumplen alguna de las conjunciones del WHERE
+#[test]
+fn test_delete_con_or() {
+    let ruta = DirectorioTemporal::new("test_delete", "test102");
+    ruta.copiar(
+        "tests/test_delete/datos/clientes_test_delete_copia.csv",
+        "clientes.csv",
+    );
+
+    let query = "DELETE FROM clientes WHERE apellido = 'López' OR id = 1 AND nombre = 'Carlos'";
+    assert!(analisar_query(&ruta, query).is_ok());
+    let ids: Vec<String> = read_to_string(format!("{}/clientes.csv", ruta))
+        .unwrap()
+        .lines()
+        .skip(1)
+        .map(|linea| linea.split(',').next().unwrap_or_default().to_string())
+        .collect();
+    assert_eq!(ids, vec!["1", "3", "4", "6"]);
+}
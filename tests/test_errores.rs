@@ -0,0 +1,139 @@
+use std::error::Error;
+use std::io;
+use taller_tp_individual::errors::{ErrorPrograma, ErrorTipo};
+use taller_tp_individual::query_identifier::analisar_query;
+use taller_tp_individual::sugerencias::distancia_edicion;
+
+fn error_de(query: &str) -> ErrorPrograma {
+    analisar_query(&"tests/test_errores/datos".to_string(), query).unwrap_err()
+}
+
+// Test 68: Los errores indican la posición del token que los causó y las alternativas esperadas
+#[test]
+fn test_errores_posicion_token_y_esperados() {
+    let query = "SELECT id FROM clientes WHERE id == 3";
+    let error = error_de(query);
+    assert_eq!(error.tipo(), &ErrorTipo::Syntax);
+    assert_eq!(error.token(), Some("=="));
+    assert_eq!(error.posicion(), Some(33..35));
+    assert_eq!(&query[error.posicion().unwrap()], "==");
+    assert_eq!(error.esperados(), ["=", "!=", "<", ">", "<=", ">="]);
+    assert_eq!(error.query(), Some(query));
+
+    // Un nombre de columna que también aparece dentro de otra palabra se ubica en la palabra completa.
+    let query = "SELECT nombre, id_x FROM clientes WHERE id_x = 1";
+    let error = error_de(query);
+    assert_eq!(
+        error.tipo(),
+        &ErrorTipo::UnknownColumn {
+            columna: "id_x".to_string()
+        }
+    );
+    assert_eq!(error.posicion(), Some(15..19));
+}
+
+// Test 69: El diagnóstico muestra la línea de la query con '^' debajo del problema, también al final de la query
+#[test]
+fn test_errores_diagnostico_con_marca() {
+    let error = error_de("SELECT id\nFROM clientes\nWHERE nombré = 'x'");
+    assert_eq!(
+        format!("{:#}", error),
+        "Invalid Column: Ninguno de estos dos valores 'nombré' y ''x'' en la WHERE clause fue encontrado como una columna en el csv.\n  --> línea 3, columna 7\n  |\n3 | WHERE nombré = 'x'\n  |       ^^^^^^\n  = ¿quiso decir nombre?"
+    );
+    // Sin el formato alternativo se mantiene el mensaje de una línea.
+    assert!(!error.to_string().contains('\n'));
+
+    let error = error_de("SELECT id, nombre clientes;");
+    let diagnostico = format!("{:#}", error);
+    assert!(diagnostico.ends_with(
+        "1 | SELECT id, nombre clientes;\n  |                           ^\n  = se esperaba FROM"
+    ));
+}
+
+// Test 70: Cada error indica su tipo con la tabla, columna o valor que lo causó
+#[test]
+fn test_errores_tipos_con_datos() {
+    let error = error_de("SELECT id FROM proveedores");
+    assert_eq!(
+        error.tipo(),
+        &ErrorTipo::UnknownTable {
+            tabla: "proveedores".to_string()
+        }
+    );
+    assert_eq!(error.tipo().codigo_salida(), 3);
+
+    let error = error_de("SELECT telefono FROM clientes");
+    assert!(matches!(error.tipo(), ErrorTipo::UnknownColumn { columna } if columna == "telefono"));
+
+    let error = error_de("ALTER TABLE clientes ALTER COLUMN nombre SET AUTO_INCREMENT");
+    assert_eq!(
+        error.tipo(),
+        &ErrorTipo::TypeMismatch {
+            columna: Some("nombre".to_string()),
+            valor: "Juan".to_string()
+        }
+    );
+    assert!(error.to_string().starts_with("Invalid Type:"));
+}
+
+// Test 71: Los errores de un archivo importado indican la línea, y los de lectura conservan el error original
+#[test]
+fn test_errores_csv_con_linea_e_io() {
+    let archivo = "tests/test_errores/importar_con_errores.csv";
+    let error = error_de(&format!("COPY clientes FROM '{}'", archivo));
+    assert_eq!(
+        error.tipo(),
+        &ErrorTipo::Csv {
+            archivo: archivo.to_string(),
+            linea: Some(2)
+        }
+    );
+    assert_eq!(error.tipo().codigo_salida(), 8);
+
+    let error = error_de("COPY clientes FROM 'tests/test_errores/no_existe.csv'");
+    assert_eq!(
+        error.tipo(),
+        &ErrorTipo::Io {
+            tipo: io::ErrorKind::NotFound
+        }
+    );
+    let origen = error.source().unwrap();
+    assert!(origen.downcast_ref::<io::Error>().is_some());
+}
+
+// Test 72: Las tablas, columnas y palabras clave mal escritas sugieren el nombre válido más parecido
+#[test]
+fn test_errores_sugieren_el_nombre_mas_parecido() {
+    let error = error_de("SELEECT id, nombre FROM clientes");
+    assert_eq!(error.sugerencia(), Some("SELECT"));
+    assert!(format!("{:#}", error).ends_with("\n  = ¿quiso decir SELECT?"));
+
+    let error = error_de("SELECT nombres FROM clientes");
+    assert_eq!(error.sugerencia(), Some("nombre"));
+    assert!(error.to_string().ends_with(" ¿Quiso decir nombre?"));
+
+    assert_eq!(
+        error_de("SELECT id FROM cliente").sugerencia(),
+        Some("clientes")
+    );
+    assert_eq!(
+        error_de("UPDATE clientes SET emial = 'x' WHERE id = 1").sugerencia(),
+        Some("email")
+    );
+    assert_eq!(
+        error_de("DELET FROM clientes").sugerencia(),
+        Some("DELETE FROM")
+    );
+}
+
+// Test 73: Solo se sugieren nombres cercanos, la distancia cuenta caracteres e intercambios de vecinos
+#[test]
+fn test_errores_sin_sugerencia_lejana() {
+    assert_eq!(error_de("SELECT telefono FROM clientes").sugerencia(), None);
+    assert_eq!(error_de("SELECT id FROM proveedores").sugerencia(), None);
+
+    assert_eq!(distancia_edicion("nombré", "nombre"), 1);
+    assert_eq!(distancia_edicion("emial", "email"), 1);
+    assert_eq!(distancia_edicion("SELEECT", "SELECT"), 1);
+    assert_eq!(distancia_edicion("", "id"), 2);
+}
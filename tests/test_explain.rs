@@ -0,0 +1,317 @@
+mod common;
+
+use common::{consultar, DirectorioTemporal};
+use taller_tp_individual::errors::ErrorTipo;
+use taller_tp_individual::query_identifier::{analisar_query, analisar_query_con_destino};
+use taller_tp_individual::salida::{Formato, SalidaFormateada};
+
+fn preparar_directorio(nombre_test: &str) -> DirectorioTemporal {
+    common::preparar_directorio("test_explain", nombre_test, &["clientes", "ordenes"])
+}
+
+// Devuelve las líneas del plan, sin el encabezado.
+fn explicar(ruta: &str, query: &str) -> Vec<String> {
+    let mut salida = Vec::new();
+    {
+        let mut destino = SalidaFormateada::new(Formato::Tsv, &mut salida);
+        analisar_query_con_destino(&ruta.to_string(), query, &mut destino).unwrap();
+    }
+    String::from_utf8(salida)
+        .unwrap()
+        .lines()
+        .skip(1)
+        .map(|linea| linea.to_string())
+        .collect()
+}
+
+// Test 81: EXPLAIN muestra el árbol de operaciones del SELECT, con el índice que se usa para leer la tabla
+#[test]
+fn test_explain_muestra_el_plan() {
+    let ruta = preparar_directorio("test81");
+
+    let plan = explicar(
+        &ruta,
+        "EXPLAIN SELECT id, nombre FROM clientes WHERE id >= 3 AND NOT nombre = 'Carlos' ORDER BY apellido DESC",
+    );
+    assert_eq!(
+        plan,
+        vec![
+            "Proyección: id, nombre",
+            "  -> Orden: apellido DESC",
+            "      -> Recorrido secuencial en clientes [columnas: id, nombre, apellido] [filtro: id >= 3 AND NOT nombre = 'Carlos']",
+        ]
+    );
+
+    assert!(analisar_query(
+        &ruta,
+        "CREATE INDEX idx_producto ON ordenes USING BTREE (producto)"
+    )
+    .is_ok());
+    assert!(analisar_query(&ruta, "CREATE INDEX idx_cliente ON ordenes (id_cliente)").is_ok());
+    let plan = explicar(
+        &ruta,
+        "EXPLAIN SELECT * FROM ordenes ORDER BY producto DESC",
+    );
+    assert_eq!(
+        plan,
+        vec![
+            "Proyección: *",
+            "  -> Recorrido con índice idx_producto (BTREE) en ordenes: ORDER BY producto DESC",
+        ]
+    );
+    let plan = explicar(
+        &ruta,
+        "EXPLAIN SELECT producto FROM ordenes WHERE 4 = id_cliente AND producto > 'A'",
+    );
+    assert_eq!(
+        plan[1],
+        "  -> Recorrido con índice idx_cliente (HASH) en ordenes: id_cliente = '4' [columnas: id_cliente, producto] [filtro: 4 = id_cliente AND producto > 'A']"
+    );
+    let plan = explicar(
+        &ruta,
+        "EXPLAIN SELECT id FROM ordenes WHERE producto >= 'M' AND producto < 'Mp'",
+    );
+    assert_eq!(
+        plan[1],
+        "  -> Recorrido con índice idx_producto (BTREE) en ordenes: producto >= 'M' AND producto < 'Mp' [columnas: id, producto] [filtro: producto >= 'M' AND producto < 'Mp']"
+    );
+}
+
+// Test 82: EXPLAIN ANALYZE ejecuta la consulta e indica las filas y el tiempo de cada operación
+#[test]
+fn test_explain_analyze_filas_por_operacion() {
+    let ruta = preparar_directorio("test82");
+
+    let plan = explicar(
+        &ruta,
+        "EXPLAIN ANALYZE SELECT producto FROM ordenes WHERE cantidad = 1 ORDER BY producto",
+    );
+    assert_eq!(plan.len(), 4);
+    assert!(plan[0].starts_with("Proyección: producto (filas=7 tiempo="));
+    assert!(plan[1].starts_with("  -> Orden: producto ASC (filas=7 tiempo="));
+    assert!(plan[2].starts_with(
+        "      -> Recorrido secuencial en ordenes [columnas: producto, cantidad] [filtro: cantidad = 1] (filas=7 tiempo="
+    ));
+    assert!(plan.iter().take(3).all(|linea| linea.ends_with(" ms)")));
+    assert!(plan[3].starts_with("Tiempo total: "));
+
+    let error = analisar_query(&ruta, "EXPLAIN DELETE FROM ordenes").unwrap_err();
+    assert_eq!(error.tipo(), &ErrorTipo::Syntax);
+    assert_eq!(error.esperados(), &["SELECT", "ANALYZE"]);
+    let error = analisar_query(&ruta, "EXPLAIN ANALYZE SELECT telefono FROM clientes").unwrap_err();
+    assert!(matches!(error.tipo(), ErrorTipo::UnknownColumn { columna } if columna == "telefono"));
+}
+
+// Test 83: Las condiciones constantes del WHERE se pliegan al planificar, sin cambiar el resultado
+#[test]
+fn test_explain_plegado_de_constantes() {
+    let ruta = preparar_directorio("test83");
+
+    let plan = explicar(&ruta, "EXPLAIN SELECT nombre FROM clientes WHERE 1 = 2");
+    assert_eq!(
+        plan,
+        vec![
+            "Proyección: nombre",
+            "  -> Sin filas en clientes: el WHERE nunca se cumple",
+        ]
+    );
+    assert_eq!(
+        consultar(&ruta, "SELECT nombre FROM clientes WHERE 1 = 2"),
+        "nombre\n"
+    );
+
+    let plan = explicar(
+        &ruta,
+        "EXPLAIN SELECT nombre FROM clientes WHERE 1 = 1 AND apellido = 'López' OR 'a' > 'b'",
+    );
+    assert_eq!(
+        plan[1],
+        "  -> Recorrido secuencial en clientes [columnas: nombre, apellido] [filtro: apellido = 'López']"
+    );
+    assert_eq!(
+        consultar(
+            &ruta,
+            "SELECT nombre FROM clientes WHERE 1 = 1 AND apellido = 'López' OR 'a' > 'b'"
+        ),
+        "nombre\nAna\nJosé\n"
+    );
+    let plan = explicar(&ruta, "EXPLAIN SELECT * FROM clientes WHERE 2 >= 1");
+    assert_eq!(
+        plan,
+        vec!["Proyección: *", "  -> Recorrido secuencial en clientes"]
+    );
+
+    // Un valor inválido no se pliega, para que siga dando error al evaluarlo.
+    let error =
+        analisar_query(&ruta, "SELECT * FROM clientes WHERE 1 = 2 AND id = -1").unwrap_err();
+    assert!(matches!(error.tipo(), ErrorTipo::TypeMismatch { .. }));
+}
+
+// Test 84: AND tiene precedencia sobre OR en SELECT, UPDATE y DELETE
+#[test]
+fn test_explain_precedencia_and_sobre_or() {
+    let ruta = preparar_directorio("test84");
+
+    assert_eq!(
+        consultar(&ruta, "SELECT id FROM clientes WHERE id = 1 OR id = 2"),
+        "id\n1\n2\n"
+    );
+    let query = "SELECT id, nombre FROM clientes WHERE id = 6 OR apellido = 'López' AND nombre = 'José' OR id = 1 ORDER BY nombre";
+    assert_eq!(
+        consultar(&ruta, query),
+        "id,nombre\n5,José\n1,Juan\n6,Laura\n"
+    );
+    let plan = explicar(&ruta, &format!("EXPLAIN {}", query));
+    assert_eq!(
+        plan[2],
+        "      -> Recorrido secuencial en clientes [columnas: id, nombre, apellido] [filtro: id = 6 OR apellido = 'López' AND nombre = 'José' OR id = 1]"
+    );
+
+    assert!(analisar_query(
+        &ruta,
+        "UPDATE clientes SET apellido = 'Sosa' WHERE id = 1 OR id = 3 AND nombre = 'Ana'"
+    )
+    .is_ok());
+    assert_eq!(
+        consultar(&ruta, "SELECT id FROM clientes WHERE apellido = 'Sosa'"),
+        "id\n1\n"
+    );
+    assert!(analisar_query(&ruta, "DELETE FROM clientes WHERE id = 2 OR id = 4").is_ok());
+    assert_eq!(
+        consultar(&ruta, "SELECT id FROM clientes"),
+        "id\n1\n3\n5\n6\n"
+    );
+}
+
+// Test 98: JOIN une las filas de dos tablas y cada condición del WHERE se evalúa en el recorrido de su tabla
+#[test]
+fn test_explain_join() {
+    let ruta = preparar_directorio("test98");
+
+    let query = "SELECT clientes.nombre, producto FROM clientes JOIN ordenes ON clientes.id = id_cliente WHERE cantidad = 2 ORDER BY producto";
+    assert_eq!(
+        consultar(&ruta, query),
+        "clientes.nombre,producto\nMaría,Mouse\nAna,Teléfono\n"
+    );
+    let query = "SELECT nombre, producto FROM clientes INNER JOIN ordenes ON ordenes.id_cliente = clientes.id WHERE cantidad = 2 AND apellido = 'López'";
+    assert_eq!(consultar(&ruta, query), "nombre,producto\nAna,Teléfono\n");
+    assert_eq!(
+        explicar(&ruta, &format!("EXPLAIN {}", query)),
+        vec![
+            "Proyección: clientes.nombre, ordenes.producto",
+            "  -> Join (hash): clientes.id = ordenes.id_cliente",
+            "      -> Recorrido secuencial en clientes [columnas: clientes.id, clientes.nombre, clientes.apellido] [filtro: clientes.apellido = 'López']",
+            "      -> Recorrido secuencial en ordenes [columnas: ordenes.id_cliente, ordenes.producto, ordenes.cantidad] [filtro: ordenes.cantidad = 2]",
+        ]
+    );
+
+    // Una condición con OR entre columnas de las dos tablas queda sobre el join.
+    let query = "SELECT ordenes.id FROM clientes JOIN ordenes ON clientes.id = id_cliente WHERE cantidad = 2 OR nombre = 'José' ORDER BY ordenes.id";
+    assert_eq!(consultar(&ruta, query), "ordenes.id\n102\n105\n106\n109\n");
+    assert_eq!(
+        explicar(&ruta, &format!("EXPLAIN {}", query))[2],
+        "      -> Filtro: ordenes.cantidad = 2 OR clientes.nombre = 'José'"
+    );
+
+    let error = analisar_query(
+        &ruta,
+        "SELECT id FROM clientes JOIN ordenes ON clientes.id = id_cliente",
+    )
+    .unwrap_err();
+    assert!(matches!(error.tipo(), ErrorTipo::UnknownColumn { columna } if columna == "id"));
+    let error = analisar_query(
+        &ruta,
+        "SELECT nombre FROM clientes JOIN clientes ON id = id",
+    )
+    .unwrap_err();
+    assert_eq!(error.tipo(), &ErrorTipo::Syntax);
+    let error = analisar_query(
+        &ruta,
+        "SELECT nombre FROM clientes JOIN ordenes ON clientes.id = ordenes.cliente",
+    )
+    .unwrap_err();
+    assert!(
+        matches!(error.tipo(), ErrorTipo::UnknownColumn { columna } if columna == "ordenes.cliente")
+    );
+}
+
+// Test 99: GROUP BY y las funciones de agregación devuelven una fila por grupo
+#[test]
+fn test_explain_agregacion() {
+    let ruta = preparar_directorio("test99");
+
+    assert_eq!(
+        consultar(
+            &ruta,
+            "SELECT id_cliente, COUNT(*), SUM(cantidad) FROM ordenes GROUP BY id_cliente ORDER BY id_cliente"
+        ),
+        "id_cliente,COUNT(*),SUM(cantidad)\n1,2,2\n2,1,2\n3,1,1\n4,2,3\n5,2,2\n6,1,1\n"
+    );
+    assert_eq!(
+        consultar(
+            &ruta,
+            "SELECT COUNT(*), MIN(producto), MAX(cantidad), AVG(cantidad) FROM ordenes"
+        ),
+        "COUNT(*),MIN(producto),MAX(cantidad),AVG(cantidad)\n9,Altavoces,2,1.2222222222222223\n"
+    );
+    assert_eq!(
+        consultar(
+            &ruta,
+            "SELECT COUNT(*), SUM(cantidad) FROM ordenes WHERE producto = 'Tablet'"
+        ),
+        "COUNT(*),SUM(cantidad)\n0,\n"
+    );
+    assert_eq!(
+        consultar(
+            &ruta,
+            "SELECT clientes.nombre, COUNT(*) FROM clientes JOIN ordenes ON clientes.id = id_cliente GROUP BY clientes.nombre ORDER BY clientes.nombre"
+        ),
+        "clientes.nombre,COUNT(*)\nAna,1\nCarlos,1\nJosé,2\nJuan,2\nLaura,1\nMaría,2\n"
+    );
+    assert_eq!(
+        explicar(
+            &ruta,
+            "EXPLAIN SELECT id_cliente, COUNT(*) FROM ordenes GROUP BY id_cliente"
+        ),
+        vec![
+            "Proyección: id_cliente, COUNT(*)",
+            "  -> Agregación: COUNT(*) [grupos: id_cliente]",
+            "      -> Recorrido secuencial en ordenes [columnas: id_cliente]",
+        ]
+    );
+
+    let error = analisar_query(&ruta, "SELECT producto, COUNT(*) FROM ordenes").unwrap_err();
+    assert_eq!(error.tipo(), &ErrorTipo::Syntax);
+    let error = analisar_query(&ruta, "SELECT SUM(*) FROM ordenes").unwrap_err();
+    assert_eq!(error.tipo(), &ErrorTipo::Syntax);
+    let error = analisar_query(&ruta, "SELECT SUM(producto) FROM ordenes").unwrap_err();
+    assert!(matches!(error.tipo(), ErrorTipo::TypeMismatch { .. }));
+}
+
+// Test 100: LIMIT deja de pedir filas a las operaciones de abajo cuando ya tiene las que necesita
+#[test]
+fn test_explain_limit() {
+    let ruta = preparar_directorio("test100");
+
+    assert_eq!(
+        consultar(
+            &ruta,
+            "SELECT producto FROM ordenes ORDER BY producto LIMIT 3"
+        ),
+        "producto\nAltavoces\nAuriculares\nImpresora\n"
+    );
+    assert_eq!(
+        consultar(&ruta, "SELECT producto FROM ordenes LIMIT 0"),
+        "producto\n"
+    );
+    let plan = explicar(&ruta, "EXPLAIN ANALYZE SELECT id FROM ordenes LIMIT 2");
+    assert!(plan[0].starts_with("Límite: 2 (filas=2 tiempo="));
+    assert!(plan[1].starts_with("  -> Proyección: id (filas=2 tiempo="));
+    assert!(plan[2]
+        .starts_with("      -> Recorrido secuencial en ordenes [columnas: id] (filas=2 tiempo="));
+
+    let error = analisar_query(&ruta, "SELECT id FROM ordenes LIMIT -1").unwrap_err();
+    assert_eq!(error.tipo(), &ErrorTipo::Syntax);
+    let error = analisar_query(&ruta, "SELECT id FROM ordenes LIMIT").unwrap_err();
+    assert_eq!(error.tipo(), &ErrorTipo::Syntax);
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use common::DirectorioTemporal;
+use std::fs::{read_dir, read_to_string, write};
+use taller_tp_individual::errors::ErrorTipo;
+use taller_tp_individual::query_identifier::{analisar_query, CopyTo};
+use taller_tp_individual::salida::Formato;
+
+fn preparar_directorio(nombre_test: &str) -> DirectorioTemporal {
+    common::preparar_directorio("test_exportar", nombre_test, &["clientes"])
+}
+
+// Test 62: SELECT ... INTO OUTFILE escribe el encabezado y las filas con comillas donde hacen falta
+#[test]
+fn test_exportar_into_outfile_csv() {
+    let ruta = preparar_directorio("test62");
+    write(
+        format!("{}/notas.csv", ruta),
+        "id,nota\n1,dijo \"hola\"\n2,\n",
+    )
+    .unwrap();
+    let salida = format!("{}/salida.csv", ruta);
+
+    let query = format!(
+        "SELECT id, nombre FROM clientes WHERE apellido = 'López' ORDER BY nombre DESC INTO OUTFILE '{}'",
+        salida
+    );
+    assert!(analisar_query(&ruta, &query).is_ok());
+    assert_eq!(
+        read_to_string(&salida).unwrap(),
+        "id,nombre\n5,José\n2,Ana\n"
+    );
+
+    let query = format!("SELECT * FROM notas INTO OUTFILE '{}' FORMAT json", salida);
+    assert!(analisar_query(&ruta, &query).is_ok());
+    assert_eq!(
+        read_to_string(&salida).unwrap(),
+        "[\n  {\"id\": \"1\", \"nota\": \"dijo \\\"hola\\\"\"},\n  {\"id\": \"2\", \"nota\": null}\n]\n"
+    );
+}
+
+// Test 63: COPY (SELECT ...) TO se parsea con sus opciones, y un error no toca el archivo que ya existía ni deja temporales
+#[test]
+fn test_exportar_copy_to_es_atomico() {
+    let copy_to = CopyTo::copy_to_parsear_query(
+        "COPY (SELECT id FROM clientes WHERE id = 1 OR nombre = 'Ana') TO 'salida.json' WITH (FORMAT json);",
+    )
+    .unwrap();
+    assert_eq!(copy_to.select.tabla, "clientes");
+    assert_eq!(copy_to.exportacion.ruta, "salida.json");
+    assert_eq!(copy_to.exportacion.formato, Formato::Json);
+    assert!(CopyTo::copy_to_parsear_query("COPY (SELECT id FROM clientes) 'salida.csv'").is_err());
+
+    let ruta = preparar_directorio("test63");
+    let salida = format!("{}/salida.csv", ruta);
+    let query = format!(
+        "COPY (SELECT id FROM clientes WHERE id = 3) TO '{}'",
+        salida
+    );
+    assert!(analisar_query(&ruta, &query).is_ok());
+    assert_eq!(read_to_string(&salida).unwrap(), "id\n3\n");
+
+    let query = format!("COPY (SELECT telefono FROM clientes) TO '{}'", salida);
+    assert!(analisar_query(&ruta, &query).is_err());
+    assert_eq!(read_to_string(&salida).unwrap(), "id\n3\n");
+    let temporales = read_dir(&ruta)
+        .unwrap()
+        .filter(|e| {
+            let nombre = e.as_ref().unwrap().file_name();
+            nombre.to_string_lossy().ends_with(".tmp")
+        })
+        .count();
+    assert_eq!(temporales, 0);
+}
+
+// Test 106: Un INTO OUTFILE dentro de un valor entre comillas no se toma como la cláusula
+#[test]
+fn test_exportar_into_outfile_entre_comillas() {
+    let ruta = preparar_directorio("test106");
+    let query = "SELECT id, 'a INTO OUTFILE b' FROM clientes";
+    let error = analisar_query(&ruta, query).unwrap_err();
+    assert!(matches!(
+        error.tipo(),
+        ErrorTipo::UnknownColumn { columna } if columna == "'a INTO OUTFILE b'"
+    ));
+}
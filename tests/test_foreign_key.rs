@@ -0,0 +1,99 @@
+mod common;
+
+use common::DirectorioTemporal;
+use std::fs::{read_to_string, OpenOptions};
+use std::io::Write;
+use taller_tp_individual::query_identifier::analisar_query;
+
+/*
+ * Cada test copia las tablas de tests/test_foreign_key/datos a un directorio propio,
+ * así las foreign keys declaradas y los cambios no afectan a los otros tests.
+ */
+fn preparar_directorio(nombre_test: &str) -> DirectorioTemporal {
+    common::preparar_directorio("test_foreign_key", nombre_test, &["clientes", "ordenes"])
+}
+
+// Test 29: No se puede insertar una orden de un cliente que no existe
+#[test]
+fn test_foreign_key_insert_huerfano() {
+    let ruta = preparar_directorio("test29");
+    let alter = "ALTER TABLE ordenes ADD FOREIGN KEY (id_cliente) REFERENCES clientes (id)";
+    assert!(analisar_query(&ruta, alter).is_ok());
+
+    let query =
+        "INSERT INTO ordenes (id, id_cliente, producto, cantidad) VALUES (110, 99, 'Laptop', 1);";
+    assert!(analisar_query(&ruta, query).is_err());
+    let query =
+        "INSERT INTO ordenes (id, id_cliente, producto, cantidad) VALUES (110, 3, 'Laptop', 1);";
+    assert!(analisar_query(&ruta, query).is_ok());
+}
+
+// Test 30: Con RESTRICT no se puede eliminar un cliente que tiene ordenes
+#[test]
+fn test_foreign_key_delete_restrict() {
+    let ruta = preparar_directorio("test30");
+    let alter = "ALTER TABLE ordenes ADD FOREIGN KEY (id_cliente) REFERENCES clientes (id) ON DELETE RESTRICT";
+    assert!(analisar_query(&ruta, alter).is_ok());
+
+    assert!(analisar_query(&ruta, "DELETE FROM clientes WHERE id = 1").is_err());
+    let clientes = read_to_string(format!("{}/clientes.csv", ruta)).unwrap();
+    assert!(clientes.contains("1,Juan"));
+}
+
+// Test 31: Con CASCADE se eliminan las ordenes del cliente eliminado
+#[test]
+fn test_foreign_key_delete_cascade() {
+    let ruta = preparar_directorio("test31");
+    let alter = "ALTER TABLE ordenes ADD FOREIGN KEY (id_cliente) REFERENCES clientes (id) ON DELETE CASCADE";
+    assert!(analisar_query(&ruta, alter).is_ok());
+
+    assert!(analisar_query(&ruta, "DELETE FROM clientes WHERE id = 4").is_ok());
+    let ordenes = read_to_string(format!("{}/ordenes.csv", ruta)).unwrap();
+    assert!(!ordenes.contains(",4,"));
+    assert!(ordenes.contains("101,1,Laptop,1"));
+}
+
+// Test 32: Con SET NULL y CASCADE en UPDATE se modifican las ordenes del cliente
+#[test]
+fn test_foreign_key_update_set_null_y_cascade() {
+    let ruta = preparar_directorio("test32");
+    let alter = "ALTER TABLE ordenes ADD FOREIGN KEY (id_cliente) REFERENCES clientes (id) ON DELETE SET NULL ON UPDATE CASCADE";
+    assert!(analisar_query(&ruta, alter).is_ok());
+
+    assert!(analisar_query(&ruta, "UPDATE clientes SET id = 10 WHERE id = 5").is_ok());
+    assert!(analisar_query(&ruta, "DELETE FROM clientes WHERE id = 6").is_ok());
+    let ordenes = read_to_string(format!("{}/ordenes.csv", ruta)).unwrap();
+    assert!(ordenes.contains("106,10,Impresora,1"));
+    assert!(ordenes.contains("107,,Altavoces,1"));
+
+    assert!(analisar_query(&ruta, "UPDATE ordenes SET id_cliente = 77 WHERE id = 101").is_err());
+}
+
+// Test 33: No se puede declarar una foreign key si ya hay valores huérfanos
+#[test]
+fn test_foreign_key_datos_existentes_invalidos() {
+    let ruta = preparar_directorio("test33");
+    let query = "DELETE FROM clientes WHERE id = 6";
+    assert!(analisar_query(&ruta, query).is_ok());
+    let alter = "ALTER TABLE ordenes ADD FOREIGN KEY (id_cliente) REFERENCES clientes (id)";
+    assert!(analisar_query(&ruta, alter).is_err());
+}
+
+// Test 89: Una fila de la tabla hija con menos valores da un error del archivo al propagar un cambio
+#[test]
+fn test_foreign_key_fila_corta_en_tabla_hija() {
+    let ruta = preparar_directorio("test89");
+    let alter = "ALTER TABLE ordenes ADD FOREIGN KEY (id_cliente) REFERENCES clientes (id) ON DELETE CASCADE";
+    assert!(analisar_query(&ruta, alter).is_ok());
+
+    let ruta_ordenes = format!("{}/ordenes.csv", ruta);
+    let mut ordenes = OpenOptions::new().append(true).open(&ruta_ordenes).unwrap();
+    ordenes.write_all(b"110\n").unwrap();
+    drop(ordenes);
+
+    let error = analisar_query(&ruta, "DELETE FROM clientes WHERE id = 4").unwrap_err();
+    assert!(error.to_string().starts_with("Invalid Csv"));
+    assert!(error.to_string().contains("La línea 11 tiene 1 valores"));
+    let clientes = read_to_string(format!("{}/clientes.csv", ruta)).unwrap();
+    assert!(clientes.contains("4,María"));
+}
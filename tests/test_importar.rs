@@ -0,0 +1,75 @@
+mod common;
+
+use common::DirectorioTemporal;
+use std::fs::{read_to_string, write};
+use taller_tp_individual::query_identifier::{analisar_query, CopyFrom};
+
+fn preparar_directorio(nombre_test: &str) -> DirectorioTemporal {
+    common::preparar_directorio("test_importar", nombre_test, &["clientes", "ordenes"])
+}
+
+// Test 64: COPY FROM con HEADER mapea las columnas por nombre, respeta comillas y completa el auto increment
+#[test]
+fn test_importar_con_encabezado_y_delimitador() {
+    let copy = CopyFrom::copy_from_parsear_query(
+        "COPY clientes FROM 'nuevos.csv' WITH (HEADER, DELIMITER ';');",
+    )
+    .unwrap();
+    assert_eq!(copy.tabla, "clientes");
+    assert_eq!(copy.archivo, "nuevos.csv");
+    assert!(copy.encabezado);
+    assert_eq!(copy.delimitador, ';');
+
+    let ruta = preparar_directorio("test64");
+    let query = "ALTER TABLE clientes ALTER COLUMN id SET AUTO_INCREMENT";
+    assert!(analisar_query(&ruta, query).is_ok());
+    let archivo = format!("{}/nuevos.csv", ruta);
+    write(
+        &archivo,
+        "email;nombre;apellido\n\"pedro@email.com\";Pedro;\"O'Neil\"\n\nlucia@email.com; Lucía ;\"\"\n",
+    )
+    .unwrap();
+
+    let query = format!(
+        "COPY clientes FROM '{}' WITH (HEADER, DELIMITER ';')",
+        archivo
+    );
+    assert!(analisar_query(&ruta, &query).is_ok());
+    let clientes = read_to_string(format!("{}/clientes.csv", ruta)).unwrap();
+    assert!(clientes.ends_with("7,Pedro,O'Neil,pedro@email.com\n8,Lucía,,lucia@email.com\n"));
+}
+
+// Test 65: Si alguna fila del archivo tiene errores no se importa ninguna y se informa cada línea con error
+#[test]
+fn test_importar_informa_errores_por_linea() {
+    let ruta = preparar_directorio("test65");
+    let query = "ALTER TABLE ordenes ADD FOREIGN KEY (id_cliente) REFERENCES clientes (id)";
+    assert!(analisar_query(&ruta, query).is_ok());
+    let ordenes_antes = read_to_string(format!("{}/ordenes.csv", ruta)).unwrap();
+    let archivo = format!("{}/ordenes_nuevas.csv", ruta);
+    write(
+        &archivo,
+        "200,1,Teclado,2\n201,9,Mouse,1\n202,2,\"Cable, USB\",1\ndoscientos,2,Silla,1\n204,3,Mesa\n205,2,Lámpara,3\n",
+    )
+    .unwrap();
+
+    let query = format!("COPY ordenes FROM '{}'", archivo);
+    let error = analisar_query(&ruta, &query).unwrap_err().to_string();
+    assert!(error.contains("4 filas tienen errores"));
+    assert!(error.contains("línea 2: El valor '9' de ordenes.id_cliente no existe en clientes.id."));
+    assert!(error.contains("línea 3: el valor 'Cable, USB' tiene comas"));
+    assert!(error.contains("línea 4: el valor 'doscientos' de la columna id no es un entero"));
+    assert!(error.contains("línea 5: tiene 3 valores pero se esperaban 4."));
+    assert!(!error.contains("línea 1:") && !error.contains("línea 6:"));
+    assert_eq!(
+        read_to_string(format!("{}/ordenes.csv", ruta)).unwrap(),
+        ordenes_antes
+    );
+
+    // Con las columnas indicadas, las que faltan quedan vacías.
+    write(&archivo, "300,Parlante\n").unwrap();
+    let query = format!("COPY ordenes (id, producto) FROM '{}'", archivo);
+    assert!(analisar_query(&ruta, &query).is_ok());
+    let ordenes = read_to_string(format!("{}/ordenes.csv", ruta)).unwrap();
+    assert!(ordenes.ends_with("300,,Parlante,\n"));
+}
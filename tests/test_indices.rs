@@ -0,0 +1,314 @@
+mod common;
+
+use common::{consultar, DirectorioTemporal};
+use std::fs::{read_to_string, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use taller_tp_individual::errors::ErrorTipo;
+use taller_tp_individual::query_identifier::analisar_query;
+use taller_tp_individual::sesion::Sesion;
+
+fn preparar_directorio(nombre_test: &str) -> DirectorioTemporal {
+    common::preparar_directorio("test_indices", nombre_test, &["clientes", "ordenes"])
+}
+
+// Ejecuta la query en los dos directorios, con y sin índice, y verifica que den el mismo resultado.
+fn consultar_en_ambos(con_indice: &str, sin_indice: &str, query: &str) -> String {
+    let resultado = consultar(con_indice, query);
+    assert_eq!(resultado, consultar(sin_indice, query));
+    resultado
+}
+
+// Test 77: El índice se crea, se mantiene con INSERT, UPDATE y DELETE y da los mismos resultados que recorrer la tabla
+#[test]
+fn test_indices_create_index_y_mantenimiento() {
+    let con_indice = preparar_directorio("test77_con_indice");
+    let sin_indice = preparar_directorio("test77_sin_indice");
+
+    assert!(analisar_query(
+        &con_indice,
+        "CREATE INDEX idx_apellido ON clientes (apellido);"
+    )
+    .is_ok());
+    assert!(Path::new(&format!("{}/clientes.idx_apellido.indice", con_indice)).exists());
+    let esquema = read_to_string(format!("{}/clientes.esquema", con_indice)).unwrap();
+    assert_eq!(esquema, "INDEX,idx_apellido,HASH,apellido\n");
+
+    let query = "SELECT id, nombre FROM clientes WHERE apellido = 'López'";
+    assert_eq!(
+        consultar_en_ambos(&con_indice, &sin_indice, query),
+        "id,nombre\n2,Ana\n5,José\n"
+    );
+    let query =
+        "SELECT * FROM clientes WHERE apellido = 'López' AND nombre != 'Ana' ORDER BY id DESC";
+    consultar_en_ambos(&con_indice, &sin_indice, query);
+
+    for ruta in [&con_indice, &sin_indice] {
+        let ruta = ruta.to_string();
+        assert!(analisar_query(
+            &ruta,
+            "INSERT INTO clientes (id, nombre, apellido, email) VALUES (7, 'Lucía', 'López', 'lucia@email.com')"
+        )
+        .is_ok());
+        assert!(
+            analisar_query(&ruta, "UPDATE clientes SET apellido = 'Gómez' WHERE id = 2").is_ok()
+        );
+        assert!(analisar_query(&ruta, "DELETE FROM clientes WHERE id = 5").is_ok());
+    }
+    let query = "SELECT id, nombre FROM clientes WHERE apellido = 'López'";
+    assert_eq!(
+        consultar_en_ambos(&con_indice, &sin_indice, query),
+        "id,nombre\n7,Lucía\n"
+    );
+    let query = "SELECT id FROM clientes WHERE 'Gómez' = apellido";
+    assert_eq!(
+        consultar_en_ambos(&con_indice, &sin_indice, query),
+        "id\n2\n3\n"
+    );
+}
+
+// Test 78: DROP INDEX elimina el índice, los nombres inválidos dan error y un índice desactualizado no se usa
+#[test]
+fn test_indices_drop_index_y_errores() {
+    let ruta = preparar_directorio("test78");
+    let archivo_indice = format!("{}/clientes.idx_id.indice", ruta);
+
+    assert!(analisar_query(&ruta, "CREATE INDEX idx_id ON clientes USING HASH (id)").is_ok());
+    let error = analisar_query(&ruta, "CREATE INDEX idx_id ON clientes (nombre)").unwrap_err();
+    assert!(matches!(
+        error.tipo(),
+        ErrorTipo::ConstraintViolation { .. }
+    ));
+    let error = analisar_query(&ruta, "CREATE INDEX idx_tel ON clientes (telefono)").unwrap_err();
+    assert!(matches!(error.tipo(), ErrorTipo::UnknownColumn { columna } if columna == "telefono"));
+    let error =
+        analisar_query(&ruta, "CREATE INDEX idx ON clientes USING BITMAP (id)").unwrap_err();
+    assert_eq!(error.tipo(), &ErrorTipo::Syntax);
+
+    // Una fila agregada sin pasar por el programa deja al índice desactualizado y se recorre la tabla.
+    let mut tabla = OpenOptions::new()
+        .append(true)
+        .open(format!("{}/clientes.csv", ruta))
+        .unwrap();
+    writeln!(tabla, "9,Pedro,Sosa,pedro@email.com").unwrap();
+    assert_eq!(
+        consultar(&ruta, "SELECT nombre FROM clientes WHERE id = 9"),
+        "nombre\nPedro\n"
+    );
+
+    let mut sesion = Sesion::new(&ruta);
+    assert!(sesion.ejecutar("BEGIN").is_ok());
+    assert!(sesion.ejecutar("DROP INDEX idx_id").is_ok());
+    assert!(sesion.ejecutar("ROLLBACK").is_ok());
+    assert!(Path::new(&archivo_indice).exists());
+    let esquema = read_to_string(format!("{}/clientes.esquema", ruta)).unwrap();
+    assert_eq!(esquema, "INDEX,idx_id,HASH,id\n");
+
+    let error = analisar_query(&ruta, "DROP INDEX idx_ids").unwrap_err();
+    assert_eq!(
+        error.tipo(),
+        &ErrorTipo::UnknownIndex {
+            indice: "idx_ids".to_string()
+        }
+    );
+    assert_eq!(error.sugerencia(), Some("idx_id"));
+    assert_eq!(error.tipo().codigo_salida(), 3);
+
+    assert!(analisar_query(&ruta, "DROP INDEX idx_id ON clientes;").is_ok());
+    assert!(!Path::new(&archivo_indice).exists());
+    assert_eq!(
+        read_to_string(format!("{}/clientes.esquema", ruta)).unwrap(),
+        ""
+    );
+    assert!(analisar_query(&ruta, "DROP INDEX idx_id").is_err());
+}
+
+// Test 79: Un índice BTREE resuelve rangos con los mismos resultados que recorrer la tabla
+#[test]
+fn test_indices_btree_rangos() {
+    let con_indice = preparar_directorio("test79_con_indice");
+    let sin_indice = preparar_directorio("test79_sin_indice");
+
+    assert!(analisar_query(
+        &con_indice,
+        "CREATE INDEX idx_orden ON ordenes USING BTREE (id)"
+    )
+    .is_ok());
+    let contenido = read_to_string(format!("{}/ordenes.idx_orden.indice", con_indice)).unwrap();
+    assert!(contenido.starts_with("BTREE,id,"));
+
+    let query = "SELECT id, producto FROM ordenes WHERE id >= 104 AND id < 107";
+    assert_eq!(
+        consultar_en_ambos(&con_indice, &sin_indice, query),
+        "id,producto\n104,Teclado\n105,Mouse\n106,Impresora\n"
+    );
+    for query in [
+        "SELECT * FROM ordenes WHERE 108 <= id",
+        "SELECT * FROM ordenes WHERE id > 102 AND id <= 103 AND cantidad = 1",
+        "SELECT * FROM ordenes WHERE id = 105",
+        "SELECT * FROM ordenes WHERE id > 109",
+        "SELECT * FROM ordenes WHERE id > 100",
+    ] {
+        consultar_en_ambos(&con_indice, &sin_indice, query);
+    }
+
+    for ruta in [&con_indice, &sin_indice] {
+        let ruta = ruta.to_string();
+        assert!(analisar_query(
+            &ruta,
+            "INSERT INTO ordenes (id, id_cliente, producto, cantidad) VALUES (100, 6, 'Cable', 2)"
+        )
+        .is_ok());
+        assert!(analisar_query(&ruta, "DELETE FROM ordenes WHERE id = 105").is_ok());
+    }
+    let query = "SELECT id FROM ordenes WHERE id < 106";
+    assert_eq!(
+        consultar_en_ambos(&con_indice, &sin_indice, query),
+        "id\n101\n103\n102\n104\n100\n"
+    );
+}
+
+// Test 80: Un índice BTREE devuelve las filas en el orden del ORDER BY, con los empates en el orden del archivo
+#[test]
+fn test_indices_btree_order_by() {
+    let con_indice = preparar_directorio("test80_con_indice");
+    let sin_indice = preparar_directorio("test80_sin_indice");
+
+    assert!(analisar_query(
+        &con_indice,
+        "CREATE INDEX idx_producto ON ordenes USING BTREE (producto)"
+    )
+    .is_ok());
+    let query = "SELECT id, producto FROM ordenes ORDER BY producto DESC";
+    assert_eq!(
+        consultar_en_ambos(&con_indice, &sin_indice, query),
+        "id,producto\n102,Teléfono\n104,Teclado\n105,Mouse\n103,Monitor\n101,Laptop\n109,Laptop\n106,Impresora\n108,Auriculares\n107,Altavoces\n"
+    );
+    for query in [
+        "SELECT * FROM ordenes ORDER BY producto",
+        "SELECT * FROM ordenes WHERE producto >= 'L' AND producto < 'N' ORDER BY producto DESC",
+        "SELECT * FROM ordenes WHERE cantidad = 1 ORDER BY producto ASC",
+        "SELECT * FROM ordenes ORDER BY producto, id DESC",
+    ] {
+        consultar_en_ambos(&con_indice, &sin_indice, query);
+    }
+
+    // Una fila agregada sin pasar por el programa deja al índice desactualizado y se ordena la tabla.
+    for ruta in [&con_indice, &sin_indice] {
+        let mut tabla = OpenOptions::new()
+            .append(true)
+            .open(format!("{}/ordenes.csv", ruta))
+            .unwrap();
+        writeln!(tabla, "110,2,Cargador,1").unwrap();
+    }
+    consultar_en_ambos(
+        &con_indice,
+        &sin_indice,
+        "SELECT * FROM ordenes ORDER BY producto",
+    );
+    consultar_en_ambos(
+        &con_indice,
+        &sin_indice,
+        "SELECT * FROM ordenes WHERE producto = 'Cargador'",
+    );
+}
+
+// Devuelve la primera línea del índice y la última, que después de agregar filas es la marca con la longitud de la tabla.
+fn extremos_del_indice(ruta: &str, indice: &str) -> (String, String) {
+    let contenido = read_to_string(format!("{}/{}.indice", ruta, indice)).unwrap();
+    let mut lineas = contenido.lines();
+    let primera = lineas.next().unwrap().to_string();
+    (primera, lineas.last().unwrap().to_string())
+}
+
+// Test 96: Las filas insertadas se agregan al final de los índices sin reconstruirlos, y UPDATE los reconstruye
+#[test]
+fn test_indices_insert_agrega_al_indice() {
+    let con_indice = preparar_directorio("test96_con_indice");
+    let sin_indice = preparar_directorio("test96_sin_indice");
+
+    assert!(analisar_query(
+        &con_indice,
+        "CREATE INDEX idx_cliente ON ordenes (id_cliente)"
+    )
+    .is_ok());
+    assert!(analisar_query(
+        &con_indice,
+        "CREATE INDEX idx_producto ON ordenes USING BTREE (producto)"
+    )
+    .is_ok());
+    let esquema = read_to_string(format!("{}/ordenes.esquema", con_indice)).unwrap();
+    assert_eq!(
+        esquema,
+        "INDEX,idx_cliente,HASH,id_cliente\nINDEX,idx_producto,BTREE,producto\n"
+    );
+    let hash = extremos_del_indice(&con_indice, "ordenes.idx_cliente").0;
+    let arbol = extremos_del_indice(&con_indice, "ordenes.idx_producto").0;
+
+    let query =
+        "INSERT INTO ordenes (id, id_cliente, producto, cantidad) VALUES (200, 3, 'Cable', 1)";
+    assert!(analisar_query(&con_indice, query).is_ok());
+    assert!(analisar_query(&sin_indice, query).is_ok());
+    let longitud = std::fs::metadata(format!("{}/ordenes.csv", con_indice))
+        .unwrap()
+        .len();
+    let marca = format!("#{:020}", longitud);
+    assert_eq!(
+        extremos_del_indice(&con_indice, "ordenes.idx_cliente"),
+        (hash.clone(), marca.clone())
+    );
+    assert_eq!(
+        extremos_del_indice(&con_indice, "ordenes.idx_producto"),
+        (arbol.clone(), marca)
+    );
+
+    for query in [
+        "SELECT id, producto FROM ordenes WHERE id_cliente = 3",
+        "SELECT * FROM ordenes WHERE producto = 'Cable'",
+        "SELECT * FROM ordenes ORDER BY producto",
+        "SELECT * FROM ordenes WHERE producto >= 'B' AND producto < 'D' ORDER BY producto DESC",
+    ] {
+        consultar_en_ambos(&con_indice, &sin_indice, query);
+    }
+
+    // Cuando lo agregado ocupa demasiado el índice se reconstruye, y sigue dando los mismos resultados.
+    for id in 201..230 {
+        let query = format!(
+            "INSERT INTO ordenes (id, id_cliente, producto, cantidad) VALUES ({}, {}, 'Producto_{}', 1)",
+            id,
+            id % 7,
+            id % 11
+        );
+        assert!(analisar_query(&con_indice, &query).is_ok());
+        assert!(analisar_query(&sin_indice, &query).is_ok());
+    }
+    assert_ne!(
+        extremos_del_indice(&con_indice, "ordenes.idx_cliente").0,
+        hash
+    );
+    consultar_en_ambos(
+        &con_indice,
+        &sin_indice,
+        "SELECT id FROM ordenes WHERE id_cliente = 3",
+    );
+    consultar_en_ambos(
+        &con_indice,
+        &sin_indice,
+        "SELECT * FROM ordenes WHERE producto > 'Producto_5' ORDER BY producto",
+    );
+
+    // UPDATE reescribe la tabla y reconstruye los índices, sin filas agregadas al final.
+    for ruta in [&con_indice, &sin_indice] {
+        assert!(
+            analisar_query(ruta, "UPDATE ordenes SET producto = 'Cable' WHERE id = 205").is_ok()
+        );
+    }
+    assert!(!extremos_del_indice(&con_indice, "ordenes.idx_producto")
+        .1
+        .starts_with('#'));
+    consultar_en_ambos(
+        &con_indice,
+        &sin_indice,
+        "SELECT * FROM ordenes WHERE producto = 'Cable'",
+    );
+}
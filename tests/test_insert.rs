@@ -1,3 +1,8 @@
+mod common;
+
+use common::{consultar, DirectorioTemporal};
+use std::fs::read_to_string;
+use taller_tp_individual::errors::ErrorTipo;
 use taller_tp_individual::query_identifier::analisar_query;
 use taller_tp_individual::query_identifier::Insert;
 
@@ -58,10 +63,129 @@ fn test_insert_parsear_query_funciona_correctamente() {
     assert!(resultado.is_ok());
     let valor1 = "111, 6, 'Laptop', 3".to_string();
     let valor2 = "112, 4, 'Cargador', 4".to_string();
-    let mut valores = Vec::new();
-    valores.push(valor1);
-    valores.push(valor2);
+    let valores = vec![valor1, valor2];
     let resultado_esperado = Insert::new("ordenes", "id, id_cliente, producto, cantidad", valores);
 
     assert_eq!(resultado.unwrap(), resultado_esperado);
 }
+
+fn preparar_directorio(nombre_test: &str) -> DirectorioTemporal {
+    common::preparar_directorio(
+        "test_insert",
+        nombre_test,
+        &["ordenes", "ordenes_historico"],
+    )
+}
+
+fn ultima_linea(ruta: &str) -> String {
+    read_to_string(format!("{}/ordenes.csv", ruta))
+        .unwrap()
+        .lines()
+        .last()
+        .unwrap_or("")
+        .to_string()
+}
+
+// Test 36: No se puede insertar una fila con más o menos valores que columnas
+#[test]
+fn test_insert_cantidad_de_valores_incorrecta() {
+    let ruta = preparar_directorio("test36");
+    let query = "INSERT INTO ordenes (id, id_cliente, producto) VALUES (110, 6, 'Laptop', 3);";
+    assert!(analisar_query(&ruta, query).is_err());
+    let query = "INSERT INTO ordenes (id, id_cliente, producto, cantidad) VALUES (110, 6), (111, 6, 'Laptop', 3);";
+    assert!(analisar_query(&ruta, query).is_err());
+    assert_eq!(ultima_linea(&ruta), "109,5,Laptop,1");
+}
+
+// Test 37: Las columnas no indicadas toman su valor por defecto o quedan vacías
+#[test]
+fn test_insert_valores_por_defecto() {
+    let ruta = preparar_directorio("test37");
+    let alter = "ALTER TABLE ordenes ALTER COLUMN cantidad SET DEFAULT 1";
+    assert!(analisar_query(&ruta, alter).is_ok());
+    let alter = "ALTER TABLE ordenes ALTER COLUMN producto SET DEFAULT 'Sin producto'";
+    assert!(analisar_query(&ruta, alter).is_ok());
+
+    let query = "INSERT INTO ordenes (id, producto) VALUES (110, 'Cargador');";
+    assert!(analisar_query(&ruta, query).is_ok());
+    assert_eq!(ultima_linea(&ruta), "110,,Cargador,1");
+
+    let query =
+        "INSERT INTO ordenes (id, id_cliente, producto, cantidad) VALUES (111, 2, DEFAULT, 5);";
+    assert!(analisar_query(&ruta, query).is_ok());
+    assert_eq!(ultima_linea(&ruta), "111,2,Sin producto,5");
+}
+
+// Test 38: Se puede insertar sin lista de columnas y con DEFAULT VALUES
+#[test]
+fn test_insert_sin_columnas_y_default_values() {
+    let ruta = preparar_directorio("test38");
+    let query = "INSERT INTO ordenes VALUES (110, 3, 'Mouse', 2);";
+    assert!(analisar_query(&ruta, query).is_ok());
+    assert_eq!(ultima_linea(&ruta), "110,3,Mouse,2");
+    let query = "INSERT INTO ordenes VALUES (111, 3, 'Mouse');";
+    assert!(analisar_query(&ruta, query).is_err());
+
+    let alter = "ALTER TABLE ordenes ALTER COLUMN id SET AUTO_INCREMENT";
+    assert!(analisar_query(&ruta, alter).is_ok());
+    let alter = "ALTER TABLE ordenes ALTER COLUMN cantidad SET DEFAULT 1";
+    assert!(analisar_query(&ruta, alter).is_ok());
+    assert!(analisar_query(&ruta, "INSERT INTO ordenes DEFAULT VALUES;").is_ok());
+    assert_eq!(ultima_linea(&ruta), "111,,,1");
+}
+
+// Test 39: Se pueden copiar filas de una tabla a otra con INSERT INTO ... SELECT
+#[test]
+fn test_insert_select() {
+    let ruta = preparar_directorio("test39");
+    let query = "INSERT INTO ordenes_historico (id, id_cliente, producto, cantidad) SELECT id, id_cliente, producto, cantidad FROM ordenes WHERE id_cliente = 1 ORDER BY id;";
+    assert!(analisar_query(&ruta, query).is_ok());
+    let historico = read_to_string(format!("{}/ordenes_historico.csv", ruta)).unwrap();
+    assert_eq!(
+        historico,
+        "id,id_cliente,producto,cantidad\n101,1,Laptop,1\n103,1,Monitor,1\n"
+    );
+
+    let query = "INSERT INTO ordenes_historico (producto, id) SELECT producto, id FROM ordenes WHERE producto = 'Mouse'";
+    assert!(analisar_query(&ruta, query).is_ok());
+    let historico = read_to_string(format!("{}/ordenes_historico.csv", ruta)).unwrap();
+    assert!(historico.ends_with("105,,Mouse,\n"));
+}
+
+// Test 40: El SELECT debe devolver tantas columnas como las indicadas en el INSERT
+#[test]
+fn test_insert_select_cantidad_columnas_incorrecta() {
+    let ruta = preparar_directorio("test40");
+    let query = "INSERT INTO ordenes_historico (id, producto) SELECT * FROM ordenes;";
+    assert!(analisar_query(&ruta, query).is_err());
+    let query = "INSERT INTO ordenes_historico SELECT * FROM ordenes WHERE id > 105;";
+    assert!(analisar_query(&ruta, query).is_ok());
+    let historico = read_to_string(format!("{}/ordenes_historico.csv", ruta)).unwrap();
+    assert_eq!(historico.lines().count(), 5);
+}
+
+// Test 104: Un valor con comas no se guarda en la tabla, que no usa comillas, y no se inserta ninguna fila
+#[test]
+fn test_insert_valor_con_comas() {
+    let ruta = preparar_directorio("test104");
+    let anterior = ultima_linea(&ruta);
+
+    let query = "INSERT INTO ordenes VALUES (200, 1, 'Mouse', 2), (201, 1, 'a,b', 2) RETURNING *;";
+    let error = analisar_query(&ruta, query).unwrap_err();
+    assert!(matches!(
+        error.tipo(),
+        ErrorTipo::TypeMismatch { columna: Some(columna), valor } if columna == "producto" && valor == "a,b"
+    ));
+    assert_eq!(ultima_linea(&ruta), anterior);
+    assert_eq!(
+        consultar(&ruta, "SELECT * FROM ordenes WHERE id >= 200"),
+        "id,id_cliente,producto,cantidad\n"
+    );
+
+    let query = "INSERT INTO ordenes VALUES (200, 1, 'a b', 2);";
+    assert!(analisar_query(&ruta, query).is_ok());
+    assert_eq!(
+        consultar(&ruta, "SELECT * FROM ordenes WHERE id = 200"),
+        "id,id_cliente,producto,cantidad\n200,1,a b,2\n"
+    );
+}
@@ -0,0 +1,64 @@
+use std::collections::HashSet;
+use taller_tp_individual::errors::ErrorTipo;
+use taller_tp_individual::mensajes::{configurar_idioma, idioma_actual, Idioma, Mensaje};
+use taller_tp_individual::query_identifier::analisar_query;
+
+// Test 74: Los errores se traducen con el idioma configurado y conservan su código del catálogo
+#[test]
+fn test_mensajes_en_ingles_con_el_mismo_codigo() {
+    let ruta = "tests/test_errores/datos".to_string();
+    assert_eq!(idioma_actual(), Idioma::Espanol);
+    let error = analisar_query(&ruta, "SELECT * FROM clientes WHERE id == 3").unwrap_err();
+    assert!(error
+        .descripcion()
+        .starts_with("El tipo de comparación ingresado no es válido"));
+    assert_eq!(error.codigo(), Some(Mensaje::ComparacionInvalida.codigo()));
+
+    configurar_idioma(Idioma::Ingles);
+    let error = analisar_query(&ruta, "SELECT * FROM clientes WHERE id == 3").unwrap_err();
+    let error_tabla = analisar_query(&ruta, "SELECT * FROM cliente").unwrap_err();
+    // Los textos que acompañan al error, como las alternativas, se traducen al mostrarlo.
+    let diagnostico = format!("{:#}", error);
+    let mensaje_tabla = error_tabla.to_string();
+    configurar_idioma(Idioma::Espanol);
+
+    assert_eq!(error.tipo(), &ErrorTipo::Syntax);
+    assert_eq!(error.codigo(), Some(112));
+    assert_eq!(
+        error.descripcion(),
+        "The comparison is not valid, remember that it can be: =, !=, <, >, <= or >=."
+    );
+    assert!(diagnostico.contains("\n  = expected =, !=, <, >, <= or >="));
+    assert_eq!(error_tabla.codigo(), Some(201));
+    assert_eq!(
+        mensaje_tabla,
+        "Invalid Table: The table cliente does not exist. Did you mean clientes?"
+    );
+}
+
+// Test 75: Cada mensaje tiene un código único y los mismos datos en los dos idiomas
+#[test]
+fn test_mensajes_catalogo_completo() {
+    let mut codigos = HashSet::new();
+    for mensaje in Mensaje::TODOS {
+        assert!(
+            codigos.insert(mensaje.codigo()),
+            "{:?} repite el código",
+            mensaje
+        );
+        let espanol = mensaje.plantilla(Idioma::Espanol);
+        let ingles = mensaje.plantilla(Idioma::Ingles);
+        assert_eq!(
+            espanol.matches("{}").count(),
+            ingles.matches("{}").count(),
+            "{:?}",
+            mensaje
+        );
+    }
+    assert_eq!(
+        Mensaje::CantidadValoresSelect.texto_en(Idioma::Espanol, &["2", "3"]),
+        "El SELECT tiene 2 valores pero se indicaron 3 columnas."
+    );
+    assert_eq!(Idioma::desde_str("en-US").unwrap(), Idioma::Ingles);
+    assert!(Idioma::desde_str("fr").is_err());
+}
@@ -0,0 +1,159 @@
+mod common;
+
+use common::{consultar, DirectorioTemporal};
+use std::error::Error;
+use std::fs::{read_dir, write};
+use std::sync::Mutex;
+use taller_tp_individual::orden_externo::configurar_memoria_orden;
+use taller_tp_individual::query_identifier::analisar_query_con_destino;
+use taller_tp_individual::salida::DestinoFilas;
+use taller_tp_individual::sesion::Sesion;
+
+// Memoria que alcanza para ordenar las tablas de los tests sin pasar a disco.
+const MEMORIA_SUFICIENTE: u64 = 64 * 1024 * 1024;
+// Memoria en la que entran unas pocas filas, así el orden se hace por partes en disco.
+const MEMORIA_CHICA: u64 = 600;
+
+// La memoria configurada es global, así que los tests que la cambian no corren a la vez.
+static MEMORIA: Mutex<()> = Mutex::new(());
+
+fn preparar_directorio(nombre_test: &str) -> DirectorioTemporal {
+    common::preparar_directorio("test_orden_externo", nombre_test, &["ventas"])
+}
+
+fn archivos_del_directorio(ruta: &str) -> Vec<String> {
+    let mut archivos: Vec<String> = read_dir(ruta)
+        .unwrap()
+        .map(|entrada| entrada.unwrap().file_name().to_string_lossy().to_string())
+        .filter(|nombre| !nombre.ends_with(".lock"))
+        .collect();
+    archivos.sort();
+    archivos
+}
+
+// Test 85: El ORDER BY por partes en disco da el mismo resultado que en memoria, con los empates en el orden del archivo
+#[test]
+fn test_orden_externo_mismo_resultado_que_en_memoria() {
+    let _memoria = MEMORIA.lock().unwrap();
+    let ruta = preparar_directorio("test85");
+    let queries = [
+        "SELECT * FROM ventas ORDER BY vendedor",
+        "SELECT id, zona FROM ventas ORDER BY zona DESC, monto",
+        "SELECT vendedor, monto FROM ventas WHERE monto >= 150 ORDER BY monto DESC, vendedor ASC",
+    ];
+
+    configurar_memoria_orden(MEMORIA_SUFICIENTE);
+    let en_memoria: Vec<String> = queries
+        .iter()
+        .map(|query| consultar(&ruta, query))
+        .collect();
+    configurar_memoria_orden(MEMORIA_CHICA);
+    for (query, esperado) in queries.iter().zip(en_memoria.iter()) {
+        assert_eq!(&consultar(&ruta, query), esperado);
+    }
+    // Los valores con barras se leen igual de los archivos temporales.
+    assert!(en_memoria[1].contains("C\\A"));
+    assert_eq!(archivos_del_directorio(&ruta), vec!["ventas.csv"]);
+
+    configurar_memoria_orden(MEMORIA_SUFICIENTE);
+}
+
+// Test 86: Un ORDER BY por partes en disco dentro de una transacción no deja archivos temporales al confirmarla
+#[test]
+fn test_orden_externo_en_transaccion() {
+    let _memoria = MEMORIA.lock().unwrap();
+    let ruta = preparar_directorio("test86");
+
+    configurar_memoria_orden(MEMORIA_CHICA);
+    let mut sesion = Sesion::new(&ruta);
+    assert!(sesion.ejecutar("BEGIN").is_ok());
+    assert!(sesion
+        .ejecutar("UPDATE ventas SET zona = 'Oeste' WHERE zona = ''")
+        .is_ok());
+    assert!(sesion
+        .ejecutar("SELECT * FROM ventas ORDER BY zona, monto DESC")
+        .is_ok());
+    assert!(sesion.ejecutar("COMMIT").is_ok());
+    assert_eq!(archivos_del_directorio(&ruta), vec!["ventas.csv"]);
+
+    let resultado = consultar(&ruta, "SELECT zona FROM ventas ORDER BY zona");
+    let zonas: Vec<&str> = resultado.lines().skip(1).collect();
+    let mut ordenadas = zonas.clone();
+    ordenadas.sort();
+    assert_eq!(zonas, ordenadas);
+    assert!(!zonas.contains(&""));
+
+    configurar_memoria_orden(MEMORIA_SUFICIENTE);
+}
+
+// Test 97: Con muchas partes en disco la mezcla se hace en varias pasadas y da el mismo resultado que en memoria
+#[test]
+fn test_orden_externo_muchas_partes() {
+    let _memoria = MEMORIA.lock().unwrap();
+    let ruta = DirectorioTemporal::new("test_orden_externo", "test97");
+    let mut tabla = String::from("id,grupo,valor\n");
+    for id in 0..1000 {
+        tabla.push_str(&format!(
+            "{},{},{}\n",
+            id,
+            (id * 7919) % 13,
+            (id * 104729) % 97
+        ));
+    }
+    write(format!("{}/numeros.csv", ruta), tabla).expect("No se pudo escribir el archivo");
+    let queries = [
+        "SELECT * FROM numeros ORDER BY grupo",
+        "SELECT id, valor FROM numeros ORDER BY valor DESC, grupo",
+    ];
+
+    configurar_memoria_orden(MEMORIA_SUFICIENTE);
+    let en_memoria: Vec<String> = queries
+        .iter()
+        .map(|query| consultar(&ruta, query))
+        .collect();
+    // Con una memoria en la que no entra ni una fila cada fila es una parte, más que las que se mezclan a la vez.
+    configurar_memoria_orden(1);
+    for (query, esperado) in queries.iter().zip(en_memoria.iter()) {
+        assert_eq!(&consultar(&ruta, query), esperado);
+    }
+    assert_eq!(archivos_del_directorio(&ruta), vec!["numeros.csv"]);
+
+    configurar_memoria_orden(MEMORIA_SUFICIENTE);
+}
+
+// Guarda los archivos que hay en el directorio de datos cuando llega la primera fila, en medio de la mezcla.
+struct ArchivosAlMezclar {
+    ruta: String,
+    archivos: Option<Vec<String>>,
+}
+
+impl DestinoFilas for ArchivosAlMezclar {
+    fn encabezado(&mut self, _columnas: &[String]) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    fn fila(&mut self, _valores: Vec<String>) -> Result<(), Box<dyn Error>> {
+        if self.archivos.is_none() {
+            self.archivos = Some(archivos_del_directorio(&self.ruta));
+        }
+        Ok(())
+    }
+}
+
+// Test 107: Las partes del orden en disco no se guardan en el directorio de datos, así un corte no las deja ahí
+#[test]
+fn test_orden_externo_partes_fuera_de_los_datos() {
+    let _memoria = MEMORIA.lock().unwrap();
+    let ruta = preparar_directorio("test107");
+
+    configurar_memoria_orden(MEMORIA_CHICA);
+    let mut destino = ArchivosAlMezclar {
+        ruta: ruta.to_string(),
+        archivos: None,
+    };
+    let query = "SELECT * FROM ventas ORDER BY vendedor";
+    assert!(analisar_query_con_destino(&ruta, query, &mut destino).is_ok());
+    assert_eq!(destino.archivos, Some(vec!["ventas.csv".to_string()]));
+
+    configurar_memoria_orden(MEMORIA_SUFICIENTE);
+}
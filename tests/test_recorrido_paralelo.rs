@@ -0,0 +1,79 @@
+mod common;
+
+use common::{consultar, DirectorioTemporal};
+use std::fs::read_to_string;
+use std::sync::Mutex;
+use taller_tp_individual::recorrido_paralelo::configurar_recorrido_paralelo;
+
+// Bytes que lee cada hilo por vez, chicos para que las tablas de los tests se repartan en varias tandas.
+const BYTES_POR_HILO: u64 = 256;
+
+// La configuración del recorrido es global, así que los tests que la cambian no corren a la vez.
+static RECORRIDO: Mutex<()> = Mutex::new(());
+
+fn preparar_directorio(nombre_test: &str) -> DirectorioTemporal {
+    common::preparar_directorio(
+        "test_recorrido_paralelo",
+        nombre_test,
+        &["mediciones", "notas"],
+    )
+}
+
+// Ejecuta la query recorriendo la tabla con un hilo y con varios, y verifica que den el mismo resultado.
+fn consultar_con_y_sin_hilos(ruta: &str, query: &str) -> String {
+    configurar_recorrido_paralelo(1, BYTES_POR_HILO);
+    let resultado = consultar(ruta, query);
+    configurar_recorrido_paralelo(4, BYTES_POR_HILO);
+    assert_eq!(consultar(ruta, query), resultado);
+    resultado
+}
+
+// Test 87: El recorrido en varios hilos da las mismas filas que en uno, en el orden del archivo
+#[test]
+fn test_recorrido_paralelo_mismo_resultado() {
+    let _recorrido = RECORRIDO.lock().unwrap_or_else(|error| error.into_inner());
+    let ruta = preparar_directorio("test87");
+
+    configurar_recorrido_paralelo(4, BYTES_POR_HILO);
+    let plan = consultar(
+        &ruta,
+        "EXPLAIN SELECT id FROM mediciones WHERE zona = 'Sur'",
+    );
+    assert!(plan.contains(
+        "  -> Recorrido paralelo (4 hilos) en mediciones [columnas: id, zona] [filtro: zona = 'Sur']"
+    ));
+
+    for query in [
+        "SELECT * FROM mediciones",
+        "SELECT id, valor FROM mediciones WHERE zona = 'Sur' AND valor >= '500'",
+        "SELECT id FROM mediciones WHERE sensor = 'S3' OR zona = ''",
+        "SELECT sensor, valor FROM mediciones WHERE valor < '100' ORDER BY sensor DESC",
+    ] {
+        consultar_con_y_sin_hilos(&ruta, query);
+    }
+    let resultado = consultar_con_y_sin_hilos(
+        &ruta,
+        "SELECT id FROM mediciones WHERE id = 300 OR id = 150 OR id = 1",
+    );
+    assert_eq!(resultado, "id\n1\n150\n300\n");
+
+    configurar_recorrido_paralelo(0, 8 * 1024 * 1024);
+}
+
+// Test 88: Las partes de cada hilo no cortan valores entre comillas con saltos de línea
+#[test]
+fn test_recorrido_paralelo_comillas() {
+    let _recorrido = RECORRIDO.lock().unwrap_or_else(|error| error.into_inner());
+    let ruta = preparar_directorio("test88");
+
+    let resultado = consultar_con_y_sin_hilos(&ruta, "SELECT * FROM notas");
+    // Cada línea del archivo es una fila, como al recorrerlo en un hilo.
+    let tabla = read_to_string(format!("{}/notas.csv", ruta)).unwrap();
+    assert_eq!(resultado.lines().count(), tabla.lines().count());
+    assert_eq!(
+        consultar_con_y_sin_hilos(&ruta, "SELECT id FROM notas WHERE id = 2 OR id = 117"),
+        "id\n2\n117\n"
+    );
+
+    configurar_recorrido_paralelo(0, 8 * 1024 * 1024);
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use common::DirectorioTemporal;
+use std::fs::read_to_string;
+use std::io::Cursor;
+use taller_tp_individual::repl::Repl;
+
+fn preparar_directorio(nombre_test: &str) -> DirectorioTemporal {
+    common::preparar_directorio("test_repl", nombre_test, &["clientes", "ordenes"])
+}
+
+fn ejecutar_repl(ruta: &str, entrada: &str) -> String {
+    let mut salida = Vec::new();
+    Repl::new(ruta, &mut salida)
+        .ejecutar(Cursor::new(entrada))
+        .unwrap();
+    String::from_utf8(salida).unwrap()
+}
+
+// Test 54: Las consultas pueden ocupar varias líneas y un error no termina el intérprete
+#[test]
+fn test_repl_consultas_multilinea_y_errores() {
+    let ruta = preparar_directorio("test54");
+    let entrada = ".mode table\nSELECT id, nombre\n  FROM clientes\n  WHERE id <= 2;\nSELECT * FROM inexistente;\nSELECT id FROM clientes WHERE nombre = 'María';\n";
+    let salida = ejecutar_repl(&ruta, entrada);
+
+    assert!(salida.contains("...> "));
+    assert!(salida.contains("| id | nombre |\n"));
+    assert!(salida.contains("| 1  | Juan   |\n| 2  | Ana    |\n"));
+    assert!(salida.contains("| 4  |\n"));
+}
+
+// Test 55: Comandos .tables, .schema y .mode json
+#[test]
+fn test_repl_comandos() {
+    let ruta = preparar_directorio("test55");
+    let entrada = "ALTER TABLE ordenes ADD FOREIGN KEY (id_cliente) REFERENCES clientes (id) ON DELETE CASCADE;\n.tables\n.schema ordenes\n.mode json\nSELECT id, producto FROM ordenes WHERE id = 101;\n.mode xml\n.quit\nSELECT * FROM clientes;\n";
+    let salida = ejecutar_repl(&ruta, entrada);
+
+    assert!(salida.contains("clientes\nordenes\n"));
+    assert!(salida.contains("ordenes (id, id_cliente, producto, cantidad)\n"));
+    assert!(salida.contains(
+        "ALTER TABLE ordenes ADD FOREIGN KEY (id_cliente) REFERENCES clientes (id) ON DELETE CASCADE ON UPDATE RESTRICT;"
+    ));
+    assert!(salida.contains("[\n  {\"id\": \"101\", \"producto\": \"Laptop\"}\n]\n"));
+    assert!(salida.contains("El formato 'xml' no es válido"));
+    // Después de .quit no se ejecuta nada más.
+    assert!(!salida.contains("juan.perez"));
+}
+
+// Test 56: El historial se guarda en un archivo y se puede volver a ejecutar una consulta
+#[test]
+fn test_repl_historial_y_timer() {
+    let ruta = preparar_directorio("test56");
+    let ruta_historial = format!("{}/historial", ruta);
+
+    let mut salida = Vec::new();
+    Repl::new(&ruta, &mut salida)
+        .con_historial(ruta_historial.clone().into())
+        .ejecutar(Cursor::new(
+            "SELECT nombre FROM clientes WHERE id = 3;\n.timer on\n!1\n.history\n",
+        ))
+        .unwrap();
+    let salida = String::from_utf8(salida).unwrap();
+
+    assert_eq!(salida.matches("Carlos\n").count(), 2);
+    assert!(salida.contains("Tiempo: "));
+    assert!(salida.contains("   1  SELECT nombre FROM clientes WHERE id = 3\n"));
+    assert_eq!(
+        read_to_string(&ruta_historial).unwrap(),
+        "SELECT nombre FROM clientes WHERE id = 3\nSELECT nombre FROM clientes WHERE id = 3\n"
+    );
+}
+
+// Test 105: El intérprete no cambia los espacios de los strings, ni al ejecutar ni al repetir desde el historial
+#[test]
+fn test_repl_conserva_espacios_de_los_strings() {
+    let ruta = preparar_directorio("test105");
+    let ruta_historial = format!("{}/historial", ruta);
+
+    let mut salida = Vec::new();
+    Repl::new(&ruta, &mut salida)
+        .con_historial(ruta_historial.clone().into())
+        .ejecutar(Cursor::new(
+            "INSERT INTO clientes (id, nombre, apellido, email)\n  VALUES (7, 'dos   espacios', 'Cano',\n  'x');\nDELETE FROM clientes WHERE id = 7;\n!1\n",
+        ))
+        .unwrap();
+
+    let clientes = read_to_string(format!("{}/clientes.csv", ruta)).unwrap();
+    assert!(clientes.ends_with("\n7,dos   espacios,Cano,x\n"));
+    assert_eq!(
+        read_to_string(&ruta_historial).unwrap().lines().next(),
+        Some("INSERT INTO clientes (id, nombre, apellido, email) VALUES (7, 'dos   espacios', 'Cano', 'x')")
+    );
+}
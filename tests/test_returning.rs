@@ -0,0 +1,97 @@
+mod common;
+
+use common::DirectorioTemporal;
+use taller_tp_individual::query_identifier::{analisar_query, Delete, Insert, Update};
+use taller_tp_individual::read_file::{aplicar_delete, aplicar_insert, aplicar_update};
+
+fn preparar_directorio(nombre_test: &str) -> DirectorioTemporal {
+    common::preparar_directorio("test_returning", nombre_test, &["ordenes"])
+}
+
+// Test 41: Se parsea la cláusula RETURNING de las queries de escritura
+#[test]
+fn test_returning_parsear_query() {
+    let query = "DELETE FROM ordenes WHERE id > 4 RETURNING *;";
+    let delete = Delete::delete_parsear_query(query).unwrap();
+    assert_eq!(delete.returning, Some(vec!["*".to_string()]));
+
+    let query = "UPDATE ordenes SET cantidad = 2 WHERE id = 101 RETURNING id, cantidad";
+    let update = Update::update_parsear_query(query).unwrap();
+    assert_eq!(update.set, "cantidad = 2");
+    assert_eq!(
+        update.returning,
+        Some(vec!["id".to_string(), "cantidad".to_string()])
+    );
+
+    let query = "INSERT INTO ordenes (id, producto) VALUES (110, 'Mouse') RETURNING id;";
+    let insert = Insert::insert_parsear_query(query).unwrap();
+    assert_eq!(insert.valores, vec!["110, 'Mouse'".to_string()]);
+    assert_eq!(insert.returning, Some(vec!["id".to_string()]));
+
+    let query = "DELETE FROM ordenes WHERE id > 4 RETURNING id, *";
+    assert!(Delete::delete_parsear_query(query).is_err());
+}
+
+// Test 42: Las consultas de escritura devuelven las filas afectadas
+#[test]
+fn test_returning_filas_afectadas() {
+    let ruta = preparar_directorio("test42");
+    let ruta_tabla = format!("{}/ordenes.csv", ruta);
+
+    let delete =
+        Delete::delete_parsear_query("DELETE FROM ordenes WHERE id >= 108 RETURNING *").unwrap();
+    let (columnas, filas) = aplicar_delete(&ruta_tabla, &delete).unwrap();
+    assert_eq!(columnas, vec!["id", "id_cliente", "producto", "cantidad"]);
+    assert_eq!(
+        filas,
+        vec![
+            vec!["108", "4", "Auriculares", "1"],
+            vec!["109", "5", "Laptop", "1"]
+        ]
+    );
+
+    let update =
+        Update::update_parsear_query("UPDATE ordenes SET cantidad = 5 WHERE id = 101 RETURNING *")
+            .unwrap();
+    let (_, filas) = aplicar_update(&ruta_tabla, &update).unwrap();
+    assert_eq!(filas, vec![vec!["101", "1", "Laptop", "5"]]);
+
+    let insert = Insert::insert_parsear_query(
+        "INSERT INTO ordenes (id, producto) VALUES (110, 'Mouse') RETURNING *",
+    )
+    .unwrap();
+    let (_, filas) = aplicar_insert(&ruta_tabla, &insert).unwrap();
+    assert_eq!(filas, vec![vec!["110", "", "Mouse", ""]]);
+}
+
+// Test 43: No se modifica la tabla si RETURNING pide una columna que no existe
+#[test]
+fn test_returning_columna_no_existe() {
+    let ruta = preparar_directorio("test43");
+    let query = "DELETE FROM ordenes WHERE id > 104 RETURNING precio";
+    assert!(analisar_query(&ruta, query).is_err());
+    let query = "SELECT * FROM ordenes WHERE id > 104";
+    assert!(analisar_query(&ruta, query).is_ok());
+    let query = "DELETE FROM ordenes WHERE id > 104 RETURNING id, producto";
+    assert!(analisar_query(&ruta, query).is_ok());
+}
+
+// Test 91: Un RETURNING dentro de un valor entre comillas no se toma como la cláusula
+#[test]
+fn test_returning_entre_comillas() {
+    let query = "INSERT INTO ordenes (id, producto) VALUES (110, 'no RETURNING id')";
+    let insert = Insert::insert_parsear_query(query).unwrap();
+    assert_eq!(insert.valores, vec!["110, 'no RETURNING id'".to_string()]);
+    assert_eq!(insert.returning, None);
+
+    let query =
+        "INSERT INTO ordenes (id, producto) VALUES (110, 'no RETURNING id') RETURNING producto;";
+    let insert = Insert::insert_parsear_query(query).unwrap();
+    assert_eq!(insert.valores, vec!["110, 'no RETURNING id'".to_string()]);
+    assert_eq!(insert.returning, Some(vec!["producto".to_string()]));
+
+    let query = "UPDATE ordenes SET producto = 'Mouse RETURNING *' WHERE id = 101";
+    let update = Update::update_parsear_query(query).unwrap();
+    assert_eq!(update.set, "producto = 'Mouse RETURNING *'");
+    assert_eq!(update.returning, None);
+}
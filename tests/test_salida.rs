@@ -0,0 +1,81 @@
+use taller_tp_individual::salida::{ancho_visible, DestinoFilas, Formato, SalidaFormateada};
+
+fn formatear(formato: Formato, columnas: &[&str], filas: &[&[&str]]) -> String {
+    let mut salida = Vec::new();
+    {
+        let mut destino = SalidaFormateada::new(formato, &mut salida);
+        let columnas: Vec<String> = columnas.iter().map(|c| c.to_string()).collect();
+        destino.encabezado(&columnas).unwrap();
+        for fila in filas {
+            destino
+                .fila(fila.iter().map(|v| v.to_string()).collect())
+                .unwrap();
+        }
+        destino.terminar().unwrap();
+    }
+    String::from_utf8(salida).unwrap()
+}
+
+// Test 59: CSV agrega comillas cuando hace falta y TSV escapa tabs y saltos de línea
+#[test]
+fn test_salida_csv_y_tsv_escapan_valores() {
+    let filas: &[&[&str]] = &[
+        &["1", "López, Ana", "dijo \"hola\""],
+        &["2", "a\tb", "x\ny"],
+    ];
+
+    let csv = formatear(Formato::Csv, &["id", "nombre", "nota"], filas);
+    assert_eq!(
+        csv,
+        "id,nombre,nota\n1,\"López, Ana\",\"dijo \"\"hola\"\"\"\n2,a\tb,\"x\ny\"\n"
+    );
+
+    let tsv = formatear(Formato::Tsv, &["id", "nombre", "nota"], filas);
+    assert_eq!(
+        tsv,
+        "id\tnombre\tnota\n1\tLópez, Ana\tdijo \"hola\"\n2\ta\\tb\tx\\ny\n"
+    );
+}
+
+// Test 60: La tabla alineada usa el ancho visible, con acentos compuestos o descompuestos y caracteres anchos
+#[test]
+fn test_salida_tabla_alinea_con_ancho_unicode() {
+    // "Fernández" con la tilde como marca combinada ocupa lo mismo que con la letra compuesta.
+    let descompuesto = "Ferna\u{0301}ndez";
+    assert_eq!(ancho_visible(descompuesto), 9);
+    assert_eq!(ancho_visible("Fernández"), 9);
+    assert_eq!(ancho_visible("東京"), 4);
+
+    let filas: &[&[&str]] = &[&["1", descompuesto], &["2", "東京"], &["3", ""]];
+    let tabla = formatear(Formato::Tabla, &["id", "apellido"], filas);
+    assert_eq!(
+        tabla,
+        format!(
+            "+----+-----------+\n| id | apellido  |\n+----+-----------+\n| 1  | {} |\n| 2  | 東京      |\n| 3  |           |\n+----+-----------+\n",
+            descompuesto
+        )
+    );
+}
+
+// Test 61: NDJSON escribe un objeto por línea con null para los valores vacíos, Markdown y HTML escapan sus caracteres especiales
+#[test]
+fn test_salida_ndjson_markdown_y_html() {
+    let filas: &[&[&str]] = &[&["1", "a|b <c>"], &["2", ""]];
+
+    let ndjson = formatear(Formato::Ndjson, &["id", "dato"], filas);
+    assert_eq!(
+        ndjson,
+        "{\"id\": \"1\", \"dato\": \"a|b <c>\"}\n{\"id\": \"2\", \"dato\": null}\n"
+    );
+
+    let markdown = formatear(Formato::Markdown, &["id", "dato"], filas);
+    assert_eq!(
+        markdown,
+        "| id | dato |\n| --- | --- |\n| 1 | a\\|b <c> |\n| 2 |  |\n"
+    );
+
+    let html = formatear(Formato::Html, &["id", "dato"], filas);
+    assert!(html.starts_with("<table>\n  <thead>\n    <tr>\n      <th>id</th>\n"));
+    assert!(html.contains("      <td>a|b &lt;c&gt;</td>\n"));
+    assert!(html.ends_with("  </tbody>\n</table>\n"));
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use common::DirectorioTemporal;
+use std::fs::read_to_string;
+use taller_tp_individual::salida::{Formato, SalidaFormateada};
+use taller_tp_individual::script::{ejecutar_script, ResumenScript};
+use taller_tp_individual::sesion::{separar_sentencias_con_lineas, Sesion};
+
+fn preparar_directorio(nombre_test: &str) -> DirectorioTemporal {
+    common::preparar_directorio("test_script", nombre_test, &["clientes", "ordenes"])
+}
+
+fn ejecutar(ruta: &str, script: &str, continuar_con_error: bool) -> (ResumenScript, String) {
+    let mut salida = Vec::new();
+    let resumen = {
+        let mut destino = SalidaFormateada::new(Formato::Csv, &mut salida);
+        ejecutar_script(
+            &mut Sesion::new(ruta),
+            script,
+            continuar_con_error,
+            &mut destino,
+        )
+    };
+    (resumen, String::from_utf8(salida).unwrap())
+}
+
+// Test 57: El script se separa en ';' respetando strings y comentarios, con la línea de cada consulta
+#[test]
+fn test_script_separa_respetando_strings_y_comentarios() {
+    let script = "-- comentario; con punto y coma\nSELECT * FROM clientes WHERE nombre = 'a;b';\n/* bloque;\n   de varias líneas */\nDELETE FROM clientes\n  WHERE id = 1; -- final\n";
+    let sentencias = separar_sentencias_con_lineas(script);
+
+    assert_eq!(sentencias.len(), 2);
+    assert_eq!(sentencias[0].0, 2);
+    assert!(sentencias[0].1.contains("'a;b'"));
+    assert_eq!(sentencias[1].0, 5);
+    assert!(sentencias[1].1.starts_with("DELETE FROM clientes"));
+}
+
+// Test 58: Un error detiene el script salvo que se pida continuar, y el resumen cuenta cada caso
+#[test]
+fn test_script_detener_o_continuar_con_error() {
+    let script = "DELETE FROM clientes WHERE id = 1;\nSELECT * FROM inexistente;\n\nSELECT nombre FROM clientes WHERE id = 2;\n";
+
+    let ruta = preparar_directorio("test58_detener");
+    let (resumen, salida) = ejecutar(&ruta, script, false);
+    assert_eq!((resumen.total, resumen.correctas), (3, 1));
+    assert_eq!(resumen.errores.len(), 1);
+    assert_eq!(resumen.errores[0].linea, 2);
+    assert!(!salida.contains("Ana"));
+    assert_eq!(
+        resumen.to_string(),
+        "3 consultas: 1 correctas, 1 con error, 1 sin ejecutar."
+    );
+    let clientes = read_to_string(format!("{}/clientes.csv", ruta)).unwrap();
+    assert!(!clientes.contains("Juan"));
+
+    let ruta = preparar_directorio("test58_continuar");
+    let (resumen, salida) = ejecutar(&ruta, script, true);
+    assert_eq!((resumen.total, resumen.correctas), (3, 2));
+    assert_eq!(resumen.errores.len(), 1);
+    assert!(salida.contains("nombre\nAna\n"));
+}
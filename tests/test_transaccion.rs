@@ -0,0 +1,198 @@
+mod common;
+
+use common::DirectorioTemporal;
+use std::fs::{read_dir, read_to_string};
+use taller_tp_individual::query_identifier::analisar_query;
+use taller_tp_individual::sesion::{separar_sentencias, Sesion};
+
+fn preparar_directorio(nombre_test: &str) -> DirectorioTemporal {
+    common::preparar_directorio("test_transaccion", nombre_test, &["clientes", "ordenes"])
+}
+
+fn leer_tabla(ruta: &str, tabla: &str) -> String {
+    read_to_string(format!("{}/{}.csv", ruta, tabla)).unwrap()
+}
+
+fn quedan_archivos_de_la_transaccion(ruta: &str) -> bool {
+    read_dir(ruta).unwrap().any(|e| {
+        let nombre = e.unwrap().file_name().to_string_lossy().to_string();
+        nombre.starts_with(".transaccion") || nombre.starts_with(".bitacora")
+    })
+}
+
+// Test 46: Los cambios de una transacción se ven dentro de ella y se publican juntos con COMMIT
+#[test]
+fn test_transaccion_commit() {
+    let ruta = preparar_directorio("test46");
+    let ordenes_original = leer_tabla(&ruta, "ordenes");
+    let mut sesion = Sesion::new(&ruta);
+
+    assert!(sesion.ejecutar("BEGIN").is_ok());
+    assert!(sesion
+        .ejecutar("UPDATE clientes SET email = 'juan@nuevo.com' WHERE id = 1")
+        .is_ok());
+    assert!(sesion
+        .ejecutar(
+            "INSERT INTO ordenes (id, id_cliente, producto, cantidad) VALUES (110, 1, 'Mouse', 3)"
+        )
+        .is_ok());
+    assert!(sesion
+        .ejecutar("DELETE FROM ordenes WHERE id = 101")
+        .is_ok());
+    assert_eq!(leer_tabla(&ruta, "ordenes"), ordenes_original);
+    assert!(!leer_tabla(&ruta, "clientes").contains("juan@nuevo.com"));
+
+    assert!(sesion.ejecutar("COMMIT;").is_ok());
+    assert!(leer_tabla(&ruta, "clientes").contains("1,Juan,Pérez,juan@nuevo.com"));
+    let ordenes = leer_tabla(&ruta, "ordenes");
+    assert!(ordenes.contains("110,1,Mouse,3"));
+    assert!(!ordenes.contains("101,1,Laptop,1"));
+    assert!(!quedan_archivos_de_la_transaccion(&ruta));
+}
+
+// Test 47: ROLLBACK, o una sesión que termina sin COMMIT, descartan todos los cambios
+#[test]
+fn test_transaccion_rollback() {
+    let ruta = preparar_directorio("test47");
+    let clientes_original = leer_tabla(&ruta, "clientes");
+    let ordenes_original = leer_tabla(&ruta, "ordenes");
+
+    let mut sesion = Sesion::new(&ruta);
+    assert!(sesion.ejecutar("BEGIN TRANSACTION").is_ok());
+    assert!(sesion.ejecutar("BEGIN").is_err());
+    assert!(sesion.ejecutar("DELETE FROM clientes WHERE id = 2").is_ok());
+    assert!(sesion.ejecutar("ROLLBACK").is_ok());
+    assert!(sesion.ejecutar("ROLLBACK").is_err());
+    assert_eq!(leer_tabla(&ruta, "clientes"), clientes_original);
+
+    let mut sesion = Sesion::new(&ruta);
+    assert!(sesion.ejecutar("BEGIN").is_ok());
+    assert!(sesion
+        .ejecutar("UPDATE ordenes SET cantidad = 9 WHERE id_cliente = 4")
+        .is_ok());
+    drop(sesion);
+    assert_eq!(leer_tabla(&ruta, "ordenes"), ordenes_original);
+    assert!(!quedan_archivos_de_la_transaccion(&ruta));
+}
+
+// Test 48: Si una consulta falla en medio de un script, ninguna tabla queda modificada a medias
+#[test]
+fn test_transaccion_script_con_error() {
+    let ruta = preparar_directorio("test48");
+    let clientes_original = leer_tabla(&ruta, "clientes");
+
+    let script = "BEGIN; UPDATE clientes SET nombre = 'Juana; Ana' WHERE id = 2; UPDATE ordenes SET inexistente = 1; COMMIT;";
+    let sentencias = separar_sentencias(script);
+    assert_eq!(sentencias.len(), 4);
+    assert_eq!(
+        sentencias[1],
+        "UPDATE clientes SET nombre = 'Juana; Ana' WHERE id = 2"
+    );
+
+    let mut sesion = Sesion::new(&ruta);
+    let resultado: Result<(), _> = sentencias.iter().try_for_each(|s| sesion.ejecutar(s));
+    assert!(resultado.is_err());
+    drop(sesion);
+    assert_eq!(leer_tabla(&ruta, "clientes"), clientes_original);
+}
+
+// Test 93: El COMMIT falla y descarta la transacción si otro proceso modificó una tabla que ella cambió
+#[test]
+fn test_transaccion_conflicto_con_otra_escritura() {
+    let ruta = preparar_directorio("test93");
+    let mut primera = Sesion::new(&ruta);
+    let mut segunda = Sesion::new(&ruta);
+
+    assert!(primera.ejecutar("BEGIN").is_ok());
+    assert!(segunda.ejecutar("BEGIN").is_ok());
+    assert!(primera
+        .ejecutar("UPDATE clientes SET nombre = 'Juana' WHERE id = 2")
+        .is_ok());
+    assert!(segunda
+        .ejecutar("UPDATE clientes SET nombre = 'Anita' WHERE id = 2")
+        .is_ok());
+    // La segunda también cambia una tabla que nadie más modifica, que tampoco se publica.
+    assert!(segunda
+        .ejecutar("DELETE FROM ordenes WHERE id = 101")
+        .is_ok());
+    assert!(primera.ejecutar("COMMIT").is_ok());
+    let error = segunda.ejecutar("COMMIT").unwrap_err();
+    assert!(error.to_string().starts_with("Invalid Lock"));
+    assert!(segunda.ejecutar("COMMIT").is_err());
+
+    let clientes = leer_tabla(&ruta, "clientes");
+    assert!(clientes.contains("2,Juana,López"));
+    assert!(!clientes.contains("Anita"));
+    assert!(leer_tabla(&ruta, "ordenes").contains("101,1,Laptop,1"));
+    assert!(!quedan_archivos_de_la_transaccion(&ruta));
+
+    // Una fila agregada al final por otra consulta también es un conflicto.
+    assert!(primera.ejecutar("BEGIN").is_ok());
+    assert!(primera
+        .ejecutar("DELETE FROM ordenes WHERE id = 103")
+        .is_ok());
+    let insert =
+        "INSERT INTO ordenes (id, id_cliente, producto, cantidad) VALUES (120, 2, 'Mouse', 1)";
+    assert!(analisar_query(&ruta, insert).is_ok());
+    assert!(primera.ejecutar("COMMIT").is_err());
+    let ordenes = leer_tabla(&ruta, "ordenes");
+    assert!(ordenes.contains("103,1,Monitor,1"));
+    assert!(ordenes.contains("120,2,Mouse,1"));
+}
+
+// Test 94: Los valores de auto increment de una transacción se reservan junto a los de las otras sesiones
+#[test]
+fn test_transaccion_auto_increment_compartido() {
+    let ruta = preparar_directorio("test94");
+    let alter = "ALTER TABLE ordenes ALTER COLUMN id SET AUTO_INCREMENT";
+    assert!(analisar_query(&ruta, alter).is_ok());
+    let ultimo_id = |ruta: &str| {
+        let ordenes = read_to_string(format!("{}/ordenes.csv", ruta)).unwrap();
+        let ultima = ordenes.lines().last().unwrap_or_default().to_string();
+        ultima.split(',').next().unwrap_or_default().to_string()
+    };
+
+    let mut sesion = Sesion::new(&ruta);
+    assert!(sesion.ejecutar("BEGIN").is_ok());
+    let insert = "INSERT INTO ordenes (id_cliente, producto, cantidad) VALUES (1, 'Mouse', 1)";
+    assert!(sesion.ejecutar(insert).is_ok());
+    let ruta_sombra = read_dir(&ruta)
+        .unwrap()
+        .map(|entrada| entrada.unwrap().path().to_string_lossy().to_string())
+        .find(|ruta| ruta.contains("/.transaccion"))
+        .unwrap();
+    let id_transaccion = ultimo_id(&ruta_sombra);
+    let insert = "INSERT INTO ordenes (id_cliente, producto, cantidad) VALUES (2, 'Teclado', 1)";
+    assert!(analisar_query(&ruta, insert).is_ok());
+    let id_afuera = ultimo_id(&ruta);
+    assert_ne!(id_afuera, id_transaccion);
+    assert!(sesion.ejecutar("ROLLBACK").is_ok());
+
+    // El esquema que cambia la transacción se publica sin volver atrás el contador.
+    assert!(sesion.ejecutar("BEGIN").is_ok());
+    let indice = "CREATE INDEX idx_cliente ON ordenes (id_cliente)";
+    assert!(sesion.ejecutar(indice).is_ok());
+    let insert = "INSERT INTO clientes (id, nombre, apellido, email) VALUES (9, 'Eva', 'Sosa', 'eva@email.com')";
+    assert!(analisar_query(&ruta, insert).is_ok());
+    let insert = "INSERT INTO ordenes (id_cliente, producto, cantidad) VALUES (9, 'Cable', 2)";
+    assert!(analisar_query(&ruta, insert).is_ok());
+    let id_afuera = ultimo_id(&ruta);
+    assert!(sesion.ejecutar("COMMIT").is_err());
+
+    assert!(sesion.ejecutar("BEGIN").is_ok());
+    assert!(sesion.ejecutar(indice).is_ok());
+    let insert = "INSERT INTO ordenes (id_cliente, producto, cantidad) VALUES (3, 'Monitor', 1)";
+    assert!(sesion.ejecutar(insert).is_ok());
+    assert!(sesion.ejecutar("COMMIT").is_ok());
+    let insert = "INSERT INTO ordenes (id_cliente, producto, cantidad) VALUES (4, 'Parlante', 1)";
+    assert!(analisar_query(&ruta, insert).is_ok());
+    let ids: Vec<String> = leer_tabla(&ruta, "ordenes")
+        .lines()
+        .skip(1)
+        .map(|linea| linea.split(',').next().unwrap_or_default().to_string())
+        .collect();
+    let ids_unicos: std::collections::HashSet<&String> = ids.iter().collect();
+    assert_eq!(ids.len(), ids_unicos.len());
+    assert!(ids.contains(&id_afuera));
+    assert!(!quedan_archivos_de_la_transaccion(&ruta));
+}
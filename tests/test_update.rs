@@ -1,4 +1,7 @@
-use std::fs::{copy, create_dir_all, remove_dir_all};
+mod common;
+
+use common::DirectorioTemporal;
+use std::fs::{copy, create_dir_all, read_dir, read_to_string, remove_dir_all};
 use std::vec;
 use taller_tp_individual::query_identifier::analisar_query;
 use taller_tp_individual::query_identifier::Comparacion;
@@ -21,8 +24,7 @@ fn test_update_funciona_correctamente() {
     let operaciones = None;
     let set = "nombre = 'Pedro'";
     let update = Update::new("clientes_test_update3", set, where_clauses, operaciones);
-    let resultado =
-        crear_escribir_archivo_temporal_update(&ruta, &update, &"datos/temporal5.csv".to_string());
+    let resultado = crear_escribir_archivo_temporal_update(&ruta, &update);
     println!("{:?}", resultado);
     assert!(resultado.is_ok());
 }
@@ -76,8 +78,7 @@ fn test_update_muchas_where_clauses() {
     let operaciones = Some(vec![LogicalOperators::And]);
     let set = "nombre = 'Pedro', apellido = 'Cano', id = 5";
     let update = Update::new("clientes_test_update3", set, where_clauses, operaciones);
-    let resultado =
-        crear_escribir_archivo_temporal_update(&ruta, &update, &"datos/temporal4.csv".to_string());
+    let resultado = crear_escribir_archivo_temporal_update(&ruta, &update);
     println!("{:?}", resultado);
     assert!(resultado.is_ok());
 }
@@ -140,7 +141,66 @@ fn test_update_no_hay_where_clause() {
     let ruta = "tests/test_update/datos/clientes_test_update7.csv".to_string();
     let set = "nombre = 'Pedro'";
     let update = Update::new("clientes_test_update7", set, None, None);
-    let resultado =
-        crear_escribir_archivo_temporal_update(&ruta, &update, &"datos/temporal6.csv".to_string());
+    let resultado = crear_escribir_archivo_temporal_update(&ruta, &update);
     assert!(resultado.is_ok());
 }
+
+fn archivos_del_directorio(ruta: &str) -> Vec<String> {
+    let mut archivos: Vec<String> = read_dir(ruta)
+        .expect("No se pudo leer el directorio")
+        .map(|entrada| entrada.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    archivos.sort();
+    archivos
+}
+
+// Test 44: Un error en la SET clause no deja archivos temporales en el directorio de la tabla
+#[test]
+fn test_update_error_no_deja_temporales() {
+    let ruta = DirectorioTemporal::new("test_update", "test44");
+    ruta.copiar(
+        "tests/test_update/datos/clientes_test_update_copia.csv",
+        "clientes.csv",
+    );
+
+    let query = "UPDATE clientes SET segundo_nombre = 'Pedro' WHERE id = 1";
+    assert!(analisar_query(&ruta, query).is_err());
+    assert_eq!(
+        archivos_del_directorio(&ruta),
+        vec![".clientes.lock", "clientes.csv"]
+    );
+
+    let query = "UPDATE clientes SET nombre = 'Pedro' WHERE id = 1";
+    assert!(analisar_query(&ruta, query).is_ok());
+    assert_eq!(
+        archivos_del_directorio(&ruta),
+        vec![".clientes.lock", "clientes.csv"]
+    );
+    let clientes = read_to_string(format!("{}/clientes.csv", ruta)).unwrap();
+    assert!(clientes.contains("1,Pedro,Pérez"));
+}
+
+// Test 101: Un UPDATE con OR modifica las filas que cumplen alguna de las conjunciones del WHERE
+#[test]
+fn test_update_con_or() {
+    let ruta = DirectorioTemporal::new("test_update", "test101");
+    ruta.copiar(
+        "tests/test_update/datos/clientes_test_update_copia.csv",
+        "clientes.csv",
+    );
+
+    let query = "UPDATE clientes SET apellido = 'Sosa' WHERE nombre = 'Ana' OR id = 6 AND apellido = 'Pérez' OR id = 1";
+    assert!(analisar_query(&ruta, query).is_ok());
+    let clientes = read_to_string(format!("{}/clientes.csv", ruta)).unwrap();
+    let actualizados: Vec<&str> = clientes
+        .lines()
+        .filter(|linea| linea.contains(",Sosa,"))
+        .collect();
+    assert_eq!(
+        actualizados,
+        vec![
+            "1,Juan,Sosa,juan.perez@email.com",
+            "2,Ana,Sosa,ana.lopez@email.com"
+        ]
+    );
+}